@@ -0,0 +1,460 @@
+//! Funciones de Bessel J, Y, I, K de orden real y argumento real, y funciones de Airy.
+//!
+//! Para x moderado se usa el método de Temme/Steed: fracción continua CF1 para J'/J
+//! (o I'/I), recurrencia descendente hasta |μ| ≤ ½, y la serie de Temme (x < 2) o la
+//! fracción continua compleja CF2 (x ≥ 2) para fijar la normalización. Para x grande
+//! frente al orden, la expansión asintótica de Hankel.
+
+use super::special::{cos_pi, sin_pi};
+use std::f64::consts::{FRAC_2_PI, PI};
+
+const EPS: f64 = f64::EPSILON;
+const FPMIN: f64 = f64::MIN_POSITIVE / f64::EPSILON;
+const MAX_ITER: usize = 1_000_000;
+/// Por encima de este x, I_ν desborda y K_ν se anula en f64.
+const IK_XMAX: f64 = 750.0;
+
+/// Coeficientes de Taylor de 1/Γ(1+μ) (para γ1, γ2 de Temme con |μ| ≤ ½).
+const RGAM: [f64; 23] = [
+    1.0,
+    5.772_156_649_015_329e-1,
+    -6.558_780_715_202_539e-1,
+    -4.200_263_503_409_524e-2,
+    1.665_386_113_822_915e-1,
+    -4.219_773_455_554_433e-2,
+    -9.621_971_527_876_973e-3,
+    7.218_943_246_663_1e-3,
+    -1.165_167_591_859_065e-3,
+    -2.152_416_741_149_51e-4,
+    1.280_502_823_881_162e-4,
+    -2.013_485_478_078_824e-5,
+    -1.250_493_482_142_671e-6,
+    1.133_027_231_981_696e-6,
+    -2.056_338_416_977_607e-7,
+    6.116_095_104_481_416e-9,
+    5.002_007_644_469_223e-9,
+    -1.181_274_570_487_02e-9,
+    1.043_426_711_691_1e-10,
+    7.782_263_439_905_071e-12,
+    -3.696_805_618_642_206e-12,
+    5.100_370_287_454_476e-13,
+    -2.058_326_053_566_507e-14,
+];
+
+/// (γ1, γ2) de Temme: γ1 = (1/Γ(1−μ) − 1/Γ(1+μ))/(2μ), γ2 = (1/Γ(1−μ) + 1/Γ(1+μ))/2.
+fn temme_gammas(mu: f64) -> (f64, f64) {
+    // γ1 = −Σ d_(2i+1) μ^(2i), γ2 = Σ d_(2i) μ^(2i), con d_j los coeficientes de RGAM
+    let mu2 = mu * mu;
+    let (mut g1, mut g2, mut p) = (0.0, 0.0, 1.0);
+    for pair in RGAM.chunks(2) {
+        g2 += pair[0] * p;
+        if let Some(d) = pair.get(1) {
+            g1 -= d * p;
+        }
+        p *= mu2;
+    }
+    (g1, g2)
+}
+
+/// Expansión de Hankel: (J_ν(x), Y_ν(x)) para x grande frente a ν².
+fn hankel_jy(nu: f64, x: f64) -> (f64, f64) {
+    let mu = 4.0 * nu * nu;
+    let (mut p, mut q) = (1.0, 0.0);
+    let mut t = 1.0f64;
+    for k in 1..200 {
+        let kf = k as f64;
+        let next = t * (mu - (2.0 * kf - 1.0).powi(2)) / (8.0 * kf * x);
+        if next.abs() > t.abs() && k > 2 {
+            break;
+        }
+        t = next;
+        let sign = if (k / 2) % 2 == 0 { 1.0 } else { -1.0 };
+        if k % 2 == 0 { p += sign * t } else { q += sign * t }
+        if t.abs() < EPS * (p.abs() + q.abs()) {
+            break;
+        }
+    }
+    // χ = x − (ν/2 + ¼)π, sin/cos de x sin restar (reducción exacta de la libm)
+    let phi = (0.5 * nu + 0.25).rem_euclid(2.0);
+    let (sx, cx) = x.sin_cos();
+    let (sp, cp) = (sin_pi(phi), cos_pi(phi));
+    let (s, c) = (sx * cp - cx * sp, cx * cp + sx * sp);
+    let norm = (FRAC_2_PI / x).sqrt();
+    (norm * (p * c - q * s), norm * (p * s + q * c))
+}
+
+fn use_hankel(nu: f64, x: f64) -> bool {
+    x >= 25.0 && x >= 2.0 * nu * nu
+}
+
+/// (J_ν(x), Y_ν(x)) para ν ≥ 0 y x > 0 (Temme/Steed, Numerical Recipes `besseljy`).
+fn jy_positive(nu: f64, x: f64) -> (f64, f64) {
+    if use_hankel(nu, x) {
+        return hankel_jy(nu, x);
+    }
+    let nl = if x < 2.0 { (nu + 0.5) as usize } else { (nu - x + 1.5).max(0.0) as usize };
+    let xmu = nu - nl as f64;
+    let xmu2 = xmu * xmu;
+    let xi = 1.0 / x;
+    let xi2 = 2.0 * xi;
+    let w = xi2 / PI;
+
+    // CF1: J'_ν/J_ν
+    let mut isign = 1.0;
+    let mut h = (nu * xi).max(FPMIN);
+    let mut b = xi2 * nu;
+    let mut d = 0.0;
+    let mut c = h;
+    for _ in 0..MAX_ITER {
+        b += xi2;
+        d = b - d;
+        if d.abs() < FPMIN {
+            d = FPMIN;
+        }
+        c = b - 1.0 / c;
+        if c.abs() < FPMIN {
+            c = FPMIN;
+        }
+        d = 1.0 / d;
+        let del = c * d;
+        h *= del;
+        if d < 0.0 {
+            isign = -isign;
+        }
+        if (del - 1.0).abs() <= EPS {
+            break;
+        }
+    }
+
+    // Recurrencia descendente hasta el orden μ
+    let mut rjl = isign * FPMIN;
+    let mut rjpl = h * rjl;
+    let rjl1 = rjl;
+    let mut fact = nu * xi;
+    for _ in 0..nl {
+        let rjtemp = fact * rjl + rjpl;
+        fact -= xi;
+        rjpl = fact * rjtemp - rjl;
+        rjl = rjtemp;
+    }
+    if rjl == 0.0 {
+        rjl = EPS;
+    }
+    let f = rjpl / rjl;
+
+    let (rjmu, mut rymu, mut ry1);
+    if x < 2.0 {
+        // Serie de Temme para Y_μ, Y_(μ+1)
+        let x2 = 0.5 * x;
+        let pimu = PI * xmu;
+        let fact = if pimu.abs() < EPS { 1.0 } else { pimu / pimu.sin() };
+        let d = -x2.ln();
+        let e = xmu * d;
+        let fact2 = if e.abs() < EPS { 1.0 } else { e.sinh() / e };
+        let (gam1, gam2) = temme_gammas(xmu);
+        let gampl = gam2 - xmu * gam1;
+        let gammi = gam2 + xmu * gam1;
+        let mut ff = 2.0 / PI * fact * (gam1 * e.cosh() + gam2 * fact2 * d);
+        let e = e.exp();
+        let mut p = e / (gampl * PI);
+        let mut q = 1.0 / (e * PI * gammi);
+        let pimu2 = 0.5 * pimu;
+        let fact3 = if pimu2.abs() < EPS { 1.0 } else { pimu2.sin() / pimu2 };
+        let r = PI * pimu2 * fact3 * fact3;
+        let mut c = 1.0;
+        let d = -x2 * x2;
+        let mut sum = ff + r * q;
+        let mut sum1 = p;
+        for i in 1..MAX_ITER {
+            let i = i as f64;
+            ff = (i * ff + p + q) / (i * i - xmu2);
+            c *= d / i;
+            p /= i - xmu;
+            q /= i + xmu;
+            let del = c * (ff + r * q);
+            sum += del;
+            sum1 += c * p - i * del;
+            if del.abs() < (1.0 + sum.abs()) * EPS {
+                break;
+            }
+        }
+        rymu = -sum;
+        ry1 = -sum1 * xi2;
+        let rymup = xmu * xi * rymu - ry1;
+        rjmu = w / (rymup - f * rymu);
+    } else {
+        // CF2 de Steed: p + iq = (J' + iY')/(J + iY)
+        let mut a = 0.25 - xmu2;
+        let mut p = -0.5 * xi;
+        let mut q = 1.0;
+        let br = 2.0 * x;
+        let mut bi = 2.0;
+        let mut fact = a * xi / (p * p + q * q);
+        let mut cr = br + q * fact;
+        let mut ci = bi + p * fact;
+        let mut den = br * br + bi * bi;
+        let mut dr = br / den;
+        let mut di = -bi / den;
+        let mut dlr = cr * dr - ci * di;
+        let mut dli = cr * di + ci * dr;
+        let mut temp = p * dlr - q * dli;
+        q = p * dli + q * dlr;
+        p = temp;
+        for i in 1..MAX_ITER {
+            a += (2 * i) as f64;
+            bi += 2.0;
+            dr = a * dr + br;
+            di = a * di + bi;
+            if dr.abs() + di.abs() < FPMIN {
+                dr = FPMIN;
+            }
+            fact = a / (cr * cr + ci * ci);
+            cr = br + cr * fact;
+            ci = bi - ci * fact;
+            if cr.abs() + ci.abs() < FPMIN {
+                cr = FPMIN;
+            }
+            den = dr * dr + di * di;
+            dr /= den;
+            di /= -den;
+            dlr = cr * dr - ci * di;
+            dli = cr * di + ci * dr;
+            temp = p * dlr - q * dli;
+            q = p * dli + q * dlr;
+            p = temp;
+            if (dlr - 1.0).abs() + dli.abs() <= EPS {
+                break;
+            }
+        }
+        let gam = (p - f) / q;
+        rjmu = (w / ((p - f) * gam + q)).sqrt().copysign(rjl);
+        rymu = rjmu * gam;
+        let rymup = rymu * (p + q / gam);
+        ry1 = xmu * xi * rymu - rymup;
+    }
+
+    let j = rjl1 * (rjmu / rjl);
+    // Recurrencia ascendente (estable) para Y
+    for i in 1..=nl {
+        let rytemp = (xmu + i as f64) * xi2 * ry1 - rymu;
+        rymu = ry1;
+        ry1 = rytemp;
+    }
+    (j, rymu)
+}
+
+/// (I_ν(x), K_ν(x)) para ν ≥ 0 y x > 0 (Temme/Steed, Numerical Recipes `besselik`).
+fn ik_positive(nu: f64, x: f64) -> (f64, f64) {
+    if x > IK_XMAX {
+        return (f64::INFINITY, 0.0);
+    }
+    let nl = (nu + 0.5) as usize;
+    let xmu = nu - nl as f64;
+    let xmu2 = xmu * xmu;
+    let xi = 1.0 / x;
+    let xi2 = 2.0 * xi;
+
+    // CF1: I'_ν/I_ν
+    let mut h = (nu * xi).max(FPMIN);
+    let mut b = xi2 * nu;
+    let mut d = 0.0;
+    let mut c = h;
+    for _ in 0..MAX_ITER {
+        b += xi2;
+        d = 1.0 / (b + d);
+        c = b + 1.0 / c;
+        let del = c * d;
+        h *= del;
+        if (del - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    let mut ril = FPMIN;
+    let mut ripl = h * ril;
+    let ril1 = ril;
+    let mut fact = nu * xi;
+    for _ in 0..nl {
+        let ritemp = fact * ril + ripl;
+        fact -= xi;
+        ripl = fact * ritemp + ril;
+        ril = ritemp;
+    }
+    let f = ripl / ril;
+
+    let (mut rkmu, mut rk1);
+    if x < 2.0 {
+        let x2 = 0.5 * x;
+        let pimu = PI * xmu;
+        let fact = if pimu.abs() < EPS { 1.0 } else { pimu / pimu.sin() };
+        let d = -x2.ln();
+        let e = xmu * d;
+        let fact2 = if e.abs() < EPS { 1.0 } else { e.sinh() / e };
+        let (gam1, gam2) = temme_gammas(xmu);
+        let gampl = gam2 - xmu * gam1;
+        let gammi = gam2 + xmu * gam1;
+        let mut ff = fact * (gam1 * e.cosh() + gam2 * fact2 * d);
+        let mut sum = ff;
+        let e = e.exp();
+        let mut p = 0.5 * e / gampl;
+        let mut q = 0.5 / (e * gammi);
+        let mut c = 1.0;
+        let d = x2 * x2;
+        let mut sum1 = p;
+        for i in 1..MAX_ITER {
+            let i = i as f64;
+            ff = (i * ff + p + q) / (i * i - xmu2);
+            c *= d / i;
+            p /= i - xmu;
+            q /= i + xmu;
+            let del = c * ff;
+            sum += del;
+            sum1 += c * (p - i * ff);
+            if del.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        rkmu = sum;
+        rk1 = sum1 * xi2;
+    } else {
+        // CF2 de Steed para K
+        let mut b = 2.0 * (1.0 + x);
+        let mut d = 1.0 / b;
+        let mut h = d;
+        let mut delh = d;
+        let mut q1 = 0.0;
+        let mut q2 = 1.0;
+        let a1 = 0.25 - xmu2;
+        let mut q = a1;
+        let mut c = a1;
+        let mut a = -a1;
+        let mut s = 1.0 + q * delh;
+        for i in 1..MAX_ITER {
+            a -= (2 * i) as f64;
+            c = -a * c / (i as f64 + 1.0);
+            let qnew = (q1 - b * q2) / a;
+            q1 = q2;
+            q2 = qnew;
+            q += c * qnew;
+            b += 2.0;
+            d = 1.0 / (b + a * d);
+            delh *= b * d - 1.0;
+            h += delh;
+            let dels = q * delh;
+            s += dels;
+            if (dels / s).abs() < EPS {
+                break;
+            }
+        }
+        h *= a1;
+        rkmu = (PI / (2.0 * x)).sqrt() * (-x).exp() / s;
+        rk1 = rkmu * (xmu + x + 0.5 - h) * xi;
+    }
+    let rkmup = xmu * xi * rkmu - rk1;
+    let rimu = xi / (f * rkmu - rkmup);
+    let i = rimu * ril1 / ril;
+    for k in 1..=nl {
+        let rktemp = (xmu + k as f64) * xi2 * rk1 + rkmu;
+        rkmu = rk1;
+        rk1 = rktemp;
+    }
+    (i, rkmu)
+}
+
+fn is_int(v: f64) -> bool {
+    v.fract() == 0.0
+}
+
+/// Signo (−1)^n para n entero.
+fn parity(n: f64) -> f64 {
+    if (n / 2.0).fract() == 0.0 { 1.0 } else { -1.0 }
+}
+
+/// Bessel de primera y segunda especie, J_ν(x) ('j') o Y_ν(x) ('y'), para ν y x reales.
+pub(crate) fn bessel_jy(kind: char, nu: f64, x: f64) -> Result<f64, String> {
+    if x < 0.0 {
+        // Solo J_n de orden entero es real para x < 0: J_n(−x) = (−1)^n J_n(x)
+        if kind == 'y' {
+            return Err("bessely requiere x > 0".to_string());
+        }
+        if !is_int(nu) {
+            return Err("besselj con x < 0 requiere orden entero".to_string());
+        }
+        return Ok(parity(nu) * bessel_jy(kind, nu, -x)?);
+    }
+    if x == 0.0 {
+        return match kind {
+            'y' => Err("bessely tiene una singularidad en x = 0".to_string()),
+            _ if nu == 0.0 => Ok(1.0),
+            _ if nu > 0.0 || is_int(nu) => Ok(0.0),
+            _ => Err("besselj de orden negativo no entero diverge en x = 0".to_string()),
+        };
+    }
+    let (j, y) = jy_positive(nu.abs(), x);
+    if nu >= 0.0 {
+        return Ok(if kind == 'j' { j } else { y });
+    }
+    // Orden negativo: J_(−ν) = cos(νπ)J_ν − sin(νπ)Y_ν, Y_(−ν) = sin(νπ)J_ν + cos(νπ)Y_ν
+    let (s, c) = (sin_pi(-nu), cos_pi(-nu));
+    Ok(if kind == 'j' { c * j - s * y } else { s * j + c * y })
+}
+
+/// Bessel modificadas, I_ν(x) ('i') o K_ν(x) ('k'), para ν y x reales.
+pub(crate) fn bessel_ik(kind: char, nu: f64, x: f64) -> Result<f64, String> {
+    if x < 0.0 {
+        if kind == 'k' {
+            return Err("besselk requiere x > 0".to_string());
+        }
+        if !is_int(nu) {
+            return Err("besseli con x < 0 requiere orden entero".to_string());
+        }
+        return Ok(parity(nu) * bessel_ik(kind, nu, -x)?);
+    }
+    if x == 0.0 {
+        return match kind {
+            'k' => Err("besselk tiene una singularidad en x = 0".to_string()),
+            _ if nu == 0.0 => Ok(1.0),
+            _ if nu > 0.0 || is_int(nu) => Ok(0.0),
+            _ => Err("besseli de orden negativo no entero diverge en x = 0".to_string()),
+        };
+    }
+    let (i, k) = ik_positive(nu.abs(), x);
+    if kind == 'k' {
+        return Ok(k);
+    }
+    // I_(−ν) = I_ν + (2/π) sin(νπ) K_ν
+    Ok(if nu >= 0.0 { i } else { i + FRAC_2_PI * sin_pi(-nu) * k })
+}
+
+/// Airy: k = 0 → Ai, 1 → Ai', 2 → Bi, 3 → Bi' (convenio de `airy(k, x)` de MATLAB).
+pub(crate) fn airy(k: u8, x: f64) -> f64 {
+    const AI0: f64 = 0.355_028_053_887_817_2;
+    const AIP0: f64 = -0.258_819_403_792_806_8;
+    const BI0: f64 = 0.614_926_627_446_000_7;
+    const BIP0: f64 = 0.448_288_357_353_826_4;
+    let sqrt3 = 3f64.sqrt();
+    if x == 0.0 {
+        return [AI0, AIP0, BI0, BIP0][k as usize];
+    }
+    let ax = x.abs();
+    let zeta = 2.0 / 3.0 * ax * ax.sqrt();
+    let order = if k.is_multiple_of(2) { 1.0 / 3.0 } else { 2.0 / 3.0 };
+    if x > 0.0 {
+        let (i, kk) = ik_positive(order, zeta);
+        // I_(−ν) = I_ν + (2/π) sin(νπ) K_ν
+        let i_neg = i + FRAC_2_PI * (PI * order).sin() * kk;
+        match k {
+            0 => (ax / 3.0).sqrt() * kk / PI,
+            1 => -ax / (PI * sqrt3) * kk,
+            2 => (ax / 3.0).sqrt() * (i_neg + i),
+            _ => ax / sqrt3 * (i_neg + i),
+        }
+    } else {
+        let (j, y) = jy_positive(order, zeta);
+        match k {
+            0 => 0.5 * ax.sqrt() * (j - y / sqrt3),
+            1 => 0.5 * ax * (j + y / sqrt3),
+            2 => -0.5 * ax.sqrt() * (j / sqrt3 + y),
+            _ => 0.5 * ax * (j / sqrt3 - y),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Resultado de una derivada: valor, error estimado y paso final usado.
+/// `exact` indica que se obtuvo por diferenciación automática (sin paso ni error de truncamiento).
+#[derive(Debug, Clone, Copy)]
+pub struct DerivEstimate {
+    pub value: Complex64,
+    pub error: f64,
+    pub step: f64,
+    pub exact: bool,
+}
+
+impl DerivEstimate {
+    fn exact(value: Complex64) -> Self {
+        DerivEstimate { value, error: 0.0, step: 0.0, exact: true }
+    }
+}
+
+/// Lado por el que se aproxima un límite.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Approach {
+    Left,
+    Right,
+    Both,
+}
+
+/// Resultado de un límite: valor finito con su error estimado, o divergencia a ±∞.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitValue {
+    Finite { value: Complex64, error: f64 },
+    PosInf,
+    NegInf,
+}
+
+/// Qué se deriva numéricamente: la función o su derivada exacta (duales) respecto a la semilla `j`.
+#[derive(Clone, Copy)]
+enum Target {
+    Value,
+    Grad(usize),
+}
+
+// Tabla de Richardson: nº máximo de pasos, factor de reducción de h y margen de parada.
+const RICH_NTAB: usize = 10;
+const RICH_CON: f64 = 2.0;
+const RICH_SAFE: f64 = 2.0;
+
+/// Extrapolación de Richardson sobre una aproximación cuyo error es O(h^power)
+/// (2 para las fórmulas centradas, 1 para los límites laterales).
+/// `stencil(t)` evalúa la fórmula con paso relativo `t` (empieza en 1 y se divide por 2).
+/// Se devuelve la entrada de la tabla con menor error estimado; se para cuando
+/// el error de redondeo empieza a dominar (estilo `dfridr` de Numerical Recipes).
+fn richardson<F>(power: f64, mut stencil: F) -> Result<(Complex64, f64, f64), String>
+where
+    F: FnMut(f64) -> Result<Complex64, String>,
+{
+    let con2 = RICH_CON.powf(power);
+    let mut tab = vec![vec![Complex64::new(0.0, 0.0); RICH_NTAB]; RICH_NTAB];
+    let mut t = 1.0;
+    tab[0][0] = stencil(t)?;
+    let (mut best, mut best_err, mut best_t) = (tab[0][0], f64::INFINITY, t);
+
+    for i in 1..RICH_NTAB {
+        t /= RICH_CON;
+        tab[i][0] = stencil(t)?;
+        let mut fac = con2;
+        for j in 1..=i {
+            tab[i][j] = (tab[i][j - 1] * fac - tab[i - 1][j - 1]) / (fac - 1.0);
+            fac *= con2;
+            let err = (tab[i][j] - tab[i][j - 1]).norm().max((tab[i][j] - tab[i - 1][j - 1]).norm());
+            if err <= best_err {
+                best = tab[i][j];
+                best_err = err;
+                best_t = t;
+            }
+        }
+        if (tab[i][i] - tab[i - 1][i - 1]).norm() >= RICH_SAFE * best_err {
+            break;
+        }
+    }
+
+    if !best.re.is_finite() || !best.im.is_finite() {
+        return Err("El resultado no es finito en ese punto.".to_string());
+    }
+    Ok((best, best_err, best_t))
+}
+
+/// Paso inicial automático: el 10% de la escala del punto (0.1 en el origen).
+fn initial_step(x0: f64) -> f64 {
+    if x0 == 0.0 { 0.1 } else { (0.1 * x0.abs()).min(1.0) }
+}
+
+/// Tolerancia relativa para dar un límite por convergido.
+const LIMIT_TOL: f64 = 1e-7;
+/// Muestras directas en pasos h·10^-k para límites que Richardson no resuelve.
+const LIMIT_SAMPLES: i32 = 13;
+
+/// Valor complejo legible en mensajes (solo la parte real si no hay imaginaria).
+fn show(v: Complex64) -> String {
+    if v.im == 0.0 { format!("{}", v.re) } else { format!("{}", v) }
+}
+
+/// Redondea a entero las partes de un límite que distan de él menos que su error.
+fn snap(value: Complex64, error: f64) -> Complex64 {
+    let part = |v: f64| {
+        let tol = 10.0 * error + 4.0 * f64::EPSILON * v.abs();
+        if (v - v.round()).abs() <= tol { v.round() + 0.0 } else { v }
+    };
+    Complex64::new(part(value.re), part(value.im))
+}
+
+/// Separa el orden explícito `n:` de los argumentos de `deriv` (`deriv 2: x^3 2`); sin él,
+/// el orden es 1. Un número al principio sin `:` forma parte de la expresión (`deriv 2*x 3`).
+pub fn split_deriv_order(input: &str) -> Result<(u32, &str), String> {
+    let s = input.trim_start();
+    let Some((n, rest)) = s.split_once(':') else { return Ok((1, s)) };
+    if n.is_empty() || !n.chars().all(|c| c.is_ascii_digit()) {
+        return Ok((1, s));
+    }
+    match n.parse::<u32>() {
+        Ok(order @ 1..=4) => Ok((order, rest.trim_start())),
+        _ => Err("El orden de la derivada debe estar entre 1 y 4.".to_string()),
+    }
+}
+
+/// Coeficientes de las diferencias centradas de orden n (1..=4) como pares (desplazamiento, peso).
+/// El resultado se divide por `den * h^n`.
+fn central_stencil(order: u32) -> Option<(&'static [(f64, f64)], f64)> {
+    match order {
+        1 => Some((&[(1.0, 1.0), (-1.0, -1.0)], 2.0)),
+        2 => Some((&[(1.0, 1.0), (0.0, -2.0), (-1.0, 1.0)], 1.0)),
+        3 => Some((&[(2.0, 1.0), (1.0, -2.0), (-1.0, 2.0), (-2.0, -1.0)], 2.0)),
+        4 => Some((&[(2.0, 1.0), (1.0, -4.0), (0.0, 6.0), (-1.0, -4.0), (-2.0, 1.0)], 1.0)),
+        _ => None,
+    }
+}
+
+impl Calculator {
+    /// Evalúa `expr` con variables temporales; restaura las originales y todo el estado del
+    /// último resultado (`ans`, su incertidumbre, valor exacto, base y formato) al terminar.
+    pub fn eval_with(&mut self, expr: &str, bindings: &[(&str, Complex64)]) -> Result<Complex64, String> {
+        let saved_last = self.last_result;
+        let saved_ans = self.uncertain.get("ans").cloned();
+        let saved_exact = self.exact_result.take();
+        let saved_flags = (self.approx_result, self.result_radix, self.result_dms, self.word_result);
+        let saved: Vec<(&str, Option<Complex64>)> =
+            bindings.iter().map(|(name, _)| (*name, self.vars.get(*name).copied())).collect();
+
+        for (name, val) in bindings {
+            self.vars.insert(name.to_string(), *val);
+        }
+        let res = self.evaluate(expr);
+
+        for (name, old) in saved.into_iter().rev() {
+            match old {
+                Some(v) => { self.vars.insert(name.to_string(), v); }
+                None => { self.vars.remove(name); }
+            }
+        }
+        self.last_result = saved_last;
+        match saved_ans {
+            Some(u) => { self.uncertain.insert("ans".to_string(), u); }
+            None => { self.uncertain.remove("ans"); }
+        }
+        self.exact_result = saved_exact;
+        (self.approx_result, self.result_radix, self.result_dms, self.word_result) = saved_flags;
+        res
+    }
+
+    /// Derivada de orden `order` (1..=4) de `expr` respecto a `var` en `x0`.
+    /// Sin `h`, la primera derivada es exacta (números duales) y las de orden superior se
+    /// extrapolan sobre ella; con `h` explícito, o si la expresión no es derivable con duales,
+    /// se usan diferencias finitas con `h` como paso inicial.
+    pub fn derivative(&mut self, expr: &str, var: &str, x0: f64, order: u32, h: Option<f64>) -> Result<DerivEstimate, String> {
+        if !(1..=4).contains(&order) {
+            return Err("El orden de la derivada debe estar entre 1 y 4.".to_string());
+        }
+        let point = [(var, x0)];
+        if h.is_none() {
+            let ad = if order == 1 {
+                self.evaluate_dual(expr, &[(var, Complex64::new(x0, 0.0))]).map(|d| DerivEstimate::exact(d.d(0)))
+            } else {
+                self.partial(expr, &point, 0, order - 1, None, Target::Grad(0))
+            };
+            if let Ok(d) = ad {
+                return Ok(d);
+            }
+        }
+        self.partial(expr, &point, 0, order, h, Target::Value)
+    }
+
+    /// Gradiente de `expr` en `point` (una derivada parcial por variable).
+    pub fn gradient(&mut self, expr: &str, point: &[(&str, f64)], h: Option<f64>) -> Result<Vec<DerivEstimate>, String> {
+        if h.is_none() {
+            let seeds: Vec<(&str, Complex64)> = point.iter().map(|(n, v)| (*n, Complex64::new(*v, 0.0))).collect();
+            if let Ok(d) = self.evaluate_dual(expr, &seeds) {
+                return Ok((0..point.len()).map(|k| DerivEstimate::exact(d.d(k))).collect());
+            }
+        }
+        (0..point.len()).map(|k| self.partial(expr, point, k, 1, h, Target::Value)).collect()
+    }
+
+    /// Matriz Hessiana de `expr` en `point`. Es simétrica: solo se calcula el triángulo superior.
+    /// Sin `h` se extrapola sobre el gradiente exacto; si falla, diferencias finitas de segundo orden.
+    pub fn hessian(&mut self, expr: &str, point: &[(&str, f64)], h: Option<f64>) -> Result<Vec<Vec<DerivEstimate>>, String> {
+        if h.is_none()
+            && let Ok(hm) = self.hessian_with(point.len(), |c, i, j| c.partial(expr, point, i, 1, None, Target::Grad(j)))
+        {
+            return Ok(hm);
+        }
+        self.hessian_with(point.len(), |c, i, j| {
+            if i == j {
+                c.partial(expr, point, i, 2, h, Target::Value)
+            } else {
+                c.mixed_partial(expr, point, i, j, h)
+            }
+        })
+    }
+
+    fn hessian_with<F>(&mut self, n: usize, mut entry: F) -> Result<Vec<Vec<DerivEstimate>>, String>
+    where
+        F: FnMut(&mut Self, usize, usize) -> Result<DerivEstimate, String>,
+    {
+        use std::collections::HashMap;
+        let mut upper = HashMap::new();
+        for i in 0..n {
+            for j in i..n {
+                upper.insert((i, j), entry(self, i, j)?);
+            }
+        }
+        Ok((0..n).map(|i| (0..n).map(|j| upper[&(i.min(j), i.max(j))]).collect()).collect())
+    }
+
+    /// Valor muestreado en `bindings` según el objetivo de la derivación numérica.
+    fn sample(&mut self, expr: &str, bindings: &[(&str, Complex64)], target: Target) -> Result<Complex64, String> {
+        match target {
+            Target::Value => self.eval_with(expr, bindings),
+            Target::Grad(j) => Ok(self.evaluate_dual(expr, bindings)?.d(j)),
+        }
+    }
+
+    /// Derivada parcial de orden `order` respecto a la variable `k` de `point`.
+    fn partial(&mut self, expr: &str, point: &[(&str, f64)], k: usize, order: u32, h: Option<f64>, target: Target) -> Result<DerivEstimate, String> {
+        let (coefs, den) = central_stencil(order).ok_or("El orden de la derivada debe estar entre 1 y 4.")?;
+        let h0 = h.unwrap_or_else(|| initial_step(point[k].1));
+        if h0 <= 0.0 || !h0.is_finite() {
+            return Err("El paso h debe ser un número positivo.".to_string());
+        }
+
+        let mut bindings: Vec<(&str, Complex64)> =
+            point.iter().map(|(name, v)| (*name, Complex64::new(*v, 0.0))).collect();
+        let (value, error, t) = richardson(2.0, |t| {
+            let step = h0 * t;
+            let mut acc = Complex64::new(0.0, 0.0);
+            for &(shift, weight) in coefs {
+                bindings[k].1 = Complex64::new(point[k].1 + shift * step, 0.0);
+                acc += self.sample(expr, &bindings, target)? * weight;
+            }
+            Ok(acc / (den * step.powi(order as i32)))
+        })?;
+        Ok(DerivEstimate { value, error, step: h0 * t, exact: false })
+    }
+
+    /// Derivada cruzada ∂²f/∂xi∂xj con la fórmula centrada de 4 puntos.
+    fn mixed_partial(&mut self, expr: &str, point: &[(&str, f64)], i: usize, j: usize, h: Option<f64>) -> Result<DerivEstimate, String> {
+        let hi = h.unwrap_or_else(|| initial_step(point[i].1));
+        let hj = h.unwrap_or_else(|| initial_step(point[j].1));
+        if hi <= 0.0 || hj <= 0.0 || !hi.is_finite() || !hj.is_finite() {
+            return Err("El paso h debe ser un número positivo.".to_string());
+        }
+
+        let mut bindings: Vec<(&str, Complex64)> =
+            point.iter().map(|(name, v)| (*name, Complex64::new(*v, 0.0))).collect();
+        let (value, error, t) = richardson(2.0, |t| {
+            let mut acc = Complex64::new(0.0, 0.0);
+            for (si, sj, w) in [(1.0, 1.0, 1.0), (1.0, -1.0, -1.0), (-1.0, 1.0, -1.0), (-1.0, -1.0, 1.0)] {
+                bindings[i].1 = Complex64::new(point[i].1 + si * hi * t, 0.0);
+                bindings[j].1 = Complex64::new(point[j].1 + sj * hj * t, 0.0);
+                acc += self.eval_with(expr, &bindings)? * w;
+            }
+            Ok(acc / (4.0 * hi * hj * t * t))
+        })?;
+        Ok(DerivEstimate { value, error, step: hi.max(hj) * t, exact: false })
+    }
+
+    /// Límite de `expr` cuando `var` tiende a `point` (admite ±∞) por el lado indicado.
+    /// Se extrapola (Richardson en h y en √h) sobre f(a ± h); si no converge, se prueban
+    /// muestras cada vez más cercanas y se detecta la divergencia a ±∞.
+    pub fn limit(&mut self, expr: &str, var: &str, point: f64, approach: Approach) -> Result<LimitValue, String> {
+        if point.is_nan() {
+            return Err("El punto del límite no es válido.".to_string());
+        }
+        if point.is_infinite() || approach != Approach::Both {
+            let dir = if point == f64::INFINITY || (point.is_finite() && approach == Approach::Left) { -1.0 } else { 1.0 };
+            return self.one_sided_limit(expr, var, point, dir);
+        }
+        let left = self.one_sided_limit(expr, var, point, -1.0);
+        let right = self.one_sided_limit(expr, var, point, 1.0);
+        match (left, right) {
+            (Ok(l), Ok(r)) => match (l, r) {
+                (LimitValue::Finite { value: a, error: ea }, LimitValue::Finite { value: b, error: eb }) => {
+                    let tol = LIMIT_TOL * a.norm().max(b.norm()).max(1.0) + 10.0 * (ea + eb);
+                    if (a - b).norm() <= tol {
+                        let error = ea.max(eb).max((a - b).norm());
+                        Ok(LimitValue::Finite { value: snap((a + b) / 2.0, error), error })
+                    } else {
+                        Err(format!("El límite no existe: por la izquierda {}, por la derecha {}", show(a), show(b)))
+                    }
+                }
+                (l, r) if l == r => Ok(l),
+                (l, r) => Err(format!("El límite no existe: por la izquierda {}, por la derecha {}", l, r)),
+            },
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        }
+    }
+
+    /// Límite lateral. `dir` es el sentido del desplazamiento desde el punto: +1 por la
+    /// derecha, -1 por la izquierda. En ±∞ se sustituye x = ±1/u con u → 0⁺.
+    fn one_sided_limit(&mut self, expr: &str, var: &str, point: f64, dir: f64) -> Result<LimitValue, String> {
+        let h0 = if point.is_finite() { initial_step(point) } else { 0.1 };
+        let at = |h: f64| if point.is_finite() { point + dir * h } else { point.signum() / h };
+        let f = |c: &mut Self, h: f64| c.eval_with(expr, &[(var, Complex64::new(at(h), 0.0))]);
+
+        // 1. Extrapolación: desarrollo en potencias de h y, si no, de √h (Puiseux)
+        for power in [1.0, 0.5] {
+            if let Ok((value, error, _)) = richardson(power, |t| f(self, h0 * t))
+                && error <= LIMIT_TOL * value.norm().max(1.0)
+            {
+                return Ok(LimitValue::Finite { value: snap(value, error), error });
+            }
+        }
+
+        // 2. Muestras directas f(a ± h0·10^-k). Si una falla (p. ej. desborda, como exp(x)
+        //    en +∞), se afina entre la última válida y ella en pasos de 10^(-1/16) para
+        //    tener cola suficiente con la que reconocer la divergencia.
+        let mut samples = Vec::new();
+        for k in 0..LIMIT_SAMPLES {
+            let h = h0 * 10f64.powi(-k);
+            match f(self, h) {
+                Ok(v) => samples.push(v),
+                Err(_) => {
+                    for j in 1..16 {
+                        match f(self, h * 10f64.powf((16 - j) as f64 / 16.0)) {
+                            Ok(v) if k > 0 => samples.push(v),
+                            _ => break,
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        let n = samples.len();
+        if n < 6 {
+            return Err("No se pudo evaluar la expresión cerca del punto.".to_string());
+        }
+        let diffs: Vec<f64> = samples.windows(2).map(|w| (w[1] - w[0]).norm()).collect();
+        let last = samples[n - 1];
+        let shrinking = diffs[diffs.len() - 3..].windows(2).all(|w| w[1] <= w[0]);
+        if shrinking && diffs[diffs.len() - 1] <= 1e-6 * last.norm().max(1.0) {
+            let error = diffs[diffs.len() - 1];
+            return Ok(LimitValue::Finite { value: snap(last, error), error });
+        }
+
+        // 3. Divergencia: módulo creciente, signo real constante y crecimiento apreciable
+        let tail = &samples[n - 6..];
+        let growing = tail.windows(2).all(|w| w[1].norm() > w[0].norm());
+        let same_sign = tail.iter().all(|v| v.im.abs() <= 1e-9 * v.norm() && v.re.signum() == last.re.signum());
+        if growing && same_sign && last.norm() >= 8.0 * samples[0].norm().max(1.0) {
+            return Ok(if last.re > 0.0 { LimitValue::PosInf } else { LimitValue::NegInf });
+        }
+        Err(format!("El límite no existe o no se pudo determinar (última muestra: {})", show(last)))
+    }
+}
+
+impl std::fmt::Display for LimitValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LimitValue::Finite { value, .. } => write!(f, "{}", show(*value)),
+            LimitValue::PosInf => write!(f, "+∞"),
+            LimitValue::NegInf => write!(f, "-∞"),
+        }
+    }
+}
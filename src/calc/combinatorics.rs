@@ -0,0 +1,329 @@
+//! Combinatoria. Con argumentos enteros el resultado se calcula exacto en precisión arbitraria
+//! (por el camino entero de `numtheory`) hasta `MAX_EXACT_BITS` bits; por encima se aproxima
+//! en coma flotante.
+
+use super::{numtheory::{exact_int, MAX_EXACT_BITS}, special};
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_traits::{One, ToPrimitive, Zero};
+use std::f64::consts::{PI, SQRT_2};
+
+/// Funciones combinatorias de argumentos enteros (además de `fact`, `comb` y `perm`).
+pub const COMB_FUNCS: &[&str] = &[
+    "catalan", "stirling1", "stirling2", "bell", "partitions", "multinomial", "derangements", "fib", "lucas",
+];
+
+/// Máximo de operaciones de la recurrencia de Stirling.
+const MAX_WORK: i128 = 50_000_000;
+/// Máximo de operaciones de la recurrencia de Stirling con enteros exactos.
+const MAX_EXACT_WORK: i128 = 1_000_000;
+/// Mayor n de `partitions` exacto (la recurrencia pentagonal cuesta ~n^1.5 sumas).
+const MAX_EXACT_PARTITIONS: i128 = 20_000;
+
+/// ¿Se evalúa `func` exacta cuando sus argumentos son enteros?
+pub(crate) fn is_exact(func: &str) -> bool {
+    COMB_FUNCS.contains(&func) || matches!(func, "fact" | "comb" | "nCr" | "perm" | "nPr")
+}
+
+fn arity(func: &str, args: &[i128], n: usize) -> Result<(), String> {
+    if args.len() != n {
+        return Err(format!("{} requiere {} argumento(s)", func, n));
+    }
+    Ok(())
+}
+
+fn non_negative(n: i128, func: &str) -> Result<i128, String> {
+    if n < 0 {
+        return Err(format!("{} requiere enteros >= 0", func));
+    }
+    Ok(n)
+}
+
+/// Valida los argumentos de `func`; con `n·k` acotado cuando la recurrencia es cuadrática.
+fn check(func: &str, args: &[i128]) -> Result<(), String> {
+    match func {
+        "multinomial" => {
+            if args.is_empty() {
+                return Err("Uso: multinomial(k1, k2, ...)".to_string());
+            }
+            args.iter().try_for_each(|&k| non_negative(k, func).map(|_| ()))
+        }
+        "stirling1" | "stirling2" | "comb" | "nCr" | "perm" | "nPr" => arity(func, args, 2),
+        _ => arity(func, args, 1),
+    }?;
+    match func {
+        "fib" | "lucas" | "comb" | "nCr" => Ok(()),
+        "perm" | "nPr" => {
+            if args[0] < 0 || args[1] < 0 {
+                return Err("perm(n,k) requiere n, k >= 0".to_string());
+            }
+            if args[1] > args[0] {
+                return Err("perm(n,k) requiere k <= n".to_string());
+            }
+            Ok(())
+        }
+        "stirling1" | "stirling2" => {
+            non_negative(args[0], func)?;
+            non_negative(args[1], func)?;
+            if args[0] * args[1].min(args[0]) > MAX_WORK {
+                return Err(format!("{}: argumentos demasiado grandes", func));
+            }
+            Ok(())
+        }
+        "bell" if args[0] > 1000 => Err("bell: resultado demasiado grande".to_string()),
+        _ => non_negative(args[0], func).map(|_| ()),
+    }
+}
+
+/// `v` si no pasa de `MAX_EXACT_BITS` bits.
+fn fits(v: BigInt) -> Option<BigInt> {
+    (v.bits() <= MAX_EXACT_BITS).then_some(v)
+}
+
+/// Binomial con índice superior entero cualquiera: C(n, k) = (−1)^k·C(k − n − 1, k) si n < 0.
+fn binomial_exact(n: i128, k: i128) -> Option<BigInt> {
+    if k < 0 || (n >= 0 && k > n) {
+        return Some(BigInt::zero());
+    }
+    let (top, negative) = if n < 0 { (k.checked_sub(n)?.checked_sub(1)?, k % 2 != 0) } else { (n, false) };
+    // C(n, k) >= 2^min(k, n−k)
+    let k = k.min(top - k);
+    if k as u64 > MAX_EXACT_BITS {
+        return None;
+    }
+    // (n−k+1)/1 · (n−k+2)/2 ⋯: cada producto parcial es un binomial, la división es exacta
+    let c = (1..=k).try_fold(BigInt::one(), |acc, i| fits(acc * BigInt::from(top - k + i) / i))?;
+    Some(if negative { -c } else { c })
+}
+
+/// (F(n), F(n+1)) por duplicación: F(2m) = F(m)(2F(m+1) − F(m)), F(2m+1) = F(m)² + F(m+1)².
+fn fib_pair(n: u128) -> Option<(BigInt, BigInt)> {
+    if n == 0 {
+        return Some((BigInt::zero(), BigInt::one()));
+    }
+    let (a, b) = fib_pair(n / 2)?;
+    let c = fits(&a * (&b * 2 - &a))?;
+    let d = fits(&a * &a + &b * &b)?;
+    if n.is_multiple_of(2) {
+        Some((c, d))
+    } else {
+        let e = fits(&c + &d)?;
+        Some((d, e))
+    }
+}
+
+/// Recurrencia de Stirling hasta la fila n, columnas 0..=k; `step(izq, actual, m)` da el
+/// nuevo valor (y decide la aritmética: enteros exactos acotados o f64).
+fn stirling<T: Clone>(n: usize, k: usize, first: bool, zero: T, one: T, step: impl Fn(&T, &T, usize) -> Option<T>) -> Option<T> {
+    if k > n {
+        return Some(zero);
+    }
+    let mut row = vec![zero.clone(); k + 1];
+    row[0] = one;
+    for i in 0..n {
+        for j in (1..=k.min(i + 1)).rev() {
+            // s(i+1, j) = s(i, j−1) − i·s(i, j) ;  S(i+1, j) = S(i, j−1) + j·S(i, j)
+            row[j] = step(&row[j - 1], &row[j], if first { i } else { j })?;
+        }
+        row[0] = zero.clone();
+    }
+    row.pop()
+}
+
+/// Desplazamientos pentagonales generalizados k(3k − 1)/2 con su signo en p(n).
+fn pentagonal(n: usize) -> impl Iterator<Item = (usize, bool)> {
+    (1..).flat_map(|k: usize| [(k * (3 * k - 1) / 2, k % 2 == 1), (k * (3 * k + 1) / 2, k % 2 == 1)]).take_while(move |&(g, _)| g <= n)
+}
+
+/// Valor exacto de una función combinatoria; `Ok(None)` si pasa de `MAX_EXACT_BITS` bits.
+pub(crate) fn exact(func: &str, args: &[i128]) -> Result<Option<BigInt>, String> {
+    if func == "fact" {
+        // fact de negativos o no enteros: Γ(x+1) en coma flotante
+        return Ok(args
+            .first()
+            .filter(|&&n| args.len() == 1 && n >= 0)
+            .and_then(|&n| (2..=n).try_fold(BigInt::one(), |acc, k| fits(acc * k))));
+    }
+    check(func, args)?;
+    let n = args[0];
+    Ok(match func {
+        "comb" | "nCr" => binomial_exact(n, args[1]),
+        "perm" | "nPr" => (n - args[1] + 1..=n).try_fold(BigInt::one(), |acc, m| fits(acc * m)),
+        "catalan" => (0..n).try_fold(BigInt::one(), |c, k| fits(c * (2 * (2 * k + 1)) / (k + 2))),
+        "stirling1" | "stirling2" if n * args[1].min(n) > MAX_EXACT_WORK => None,
+        "stirling1" | "stirling2" => {
+            let first = func == "stirling1";
+            stirling(n as usize, args[1] as usize, first, BigInt::zero(), BigInt::one(), |left, cur, m| {
+                let t = cur * m;
+                fits(if first { left - t } else { left + t })
+            })
+        }
+        "bell" => {
+            // Triángulo de Bell: cada fila empieza por el último elemento de la anterior
+            let mut row = vec![BigInt::one()];
+            for _ in 0..n {
+                let mut next = vec![row[row.len() - 1].clone()];
+                for x in &row {
+                    let v = &next[next.len() - 1] + x;
+                    next.push(v);
+                }
+                row = next;
+            }
+            fits(row.swap_remove(0))
+        }
+        "partitions" if n > MAX_EXACT_PARTITIONS => None,
+        "partitions" => {
+            let mut p = vec![BigInt::one()];
+            for m in 1..=n as usize {
+                let mut acc = BigInt::zero();
+                for (g, plus) in pentagonal(m) {
+                    if plus { acc += &p[m - g] } else { acc -= &p[m - g] }
+                }
+                p.push(acc);
+            }
+            p.pop()
+        }
+        "multinomial" => {
+            let mut total: i128 = 0;
+            let mut acc = BigInt::one();
+            for &k in args {
+                total = match total.checked_add(k) {
+                    Some(t) => t,
+                    None => return Ok(None),
+                };
+                match binomial_exact(total, k).and_then(|c| fits(acc * c)) {
+                    Some(v) => acc = v,
+                    None => return Ok(None),
+                }
+            }
+            Some(acc)
+        }
+        "derangements" => {
+            // !n = (n − 1)(!(n−1) + !(n−2)), con !0 = 1, !1 = 0
+            (2..=n)
+                .try_fold((BigInt::one(), BigInt::zero()), |(d0, d1), m| {
+                    let d2 = fits((d0 + &d1) * (m - 1))?;
+                    Some((d1, d2))
+                })
+                .map(|(d0, d1)| if n == 0 { d0 } else { d1 })
+        }
+        "fib" | "lucas" => {
+            let (f, f1) = match fib_pair(n.unsigned_abs()) {
+                Some(p) => p,
+                None => return Ok(None),
+            };
+            // F(−n) = (−1)^(n+1)·F(n) ;  L(n) = 2F(n+1) − F(n), L(−n) = (−1)^n·L(n)
+            let odd = n % 2 != 0;
+            if func == "fib" {
+                Some(if n < 0 && !odd { -f } else { f })
+            } else {
+                let l: BigInt = f1 * 2 - f;
+                fits(if n < 0 && odd { -l } else { l })
+            }
+        }
+        _ => return Err(format!("Función '{}' no implementada", func)),
+    })
+}
+
+/// Aproximación en coma flotante para resultados de más de `MAX_EXACT_BITS` bits.
+fn approx(func: &str, args: &[i128]) -> Result<f64, String> {
+    let n = args[0] as f64;
+    let lg = |x: f64| special::ln_gamma(x + 1.0);
+    let v = match func {
+        "comb" | "nCr" => {
+            let k = args[1];
+            let (top, sign) = if args[0] < 0 { (k - args[0] - 1, if k % 2 == 0 { 1.0 } else { -1.0 }) } else { (args[0], 1.0) };
+            let k = k.min(top - k);
+            // Producto (n−k+1)/1 · (n−k+2)/2 ⋯: más preciso que vía ln Γ
+            let v = if k <= 1_000_000 {
+                (1..=k).fold(1.0, |acc, i| acc * (top - k + i) as f64 / i as f64)
+            } else {
+                (lg(top as f64) - lg(k as f64) - lg((top - k) as f64)).exp()
+            };
+            sign * v
+        }
+        "perm" | "nPr" => (lg(n) - lg(n - args[1] as f64)).exp(),
+        "catalan" => (lg(2.0 * n) - 2.0 * lg(n) - (n + 1.0).ln()).exp(),
+        "stirling1" | "stirling2" => {
+            let first = func == "stirling1";
+            stirling(n as usize, args[1] as usize, first, 0.0, 1.0, |&left, &cur, m| {
+                Some(if first { left - m as f64 * cur } else { left + m as f64 * cur })
+            })
+            .unwrap_or(f64::NAN)
+        }
+        "bell" => {
+            let mut row = vec![1.0f64];
+            for _ in 0..args[0] {
+                let mut next = vec![*row.last().unwrap_or(&1.0)];
+                for &x in &row {
+                    next.push(next[next.len() - 1] + x);
+                }
+                row = next;
+            }
+            row[0]
+        }
+        "partitions" => {
+            // Primer término de Hardy–Ramanujan–Rademacher (la recurrencia pentagonal es
+            // inestable en coma flotante); aquí n > 20000 y el resto es despreciable
+            let lam = (n - 1.0 / 24.0).sqrt();
+            let c = PI * (2.0f64 / 3.0).sqrt();
+            (c * lam + ((c / lam - 1.0 / (lam * lam)) / (4.0 * lam * PI * SQRT_2)).ln()).exp()
+        }
+        "multinomial" => {
+            let total: f64 = args.iter().map(|&k| k as f64).sum();
+            (lg(total) - args.iter().map(|&k| lg(k as f64)).sum::<f64>()).exp()
+        }
+        "derangements" => (lg(n) - 1.0).exp().round(),
+        "fib" | "lucas" => {
+            // Binet: φ^n/√5 y φ^n (el término ψ^n es despreciable aquí)
+            let phi = (1.0 + 5f64.sqrt()) / 2.0;
+            let m = n.abs();
+            let mag = if func == "fib" { (m * phi.ln() - 0.5 * 5f64.ln()).exp() } else { (m * phi.ln()).exp() };
+            let odd = args[0] % 2 != 0;
+            let negative = args[0] < 0 && if func == "fib" { !odd } else { odd };
+            if negative { -mag } else { mag }
+        }
+        _ => f64::NAN,
+    };
+    if !v.is_finite() {
+        return Err(format!("{}: resultado demasiado grande", func));
+    }
+    Ok(v)
+}
+
+/// Evaluación en coma flotante de una función combinatoria de argumentos enteros.
+pub(crate) fn eval(func: &str, vals: &[Complex64]) -> Result<f64, String> {
+    let args = vals.iter().map(|&v| exact_int(v, func)).collect::<Result<Vec<_>, _>>()?;
+    match exact(func, &args)? {
+        Some(v) => v.to_f64().filter(|v| v.is_finite()).ok_or_else(|| format!("{}: resultado demasiado grande", func)),
+        None => approx(func, &args),
+    }
+}
+
+/// 1/Γ(z), nula en los polos.
+fn rgamma(z: Complex64) -> Result<Complex64, String> {
+    if z.im == 0.0 && z.re <= 0.0 && z.re.fract() == 0.0 {
+        return Ok(Complex64::new(0.0, 0.0));
+    }
+    Ok(special::gamma(z)?.inv())
+}
+
+/// C(a, k) generalizado. Con k entero >= 0 es el polinomio a(a−1)…(a−k+1)/k!, válido para
+/// cualquier a; en otro caso Γ(a+1)/(Γ(k+1)·Γ(a−k+1)).
+pub(crate) fn binomial(a: Complex64, k: Complex64) -> Result<Complex64, String> {
+    if let (Ok(_), Ok(_)) = (exact_int(a, "comb"), exact_int(k, "comb")) {
+        return eval("comb", &[a, k]).map(|v| Complex64::new(v, 0.0));
+    }
+    if k.im == 0.0 && k.re >= 0.0 && k.re.fract() == 0.0 && k.re <= 1e6 {
+        let mut acc = Complex64::new(1.0, 0.0);
+        for i in 0..k.re as u64 {
+            acc *= (a - i as f64) / (i + 1) as f64;
+        }
+        return Ok(acc);
+    }
+    let one = Complex64::new(1.0, 0.0);
+    if a.im == 0.0 && a.re < 0.0 && a.re.fract() == 0.0 {
+        return Err("comb(n, k) con n entero negativo requiere k entero".to_string());
+    }
+    Ok(special::gamma(a + one)? * rgamma(k + one)? * rgamma(a - k + one)?)
+}
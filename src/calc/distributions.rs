@@ -0,0 +1,583 @@
+//! Distribuciones de probabilidad: densidad (`pdf`/`pmf`), distribución acumulada (`cdf`)
+//! y cuantil (`inv`) con el convenio de nombres `<familia><tipo>(x, parámetros...)`.
+//!
+//! Cada `cdf` calcula las dos colas por separado, de modo que tanto `P(X ≤ x)` como
+//! `P(X > x)` conservan precisión relativa aunque sean diminutas; los cuantiles se
+//! resuelven sobre la cola más pequeña.
+
+use super::special::{beta_inc, dbinom_raw, dpois_raw, gamma_inc};
+use std::f64::consts::PI;
+
+pub const DIST_FUNCS: &[&str] = &[
+    "normpdf", "normcdf", "norminv",
+    "tpdf", "tcdf", "tinv",
+    "chi2pdf", "chi2cdf", "chi2inv",
+    "fpdf", "fcdf", "finv",
+    "exppdf", "expcdf", "expinv",
+    "unifpdf", "unifcdf", "unifinv",
+    "binompmf", "binomcdf", "binominv",
+    "poisspmf", "poisscdf", "poissinv",
+    "geompmf", "geomcdf", "geominv",
+    "hygepmf", "hygecdf", "hygeinv",
+];
+
+/// Familias y nombres de sus parámetros (para validar la aridad y el mensaje de uso).
+const FAMILIES: &[(&str, &[&str])] = &[
+    ("norm", &["mu", "sigma"]),
+    ("t", &["df"]),
+    ("chi2", &["k"]),
+    ("f", &["d1", "d2"]),
+    ("exp", &["lambda"]),
+    ("unif", &["a", "b"]),
+    ("binom", &["n", "p"]),
+    ("poiss", &["lambda"]),
+    ("geom", &["p"]),
+    ("hyge", &["N", "K", "n"]),
+];
+
+const SQRT_2PI: f64 = 2.506_628_274_631_000_5;
+
+#[derive(Debug, Clone, Copy)]
+enum Dist {
+    Normal { mu: f64, sigma: f64 },
+    T { df: f64 },
+    Chi2 { k: f64 },
+    F { d1: f64, d2: f64 },
+    Exp { rate: f64 },
+    Unif { a: f64, b: f64 },
+    Binom { n: f64, p: f64 },
+    Poisson { lambda: f64 },
+    Geom { p: f64 },
+    /// `N` elementos, `K` éxitos, `n` extracciones sin reemplazo.
+    Hyge { total: f64, good: f64, n: f64 },
+}
+
+fn is_count(v: f64) -> bool {
+    v >= 0.0 && v.fract() == 0.0 && v.is_finite()
+}
+
+fn check(ok: bool, func: &str, msg: &str) -> Result<(), String> {
+    if ok { Ok(()) } else { Err(format!("{}: {}", func, msg)) }
+}
+
+/// Separa `normcdf` en ("norm", "cdf").
+fn split_name(func: &str) -> Option<(&str, &str)> {
+    ["pdf", "pmf", "cdf", "inv"]
+        .iter()
+        .find_map(|kind| func.strip_suffix(kind).map(|family| (family, *kind)))
+}
+
+impl Dist {
+    fn new(func: &str, family: &str, p: &[f64]) -> Result<Dist, String> {
+        Ok(match family {
+            "norm" => {
+                let (mu, sigma) = if p.is_empty() { (0.0, 1.0) } else { (p[0], p[1]) };
+                check(sigma > 0.0, func, "sigma debe ser > 0")?;
+                Dist::Normal { mu, sigma }
+            }
+            "t" => {
+                check(p[0] > 0.0, func, "los grados de libertad deben ser > 0")?;
+                Dist::T { df: p[0] }
+            }
+            "chi2" => {
+                check(p[0] > 0.0, func, "los grados de libertad deben ser > 0")?;
+                Dist::Chi2 { k: p[0] }
+            }
+            "f" => {
+                check(p[0] > 0.0 && p[1] > 0.0, func, "los grados de libertad deben ser > 0")?;
+                Dist::F { d1: p[0], d2: p[1] }
+            }
+            "exp" => {
+                check(p[0] > 0.0, func, "lambda debe ser > 0")?;
+                Dist::Exp { rate: p[0] }
+            }
+            "unif" => {
+                check(p[0] < p[1], func, "se requiere a < b")?;
+                Dist::Unif { a: p[0], b: p[1] }
+            }
+            "binom" => {
+                check(is_count(p[0]), func, "n debe ser un entero >= 0")?;
+                check((0.0..=1.0).contains(&p[1]), func, "p debe estar en [0, 1]")?;
+                Dist::Binom { n: p[0], p: p[1] }
+            }
+            "poiss" => {
+                check(p[0] >= 0.0 && p[0].is_finite(), func, "lambda debe ser >= 0")?;
+                Dist::Poisson { lambda: p[0] }
+            }
+            "geom" => {
+                check(p[0] > 0.0 && p[0] <= 1.0, func, "p debe estar en (0, 1]")?;
+                Dist::Geom { p: p[0] }
+            }
+            _ => {
+                check(p.iter().all(|v| is_count(*v)), func, "N, K y n deben ser enteros >= 0")?;
+                check(p[1] <= p[0] && p[2] <= p[0], func, "se requiere K <= N y n <= N")?;
+                Dist::Hyge { total: p[0], good: p[1], n: p[2] }
+            }
+        })
+    }
+
+    fn is_discrete(&self) -> bool {
+        matches!(self, Dist::Binom { .. } | Dist::Poisson { .. } | Dist::Geom { .. } | Dist::Hyge { .. })
+    }
+
+    /// Soporte [mínimo, máximo].
+    fn support(&self) -> (f64, f64) {
+        match *self {
+            Dist::Normal { .. } | Dist::T { .. } => (f64::NEG_INFINITY, f64::INFINITY),
+            Dist::Chi2 { .. } | Dist::F { .. } | Dist::Exp { .. } | Dist::Poisson { .. } => (0.0, f64::INFINITY),
+            Dist::Unif { a, b } => (a, b),
+            Dist::Binom { n, .. } => (0.0, n),
+            Dist::Geom { .. } => (1.0, f64::INFINITY),
+            Dist::Hyge { total, good, n } => ((n - (total - good)).max(0.0), n.min(good)),
+        }
+    }
+
+    /// Densidad (continuas) o probabilidad puntual (discretas).
+    fn density(&self, x: f64) -> f64 {
+        if x.is_nan() {
+            return f64::NAN;
+        }
+        if self.is_discrete() {
+            let (lo, hi) = self.support();
+            if x.fract() != 0.0 || x < lo || x > hi {
+                return 0.0;
+            }
+        }
+        match *self {
+            Dist::Normal { mu, sigma } => {
+                let z = (x - mu) / sigma;
+                (-0.5 * z * z).exp() / (sigma * SQRT_2PI)
+            }
+            Dist::T { df } => t_density(x, df),
+            Dist::Chi2 { k } => gamma_density(x, k / 2.0, 2.0),
+            Dist::F { d1, d2 } => f_density(x, d1, d2),
+            Dist::Exp { rate } => if x < 0.0 { 0.0 } else { rate * (-rate * x).exp() },
+            Dist::Unif { a, b } => if x < a || x > b { 0.0 } else { 1.0 / (b - a) },
+            Dist::Binom { n, p } => dbinom_raw(x, n, p, 1.0 - p),
+            Dist::Poisson { lambda } => dpois_raw(x, lambda),
+            Dist::Geom { p } => p * ((x - 1.0) * (-p).ln_1p()).exp(),
+            Dist::Hyge { total, good, n } => {
+                let (p, q) = (n / total, (total - n) / total);
+                if total == 0.0 {
+                    return 1.0;
+                }
+                dbinom_raw(x, good, p, q) * dbinom_raw(n - x, total - good, p, q) / dbinom_raw(n, total, p, q)
+            }
+        }
+    }
+
+    /// (P(X ≤ x), P(X > x)), cada una calculada sin restar de 1 cuando es la pequeña.
+    fn tails(&self, x: f64) -> (f64, f64) {
+        let (lo, hi) = self.support();
+        if x.is_nan() {
+            return (f64::NAN, f64::NAN);
+        }
+        if x < lo {
+            return (0.0, 1.0);
+        }
+        if x >= hi {
+            return (1.0, 0.0);
+        }
+        let k = x.floor();
+        match *self {
+            Dist::Normal { mu, sigma } => norm_tails((x - mu) / sigma),
+            Dist::T { df } => {
+                if x == 0.0 {
+                    return (0.5, 0.5);
+                }
+                if df >= 1e4 {
+                    // La fracción continua pierde ~ν·ε con x ≈ 1; la transformación
+                    // asintótica de Hill (Algorithm 395) a normal es exacta a ~1e-14 aquí
+                    let a = df - 0.5;
+                    let b = 48.0 * a * a;
+                    let y = a * (x * x / df).ln_1p();
+                    let z = (((((-0.4 * y - 3.3) * y - 24.0) * y - 85.5) / (0.8 * y * y + 100.0 + b) + y + 3.0) / b + 1.0) * y.sqrt();
+                    return norm_tails(if x < 0.0 { -z } else { z });
+                }
+                if df == 1.0 {
+                    // Cauchy: forma cerrada, válida hasta |x| ~ 1e308
+                    let small = (1.0 / x.abs()).atan() / PI;
+                    return if x < 0.0 { (small, 1.0 - small) } else { (1.0 - small, small) };
+                }
+                let r = x * x / df;
+                let (bx, by) = if r < 1.0 {
+                    (1.0 / (1.0 + r), r / (1.0 + r))
+                } else {
+                    let s2 = (df.sqrt() / x).powi(2);
+                    (s2 / (1.0 + s2), 1.0 / (1.0 + s2))
+                };
+                let (i, ic) = beta_inc(df / 2.0, 0.5, bx, by);
+                let (small, big) = (0.5 * i, 0.5 + 0.5 * ic);
+                if x < 0.0 { (small, big) } else { (big, small) }
+            }
+            Dist::Chi2 { k } => gamma_inc(k / 2.0, x / 2.0),
+            Dist::F { d1, d2 } => {
+                let z = d1 * x;
+                beta_inc(d1 / 2.0, d2 / 2.0, z / (z + d2), d2 / (z + d2))
+            }
+            Dist::Exp { rate } => (-(-rate * x).exp_m1(), (-rate * x).exp()),
+            Dist::Unif { a, b } => ((x - a) / (b - a), (b - x) / (b - a)),
+            Dist::Binom { n, p } => {
+                let (upper, lower) = beta_inc(k + 1.0, n - k, p, 1.0 - p);
+                (lower, upper)
+            }
+            Dist::Poisson { lambda } => {
+                let (upper, lower) = gamma_inc(k + 1.0, lambda);
+                (lower, upper)
+            }
+            Dist::Geom { p } => {
+                let l = k * (-p).ln_1p();
+                (-l.exp_m1(), l.exp())
+            }
+            Dist::Hyge { total, good, n } => {
+                let bad = total - good;
+                if k <= n * good / total {
+                    // Suma descendente desde k: pmf(j−1)/pmf(j) = j(bad−n+j) / ((good−j+1)(n−j+1))
+                    let (mut term, mut sum, mut j) = (1.0, 1.0, k);
+                    while j > lo && term > sum * 1e-17 {
+                        term *= j * (bad - n + j) / ((good - j + 1.0) * (n - j + 1.0));
+                        sum += term;
+                        j -= 1.0;
+                    }
+                    let lower = (self.density(k) * sum).min(1.0);
+                    (lower, 1.0 - lower)
+                } else {
+                    // Suma ascendente desde k+1: pmf(j+1)/pmf(j) = (good−j)(n−j) / ((j+1)(bad−n+j+1))
+                    let (mut term, mut sum, mut j) = (1.0, 1.0, k + 1.0);
+                    while j < hi && term > sum * 1e-17 {
+                        term *= (good - j) * (n - j) / ((j + 1.0) * (bad - n + j + 1.0));
+                        sum += term;
+                        j += 1.0;
+                    }
+                    let upper = (self.density(k + 1.0) * sum).min(1.0);
+                    (1.0 - upper, upper)
+                }
+            }
+        }
+    }
+
+    /// Cuantil: el x con P(X ≤ x) = p (continuas) o el menor k con P(X ≤ k) ≥ p (discretas).
+    fn quantile(&self, p: f64) -> f64 {
+        let (lo, hi) = self.support();
+        if p == 0.0 {
+            return lo;
+        }
+        if p == 1.0 {
+            return hi;
+        }
+        let z = norm_quantile_guess(p);
+        match *self {
+            Dist::Exp { rate } => -(-p).ln_1p() / rate,
+            Dist::Unif { a, b } => a + p * (b - a),
+            Dist::Normal { mu, sigma } => self.invert(p, mu + sigma * z),
+            Dist::T { df } => {
+                if df == 1.0 {
+                    return if p < 0.5 { -1.0 / (PI * p).tan() } else { 1.0 / (PI * (1.0 - p)).tan() };
+                }
+                self.halley(p, self.invert(p, z + (z * z * z + z) / (4.0 * df)))
+            }
+            Dist::Chi2 { k } => {
+                // Wilson–Hilferty; en la cola izquierda, P(k/2, x/2) ≈ (x/2)^(k/2) / Γ(k/2+1)
+                let c = 2.0 / (9.0 * k);
+                let wh = k * (1.0 - c + z * c.sqrt()).powi(3);
+                let guess = if wh > 0.0 { wh } else { 2.0 * (p * super::special::ln_gamma(k / 2.0 + 1.0).exp()).powf(2.0 / k) };
+                self.invert(p, guess)
+            }
+            Dist::F { .. } => self.invert(p, 1.0),
+            Dist::Binom { n, p: pr } => self.discrete_quantile(p, n * pr + z * (n * pr * (1.0 - pr)).sqrt()),
+            Dist::Poisson { lambda } => self.discrete_quantile(p, lambda + z * lambda.sqrt()),
+            Dist::Geom { p: pr } => self.discrete_quantile(p, ((-p).ln_1p() / (-pr).ln_1p()).ceil()),
+            Dist::Hyge { total, good, n } => {
+                let mean = n * good / total;
+                let var = mean * (total - good) / total * (total - n) / (total - 1.0).max(1.0);
+                self.discrete_quantile(p, mean + z * var.sqrt())
+            }
+        }
+    }
+
+    /// Newton sobre ln(cola) con salvaguarda por bisección (o expansión si el intervalo
+    /// es infinito). Se trabaja con la cola que contiene a p para no perder precisión.
+    fn invert(&self, p: f64, guess: f64) -> f64 {
+        let lower = p <= 0.5;
+        let target = if lower { p } else { 1.0 - p };
+        let x = self.newton(lower, target, guess);
+        // Si la cola se anula en x, el cuantil está más allá del rango de f64
+        let (l, u) = self.tails(x);
+        let (lo, hi) = self.support();
+        match (lower, if lower { l } else { u } == 0.0) {
+            (true, true) => lo,
+            (false, true) => hi,
+            _ => x,
+        }
+    }
+
+    /// Dos pasos de Halley sobre la cola que contiene a p para pulir el cuantil de la t en
+    /// las colas extremas (p ≈ 1e-12), donde Newton sobre ln(cola) para con error relativo
+    /// de unos pocos ulp de la cola. f'(x) = −f(x)·(ν+1)·x/(ν+x²).
+    fn halley(&self, p: f64, mut x: f64) -> f64 {
+        let Dist::T { df } = *self else { return x };
+        let lower = p <= 0.5;
+        let target = if lower { p } else { 1.0 - p };
+        for _ in 0..2 {
+            let (l, u) = self.tails(x);
+            let d = self.density(x);
+            if !x.is_finite() || d == 0.0 {
+                break;
+            }
+            // g = cola − objetivo, con g' = ±f y g'' = ±f'
+            let (g, g1) = if lower { (l - target, d) } else { (u - target, -d) };
+            let g2 = -g1 * (df + 1.0) * x / (df + x * x);
+            let step = g / g1 / (1.0 - g * g2 / (2.0 * g1 * g1));
+            if !step.is_finite() || step.abs() > 0.5 * x.abs().max(1.0) {
+                break;
+            }
+            x -= step;
+        }
+        x
+    }
+
+    fn newton(&self, lower: bool, target: f64, guess: f64) -> f64 {
+        let (mut lo, mut hi) = self.support();
+        let mut x = if guess > lo && guess < hi { guess } else if lo.is_finite() { lo + 1.0 } else { 0.0 };
+        for _ in 0..2000 {
+            let (l, u) = self.tails(x);
+            let t = if lower { l } else { u };
+            if t == target {
+                return x;
+            }
+            // La cola inferior crece con x; la superior decrece
+            if (t > target) == lower { hi = x } else { lo = x }
+            let d = self.density(x);
+            let slope = if lower { d } else { -d };
+            let mut next = x - (t.ln() - target.ln()) * t / slope;
+            if !(next > lo && next < hi) {
+                next = if lo.is_finite() && hi.is_finite() {
+                    0.5 * (lo + hi)
+                } else if hi.is_finite() {
+                    hi - 2.0 * (hi.abs()).max(1.0)
+                } else {
+                    lo + 2.0 * (lo.abs()).max(1.0)
+                };
+            }
+            if (next - x).abs() <= 2.0 * f64::EPSILON * x.abs() || next == lo || next == hi {
+                return next;
+            }
+            x = next;
+        }
+        x
+    }
+
+    /// Menor entero k del soporte con P(X ≤ k) ≥ p: búsqueda exponencial desde la
+    /// aproximación normal y después bisección.
+    fn discrete_quantile(&self, p: f64, guess: f64) -> f64 {
+        let (lo, hi) = self.support();
+        let reached = |k: f64| {
+            let (l, u) = self.tails(k);
+            if p <= 0.5 { l >= p * (1.0 - 64.0 * f64::EPSILON) } else { u <= (1.0 - p) * (1.0 + 64.0 * f64::EPSILON) }
+        };
+        let g = if guess.is_finite() { guess.round().clamp(lo, hi) } else { lo };
+        // Invariante: !reached(a) (o a < lo), reached(b)
+        let (mut a, mut b);
+        let mut step = 1.0;
+        if reached(g) {
+            b = g;
+            loop {
+                a = b - step;
+                if a < lo {
+                    a = lo - 1.0;
+                    break;
+                }
+                if !reached(a) {
+                    break;
+                }
+                b = a;
+                step *= 2.0;
+            }
+        } else {
+            a = g;
+            loop {
+                b = a + step;
+                if b >= hi {
+                    b = hi;
+                    break;
+                }
+                if reached(b) {
+                    break;
+                }
+                a = b;
+                step *= 2.0;
+            }
+        }
+        while b - a > 1.0 {
+            let m = ((a + b) / 2.0).floor();
+            if reached(m) { b = m } else { a = m }
+        }
+        b
+    }
+}
+
+/// Colas (Φ(z), 1 − Φ(z)) de la normal estándar vía Q(½, z²/2) = erfc(|z|/√2).
+fn norm_tails(z: f64) -> (f64, f64) {
+    let (p, q) = gamma_inc(0.5, 0.5 * z * z);
+    let (small, big) = (0.5 * q, 0.5 + 0.5 * p);
+    if z < 0.0 { (small, big) } else { (big, small) }
+}
+
+/// Aproximación de Acklam al cuantil normal estándar (error relativo ~1e-9); solo
+/// sirve de punto de partida para `invert`.
+fn norm_quantile_guess(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2, 1.383_577_518_672_69e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2, 6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838, -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    let tail = |q: f64| {
+        let r = (-2.0 * q.ln()).sqrt();
+        (((((C[0] * r + C[1]) * r + C[2]) * r + C[3]) * r + C[4]) * r + C[5]) / ((((D[0] * r + D[1]) * r + D[2]) * r + D[3]) * r + 1.0)
+    };
+    if p < 0.02425 {
+        tail(p)
+    } else if p > 1.0 - 0.02425 {
+        -tail(1.0 - p)
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Densidad t de Student sin cancelación en Γ((ν+1)/2)/Γ(ν/2) para ν grande (Loader).
+fn t_density(x: f64, df: f64) -> f64 {
+    use super::special::{bd0, stirlerr};
+    let t = -bd0(df / 2.0, (df + 1.0) / 2.0) + stirlerr((df + 1.0) / 2.0) - stirlerr(df / 2.0);
+    let x2n = x * x / df;
+    let (u, inv_sqrt) = if x2n > 1.0 / f64::EPSILON {
+        let l = x.abs().ln() - df.ln() / 2.0;
+        (df * l, df.sqrt() / x.abs())
+    } else if x2n > 0.2 {
+        let l = x2n.ln_1p() / 2.0;
+        (df * l, (-l).exp())
+    } else {
+        let l = x2n.ln_1p() / 2.0;
+        (-bd0(df / 2.0, (df + x * x) / 2.0) + x * x / 2.0, (-l).exp())
+    };
+    (t - u).exp() / SQRT_2PI * inv_sqrt
+}
+
+/// Densidad gamma de forma `shape` y escala `scale` (la χ² es shape = k/2, scale = 2).
+fn gamma_density(x: f64, shape: f64, scale: f64) -> f64 {
+    if x < 0.0 {
+        return 0.0;
+    }
+    if x == 0.0 {
+        return if shape < 1.0 { f64::INFINITY } else if shape == 1.0 { 1.0 / scale } else { 0.0 };
+    }
+    if shape < 1.0 {
+        dpois_raw(shape, x / scale) * shape / x
+    } else {
+        dpois_raw(shape - 1.0, x / scale) / scale
+    }
+}
+
+/// Densidad F(d1, d2) expresada como binomial "saddle point" (como en R).
+fn f_density(x: f64, m: f64, n: f64) -> f64 {
+    if x < 0.0 {
+        return 0.0;
+    }
+    if x == 0.0 {
+        return if m > 2.0 { 0.0 } else if m == 2.0 { 1.0 } else { f64::INFINITY };
+    }
+    let f = 1.0 / (n + x * m);
+    let q = n * f;
+    let p = x * m * f;
+    if m >= 2.0 {
+        m * q / 2.0 * dbinom_raw((m - 2.0) / 2.0, (m + n - 2.0) / 2.0, p, q)
+    } else {
+        m * m * q / (2.0 * p * (m + n)) * dbinom_raw(m / 2.0, (m + n) / 2.0, p, q)
+    }
+}
+
+fn family_params(func: &str, family: &str) -> Result<&'static [&'static str], String> {
+    FAMILIES
+        .iter()
+        .find(|(f, _)| *f == family)
+        .map(|(_, p)| *p)
+        .ok_or_else(|| format!("Distribución '{}' desconocida", func))
+}
+
+fn parse_call<'a>(func: &'a str, args: &[f64]) -> Result<(Dist, &'a str, f64), String> {
+    let (family, kind) = split_name(func).ok_or_else(|| format!("Distribución '{}' desconocida", func))?;
+    let params = family_params(func, family)?;
+    let optional = family == "norm" && args.len() == 1;
+    if args.len() != params.len() + 1 && !optional {
+        let first = match kind {
+            "inv" => "p",
+            "pmf" => "k",
+            _ if matches!(family, "binom" | "poiss" | "geom" | "hyge") => "k",
+            _ => "x",
+        };
+        return Err(format!("Uso: {}({}, {})", func, first, params.join(", ")));
+    }
+    let dist = Dist::new(func, family, &args[1..])?;
+    Ok((dist, kind, args[0]))
+}
+
+/// Muestra de `<familia>rnd(parámetros...)` por inversión de la cdf en `u` ∈ (0, 1).
+pub fn sample(func: &str, params: &[f64], u: f64) -> Result<f64, String> {
+    let family = func.strip_suffix("rnd").unwrap_or(func);
+    let names = family_params(func, family)?;
+    if params.len() != names.len() && !(family == "norm" && params.is_empty()) {
+        return Err(format!("Uso: {}({})", func, names.join(", ")));
+    }
+    Ok(Dist::new(func, family, params)?.quantile(u))
+}
+
+/// Evalúa `func(x, parámetros...)` para una función de `DIST_FUNCS`.
+pub fn eval_dist(func: &str, args: &[f64]) -> Result<f64, String> {
+    let (dist, kind, x) = parse_call(func, args)?;
+    match kind {
+        "cdf" => Ok(dist.tails(x).0),
+        "inv" => {
+            check((0.0..=1.0).contains(&x), func, "la probabilidad debe estar en [0, 1]")?;
+            Ok(dist.quantile(x))
+        }
+        _ => Ok(dist.density(x)),
+    }
+}
+
+/// Derivada direccional Σ ∂f/∂aᵢ·daᵢ respecto a x (el primer argumento) y a los parámetros,
+/// si tiene regla exacta: respecto a x la `cdf` deriva en la densidad y el cuantil en su
+/// inversa; de los parámetros, los de la normal. `Ok(None)` si falta alguna regla.
+pub fn derivative(func: &str, args: &[f64], dargs: &[f64]) -> Result<Option<f64>, String> {
+    let (dist, kind, x) = parse_call(func, args)?;
+    let dx = dargs[0];
+    if dist.is_discrete() && dx != 0.0 {
+        return Err(format!("Derivada de '{}' respecto a k no disponible (distribución discreta)", func));
+    }
+    let wrt_x = match (kind, dist) {
+        _ if dx == 0.0 => 0.0,
+        ("cdf", _) => dist.density(x) * dx,
+        ("inv", _) => dx / dist.density(dist.quantile(x)),
+        ("pdf", Dist::Normal { mu, sigma }) => -(x - mu) / (sigma * sigma) * dist.density(x) * dx,
+        _ => return Ok(None),
+    };
+    if dargs[1..].iter().all(|d| *d == 0.0) {
+        return Ok(Some(wrt_x));
+    }
+    let Dist::Normal { mu, sigma } = dist else {
+        return Ok(None);
+    };
+    // z = (x − μ)/σ: Φ(z) y σ⁻¹φ(z) dependen de μ y σ solo a través de z; x_p = μ + σ·z_p
+    let (dmu, dsigma) = match kind {
+        "cdf" => {
+            let f = dist.density(x);
+            (-f, -(x - mu) / sigma * f)
+        }
+        "inv" => (1.0, (dist.quantile(x) - mu) / sigma),
+        _ => {
+            let (f, z) = (dist.density(x), (x - mu) / sigma);
+            (z / sigma * f, (z * z - 1.0) / sigma * f)
+        }
+    };
+    Ok(Some(wrt_x + dmu * dargs[1] + dsigma * dargs.get(2).copied().unwrap_or(0.0)))
+}
@@ -0,0 +1,274 @@
+use super::{bessel, distributions::{self, DIST_FUNCS}, eval::apply_func, finance::{self, FIN_FUNCS}, special, stats::{self, STAT_FUNCS}};
+use super::AngleUnit;
+use num_complex::{Complex64, ComplexFloat};
+
+/// Número dual `val + Σ eps[k]·εk`: valor y derivadas exactas respecto a cada semilla.
+/// Un `eps` vacío equivale a una constante (todas las derivadas nulas).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dual {
+    pub val: Complex64,
+    pub eps: Vec<Complex64>,
+}
+
+impl Dual {
+    pub fn constant(val: Complex64) -> Self {
+        Dual { val, eps: Vec::new() }
+    }
+
+    /// Variable independiente número `k` de `n`: derivada 1 en su propia componente.
+    pub fn seed(val: Complex64, k: usize, n: usize) -> Self {
+        let mut eps = vec![Complex64::new(0.0, 0.0); n];
+        eps[k] = Complex64::new(1.0, 0.0);
+        Dual { val, eps }
+    }
+
+    /// Derivada respecto a la semilla `k` (0 si no depende de ella).
+    pub fn d(&self, k: usize) -> Complex64 {
+        self.eps.get(k).copied().unwrap_or(Complex64::new(0.0, 0.0))
+    }
+}
+
+/// Calcula las componentes de derivada del resultado `r = func(args)` por la regla de la cadena.
+pub(crate) fn propagate(func: &str, args: &[Dual], r: Complex64, unit: AngleUnit) -> Result<Vec<Complex64>, String> {
+    let n = args.iter().map(|a| a.eps.len()).max().unwrap_or(0);
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    let a: Vec<Complex64> = args.iter().map(|d| d.val).collect();
+    let mut eps = Vec::with_capacity(n);
+    for k in 0..n {
+        let da: Vec<Complex64> = args.iter().map(|d| d.d(k)).collect();
+        let t = if da.iter().all(|v| *v == Complex64::new(0.0, 0.0)) {
+            Complex64::new(0.0, 0.0)
+        } else {
+            tangent(func, &a, r, &da, unit)?
+        };
+        if !t.re.is_finite() || !t.im.is_finite() {
+            return Err(format!("'{}' no es derivable en ese punto", func));
+        }
+        eps.push(t);
+    }
+    Ok(eps)
+}
+
+fn real(x: f64) -> Complex64 {
+    Complex64::new(x, 0.0)
+}
+
+/// Derivada direccional de la parte real de `func` por diferencias centradas con un paso de
+/// Richardson, argumento a argumento, para lo que no tiene regla exacta (parámetros de la
+/// mayoría de distribuciones, orden de las funciones de Bessel).
+fn central_difference(func: &str, a: &[Complex64], da: &[Complex64], unit: AngleUnit) -> Result<Complex64, String> {
+    let re: Vec<f64> = a.iter().map(|v| v.re).collect();
+    let at = |k: usize, t: f64| -> Result<f64, String> {
+        let mut vals: Vec<Complex64> = re.iter().map(|&v| real(v)).collect();
+        vals[k] += t;
+        apply_func(&mut vals, func, unit)?;
+        vals.pop().map(|v| v.re).ok_or_else(|| format!("Derivada de '{}' no disponible", func))
+    };
+    let mut acc = 0.0;
+    for (k, d) in da.iter().enumerate().filter(|(_, d)| d.re != 0.0) {
+        let h = 1e-3 * (1.0 + re[k].abs());
+        let diff = |h: f64| Ok::<f64, String>((at(k, h)? - at(k, -h)?) / (2.0 * h));
+        acc += (4.0 * diff(h / 2.0)? - diff(h)?) / 3.0 * d.re;
+    }
+    Ok(real(acc))
+}
+
+/// Derivada direccional de `func` en `a` a lo largo de `da` (mismo orden de argumentos que
+/// en la expresión). `r` es el valor ya calculado por `apply_func`.
+/// Las reglas replican exactamente la semántica de `apply_func`: los ángulos se escalan por la
+/// unidad activa, y las funciones que trabajan sobre la parte real derivan solo esa parte.
+fn tangent(func: &str, a: &[Complex64], r: Complex64, da: &[Complex64], unit: AngleUnit) -> Result<Complex64, String> {
+    let zero = Complex64::new(0.0, 0.0);
+    let one = Complex64::new(1.0, 0.0);
+    // Término `partial * d` que ignora las derivadas nulas (evita 0·∞ = NaN)
+    let term = |partial: Complex64, d: Complex64| if d == zero { zero } else { partial * d };
+    let deg_in = |d: Complex64| unit.to_rad(d);
+    let deg_out = |d: Complex64| unit.express_rad(d);
+    let x = a[a.len() - 1];
+    let dx = da[da.len() - 1];
+
+    // Estadística: sobre la parte real de los datos
+    if STAT_FUNCS.contains(&func) {
+        let data: Vec<f64> = a.iter().map(|v| v.re).collect();
+        let d: Vec<f64> = da.iter().map(|v| v.re).collect();
+        return stats::stat_derivative(func, &data, &d).map(real);
+    }
+    // Distribuciones: reglas exactas donde las hay y diferencias centradas en el resto
+    if DIST_FUNCS.contains(&func) {
+        let args: Vec<f64> = a.iter().map(|v| v.re).collect();
+        let dargs: Vec<f64> = da.iter().map(|v| v.re).collect();
+        return match distributions::derivative(func, &args, &dargs)? {
+            Some(d) => Ok(real(d)),
+            None => central_difference(func, a, da, unit),
+        };
+    }
+    // Finanzas: diferencias centradas sobre la parte real
+    if FIN_FUNCS.contains(&func) {
+        let args: Vec<f64> = a.iter().map(|v| v.re).collect();
+        let dargs: Vec<f64> = da.iter().map(|v| v.re).collect();
+        return finance::derivative(func, &args, &dargs).map(real);
+    }
+
+    let res = match func {
+        // --- Trigonométricas ---
+        "sin" => unit.to_rad(x).cos() * deg_in(dx),
+        "cos" => -unit.to_rad(x).sin() * deg_in(dx),
+        "tan" => (one + r * r) * deg_in(dx),
+        "asin" => deg_out(dx / (one - x * x).sqrt()),
+        "acos" => deg_out(-dx / (one - x * x).sqrt()),
+        "atan" => deg_out(dx / (one + x * x)),
+        "atan2" => {
+            let (y, xx, dy, dxx) = (a[0].re, a[1].re, da[0].re, da[1].re);
+            deg_out(real((xx * dy - y * dxx) / (xx * xx + y * y)))
+        }
+        "hypot" => {
+            // Derivada direccional de √(|p|² + |q|²) (no holomorfa, como abs)
+            if r == zero {
+                return Err("hypot no es derivable en (0, 0)".to_string());
+            }
+            real(((a[0].conj() * da[0]).re + (a[1].conj() * da[1]).re) / r.re)
+        }
+
+        // --- Hiperbólicas ---
+        "sinh" => x.cosh() * dx,
+        "cosh" => x.sinh() * dx,
+        "tanh" => (one - r * r) * dx,
+        "asinh" => dx / (x * x + one).sqrt(),
+        "acosh" => dx / ((x - one).sqrt() * (x + one).sqrt()),
+        "atanh" => dx / (one - x * x),
+
+        // --- Raíces y Logaritmos ---
+        "sqrt" => dx / (r * 2.0),
+        "cbrt" => dx * r / (x * 3.0),
+        "root" => {
+            let n = a[0];
+            term(r / (x * n), dx) + term(-r * x.ln() / (n * n), da[0])
+        }
+        "ln" => dx / x,
+        "log10" => dx / (x * std::f64::consts::LN_10),
+        "log2" => dx / (x * std::f64::consts::LN_2),
+        "log" => {
+            let lb = a[0].ln();
+            term(one / (x * lb), dx) + term(-r / (a[0] * lb), da[0])
+        }
+        "exp" => r * dx,
+        "cis" => Complex64::i() * r * deg_in(dx),
+        // La incertidumbre de a ± σ la añade `Calculator::add_source`
+        "±" => da[0],
+        "∠" => {
+            // r∠θ = r·e^(iθ): ∂/∂r = e^(iθ), ∂/∂θ = i·r∠θ
+            let unit = if a[0] == zero { Complex64::from_polar(1.0, unit.to_rad(x).re) } else { r / a[0] };
+            term(unit, real(da[0].re)) + term(Complex64::i() * r, deg_in(real(dx.re)))
+        }
+        "pow" | "^" => {
+            let (base, ex) = (a[0], a[1]);
+            let em1 = ex - one;
+            // Exponente entero real: potencia exacta por multiplicación
+            let p = if em1.im == 0.0 && em1.re.fract() == 0.0 && em1.re.abs() <= i32::MAX as f64 {
+                base.powi(em1.re as i32)
+            } else {
+                base.powc(em1)
+            };
+            term(ex * p, da[0]) + term(r * base.ln(), da[1])
+        }
+
+        // --- Redondeo, signo y teoría de números: constantes a trozos ---
+        "floor" | "ceil" | "round" | "trunc" | "sign" | "isprime" | "nextprime" | "mcd" | "mcm"
+        | "prevprime" | "primepi" | "nthprime" | "totient" | "sigma" | "moebius" | "isperfect"
+        | "powmod" | "invmod" | "jacobi" | "legendre" | "sqrtmod"
+        | "catalan" | "stirling1" | "stirling2" | "bell" | "partitions" | "multinomial" | "derangements" | "fib" | "lucas"
+        | "comb" | "nCr" | "perm" | "nPr" => zero,
+
+        // --- Funciones especiales ---
+        "fact" => r * special::digamma(x + one)? * dx,
+        "gamma" => r * special::digamma(x)? * dx,
+        "lgamma" => special::digamma(x)? * dx,
+        "beta" => {
+            let psi_ab = special::digamma(a[0] + a[1])?;
+            term(r * (special::digamma(a[0])? - psi_ab), da[0]) + term(r * (special::digamma(a[1])? - psi_ab), da[1])
+        }
+        "erf" => real(2.0 / std::f64::consts::PI.sqrt() * (-x.re * x.re).exp() * dx.re),
+        "erfc" => real(-2.0 / std::f64::consts::PI.sqrt() * (-x.re * x.re).exp() * dx.re),
+        "erfinv" => real(0.5 * std::f64::consts::PI.sqrt() * (r.re * r.re).exp() * dx.re),
+        "besselj" | "bessely" | "besseli" | "besselk" => {
+            // Respecto al orden, diferencias centradas; respecto a x, Z'_ν = (Z_(ν−1) ∓ Z_(ν+1))/2 (K con signo global negativo)
+            let (nu, xr) = (a[0].re, x.re);
+            let kind = func.chars().last().unwrap_or('j');
+            let z = |n: f64| if matches!(kind, 'j' | 'y') { bessel::bessel_jy(kind, n, xr) } else { bessel::bessel_ik(kind, n, xr) };
+            let d = match kind {
+                'i' => 0.5 * (z(nu - 1.0)? + z(nu + 1.0)?),
+                'k' => -0.5 * (z(nu - 1.0)? + z(nu + 1.0)?),
+                _ => 0.5 * (z(nu - 1.0)? - z(nu + 1.0)?),
+            };
+            real(d * dx.re) + central_difference(func, a, &[da[0], zero], unit)?
+        }
+        "airy" => {
+            // Ai'' = x·Ai, Bi'' = x·Bi
+            let k = if a.len() == 2 { a[0].re as u8 } else { 0 };
+            let d = if k % 2 == 0 { bessel::airy(k + 1, x.re) } else { x.re * bessel::airy(k - 1, x.re) };
+            real(d * dx.re)
+        }
+        "lambertw" => if x == zero { dx } else { r / (x * (one + r)) * dx },
+        "ellipk" | "ellipe" => {
+            let m = x.re;
+            let (k, e) = special::ellip_ke(m)?;
+            let d = match (func, m == 0.0) {
+                ("ellipk", true) => std::f64::consts::PI / 8.0,
+                ("ellipe", true) => -std::f64::consts::PI / 8.0,
+                ("ellipk", false) => (e - (1.0 - m) * k) / (2.0 * m * (1.0 - m)),
+                _ => (e - k) / (2.0 * m),
+            };
+            real(d * dx.re)
+        }
+
+        // --- Conversiones (factores de escala) ---
+        "deg2rad" => AngleUnit::Deg.to_rad(dx),
+        "rad2deg" => AngleUnit::Deg.express_rad(dx),
+        "cm2in" => dx / 2.54,
+        "in2cm" => dx * 2.54,
+        "m2ft" => dx * 3.280_839_895,
+        "ft2m" => dx / 3.280_839_895,
+
+        // --- Complejos (derivada direccional, no holomorfas) ---
+        "abs" => {
+            if x == zero {
+                return Err("abs no es derivable en 0".to_string());
+            }
+            real((x.conj() * dx).re / x.norm())
+        }
+        "arg" => real((dx / x).im),
+        "conj" => dx.conj(),
+        "re" => real(dx.re),
+        "im" => real(dx.im),
+
+        // --- Estadística / Pct ---
+        "min" | "max" => if r == a[0] { da[0] } else { da[1] },
+        "pct" => term(100.0 / a[1], da[0]) + term(-r / a[1], da[1]),
+        "applypct" => term(a[1] / 100.0, da[0]) + term(a[0] / 100.0, da[1]),
+
+        // --- Regla de tres ---
+        "r3d" => term(a[1] / a[0], da[2]) + term(a[2] / a[0], da[1]) + term(-r / a[0], da[0]),
+        "r3i" => term(a[1] / a[2], da[0]) + term(a[0] / a[2], da[1]) + term(-r / a[2], da[2]),
+
+        // --- Bases: devuelven el propio valor ---
+        "bin" | "oct" | "hex" | "dms" => dx,
+        "deg" | "rad" | "grad" => match AngleUnit::parse(func) {
+            Some(from) => from.convert(dx, unit),
+            None => return Err(format!("Derivada de '{}' no disponible", func)),
+        },
+
+        // --- Operadores ---
+        "+" => da[0] + da[1],
+        "-" => da[0] - da[1],
+        "*" => term(a[1], da[0]) + term(a[0], da[1]),
+        "/" => term(one / a[1], da[0]) + term(-r / a[1], da[1]),
+        "%" | "mod" => real(da[0].re - (a[0].re / a[1].re).floor() * da[1].re),
+
+        // Sin regla exacta: diferencias centradas, solo válidas en el eje real
+        _ if a.iter().chain(da).all(|v| v.im == 0.0) => central_difference(func, a, da, unit)?,
+        _ => return Err(format!("Derivada de '{}' no disponible", func)),
+    };
+    Ok(res)
+}
@@ -1,9 +1,12 @@
-use super::{token::Token, Calculator};
+use super::{bessel, combinatorics::{self, COMB_FUNCS}, distributions::{eval_dist, DIST_FUNCS}, dual::{self, Dual}, finance::{eval_fin, FIN_FUNCS}, format::format_real, lexer::is_identifier, measure::Uncertain, numtheory::{exact_int, int_call, INT_FUNCS}, random::RANDOM_FUNCS, special, stats::{real_data, stat, STAT_FUNCS}, token::Token, AngleUnit, Calculator, UserFunc};
 use num_complex::{Complex64, ComplexFloat};
-use crate::math_ext::{comb_u128, is_prime_u128, next_prime_u128, perm_u128};
+use num_traits::ToPrimitive;
 use num_integer::gcd as num_gcd;
 use num_integer::lcm as num_lcm;
 
+/// Profundidad máxima de variables locales anidadas (llamadas a funciones de usuario).
+const MAX_CALL_DEPTH: usize = 256;
+
 fn push_checked(vals: &mut Vec<Complex64>, v: Complex64) -> Result<(), String> {
     if !v.re.is_finite() || !v.im.is_finite() {
         Err("Resultado no finito (NaN/Inf). Revisa dominio/división por 0.".to_string())
@@ -26,6 +29,31 @@ fn safe_unsigned_limit(x: Complex64, limit: u64) -> Result<u64, String> {
     Ok(val)
 }
 
+/// Argumento real para funciones que no admiten complejos.
+fn real_arg(x: Complex64, func: &str) -> Result<f64, String> {
+    if x.im.abs() > 1e-10 {
+        return Err(format!("{} requiere un argumento real", func));
+    }
+    Ok(x.re)
+}
+
+/// Par de argumentos reales para funciones de dos argumentos que no admiten complejos.
+fn real_args(a: Complex64, b: Complex64, func: &str) -> Result<(f64, f64), String> {
+    if a.im.abs() > 1e-10 || b.im.abs() > 1e-10 {
+        return Err(format!("{} requiere argumentos reales", func));
+    }
+    Ok((a.re, b.re))
+}
+
+/// Orden o rama entera opcional (primer argumento de `airy(k, x)`, `lambertw(k, x)`).
+fn int_arg(x: Complex64, func: &str) -> Result<i64, String> {
+    let v = real_arg(x, func)?;
+    if v.fract() != 0.0 || v.abs() > 1e9 {
+        return Err(format!("{}: el primer argumento debe ser un entero", func));
+    }
+    Ok(v as i64)
+}
+
 fn safe_i64(x: Complex64) -> Result<i64, String> {
     let xr = x.re.round();
     if !xr.is_finite() || xr > i64::MAX as f64 || xr < i64::MIN as f64 {
@@ -34,34 +62,66 @@ fn safe_i64(x: Complex64) -> Result<i64, String> {
     Ok(xr as i64)
 }
 
-fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(), String> {
+pub(crate) fn apply_func(vals: &mut Vec<Complex64>, func: &str, unit: AngleUnit) -> Result<(), String> {
+    // --- Estadística: variádicas, consumen todos los argumentos ---
+    if STAT_FUNCS.contains(&func) {
+        let data = real_data(vals)?;
+        vals.clear();
+        return push_checked(vals, Complex64::new(stat(func, &data)?, 0.0));
+    }
+    // --- Distribuciones de probabilidad: argumentos reales ---
+    if DIST_FUNCS.contains(&func) {
+        if vals.iter().any(|v| v.im.abs() > 1e-10) {
+            return Err(format!("{} requiere argumentos reales", func));
+        }
+        let args: Vec<f64> = vals.drain(..).map(|v| v.re).collect();
+        return push_checked(vals, Complex64::new(eval_dist(func, &args)?, 0.0));
+    }
+    // --- Finanzas (valor temporal del dinero): argumentos reales ---
+    if FIN_FUNCS.contains(&func) {
+        if vals.iter().any(|v| v.im.abs() > 1e-10) {
+            return Err(format!("{} requiere argumentos reales", func));
+        }
+        let args: Vec<f64> = vals.drain(..).map(|v| v.re).collect();
+        return push_checked(vals, Complex64::new(eval_fin(func, &args)?, 0.0));
+    }
+
+    // --- Teoría de números: enteros exactos ---
+    if INT_FUNCS.contains(&func) {
+        let args = vals.drain(..).map(|v| exact_int(v, func)).collect::<Result<Vec<_>, _>>()?;
+        return push_checked(vals, Complex64::new(int_call(func, &args)? as f64, 0.0));
+    }
+    if COMB_FUNCS.contains(&func) || matches!(func, "perm" | "nPr") {
+        let v = combinatorics::eval(func, vals)?;
+        vals.clear();
+        return push_checked(vals, Complex64::new(v, 0.0));
+    }
+
     let b = vals.pop().ok_or("Faltan argumentos")?;
 
     match func {
         // --- Trigonométricas ---
-        "sin" => push_checked(vals, if is_rad { b.sin() } else { Complex64::new(b.re.to_radians(), b.im).sin() })?,
-        "cos" => push_checked(vals, if is_rad { b.cos() } else { Complex64::new(b.re.to_radians(), b.im).cos() })?,
-        "tan" => push_checked(vals, if is_rad { b.tan() } else { Complex64::new(b.re.to_radians(), b.im).tan() })?,
-        "asin" => {
-            let res = b.asin();
-            push_checked(vals, if is_rad { res } else { Complex64::new(res.re.to_degrees(), res.im) })?
-        }
-        "acos" => {
-            let res = b.acos();
-            push_checked(vals, if is_rad { res } else { Complex64::new(res.re.to_degrees(), res.im) })?
-        }
-        "atan" => {
-            let res = b.atan();
-            push_checked(vals, if is_rad { res } else { Complex64::new(res.re.to_degrees(), res.im) })?
-        }
+        "sin" => push_checked(vals, unit.to_rad(b).sin())?,
+        "cos" => push_checked(vals, unit.to_rad(b).cos())?,
+        "tan" => push_checked(vals, unit.to_rad(b).tan())?,
+        "asin" => push_checked(vals, unit.express_rad(b.asin()))?,
+        "acos" => push_checked(vals, unit.express_rad(b.acos()))?,
+        "atan" => push_checked(vals, unit.express_rad(b.atan()))?,
         "atan2" => {
             let a = vals.pop().ok_or("atan2 requiere 2 argumentos (y, x)")?;
-            let res = Complex64::new(a.re.atan2(b.re), 0.0);
-            push_checked(vals, if is_rad { res } else { Complex64::new(res.re.to_degrees(), 0.0) })?
+            let (y, x) = real_args(a, b, func)?;
+            push_checked(vals, unit.express_rad(Complex64::new(y.atan2(x), 0.0)))?
+        }
+        // Ángulo en una unidad concreta (sufijos 30°, 1rad, 50grad), pasado a la activa
+        "deg" | "rad" | "grad" => {
+            let from = AngleUnit::parse(func).ok_or("Unidad angular desconocida")?;
+            push_checked(vals, from.convert(b, unit))?
         }
+        "dms" => push_checked(vals, b)?,
         "hypot" => {
+            // Norma euclídea √(|x|² + |y|²), también con complejos
             let a = vals.pop().ok_or("hypot requiere 2 argumentos (x, y)")?;
-            push_checked(vals, Complex64::new(a.re.hypot(b.re), 0.0))?
+            push_checked(vals, Complex64::new(a.norm().hypot(b.norm()), 0.0))?
         }
 
         // --- Hiperbólicas ---
@@ -74,41 +134,50 @@ fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(),
 
         // --- Raíces y Logaritmos ---
         "sqrt" => push_checked(vals, b.sqrt())?,
-        "cbrt" => push_checked(vals, b.powf(1.0/3.0))?,
+        // Raíz cúbica real para reales (como `cbrt` de C); principal para complejos
+        "cbrt" => push_checked(vals, if b.im == 0.0 { Complex64::new(b.re.cbrt(), 0.0) } else { b.powf(1.0/3.0) })?,
         "root" => {
             let a = vals.pop().ok_or("root requiere 2 argumentos (n, x)")?;
-            push_checked(vals, b.powc(Complex64::new(1.0/a.re, 0.0)))?
+            if a == Complex64::new(0.0, 0.0) { return Err("root: el índice no puede ser 0".to_string()); }
+            push_checked(vals, b.powc(a.inv()))?
         }
         "ln" => {
             if b == Complex64::new(0.0, 0.0) { return Err("ln(0) indefinido".to_string()); }
             push_checked(vals, b.ln())?
         }
-        "log10" => push_checked(vals, Complex64::new(b.re.log10(), 0.0))?, // Basado en parte real
-        "log2" => push_checked(vals, Complex64::new(b.re.log2(), 0.0))?,
+        // Rama principal, como ln: log10(-1) = iπ/ln 10
+        "log10" | "log2" => {
+            if b == Complex64::new(0.0, 0.0) { return Err(format!("{}(0) indefinido", func)); }
+            let base = if func == "log10" { std::f64::consts::LN_10 } else { std::f64::consts::LN_2 };
+            push_checked(vals, b.ln() / base)?
+        }
         "log" => {
             let a = vals.pop().ok_or("log requiere 2 argumentos (base, n)")?;
             push_checked(vals, b.ln() / a.ln())?
         }
         "exp" => push_checked(vals, b.exp())?,
+        "cis" => push_checked(vals, (Complex64::i() * unit.to_rad(b)).exp())?,
         "pow" | "^" => {
             let a = vals.pop().ok_or("Falta operando base")?;
-            push_checked(vals, a.powc(b))?
+            // Con base y exponente reales, powf cuando el resultado es real (sin residuo imaginario de powc)
+            let real_pow = a.im == 0.0 && b.im == 0.0 && (a.re >= 0.0 || b.re.fract() == 0.0);
+            push_checked(vals, if real_pow { Complex64::new(a.re.powf(b.re), 0.0) } else { a.powc(b) })?
         }
 
-        // --- Redondeo y Signo (sobre parte Real) ---
-        "floor" => push_checked(vals, Complex64::new(b.re.floor(), 0.0))?,
-        "ceil" => push_checked(vals, Complex64::new(b.re.ceil(), 0.0))?,
-        "round" => push_checked(vals, Complex64::new(b.re.round(), 0.0))?,
-        "trunc" => push_checked(vals, Complex64::new(b.re.trunc(), 0.0))?,
-        "sign" => push_checked(vals, Complex64::new(b.re.signum(), 0.0))?,
+        // --- Redondeo (componente a componente) y signo (z/|z|) ---
+        "floor" => push_checked(vals, Complex64::new(b.re.floor(), b.im.floor()))?,
+        "ceil" => push_checked(vals, Complex64::new(b.re.ceil(), b.im.ceil()))?,
+        "round" => push_checked(vals, Complex64::new(b.re.round(), b.im.round()))?,
+        "trunc" => push_checked(vals, Complex64::new(b.re.trunc(), b.im.trunc()))?,
+        "sign" => push_checked(vals, if b.im == 0.0 { Complex64::new(if b.re == 0.0 { 0.0 } else { b.re.signum() }, 0.0) } else { b / b.norm() })?,
 
-        // --- Conversiones (sobre parte Real) ---
-        "deg2rad" => push_checked(vals, Complex64::new(b.re.to_radians(), 0.0))?,
-        "rad2deg" => push_checked(vals, Complex64::new(b.re.to_degrees(), 0.0))?,
-        "cm2in" => push_checked(vals, Complex64::new(b.re / 2.54, 0.0))?,
-        "in2cm" => push_checked(vals, Complex64::new(b.re * 2.54, 0.0))?,
-        "m2ft" => push_checked(vals, Complex64::new(b.re * 3.280_839_895, 0.0))?,
-        "ft2m" => push_checked(vals, Complex64::new(b.re / 3.280_839_895, 0.0))?,
+        // --- Conversiones (factores de escala) ---
+        "deg2rad" => push_checked(vals, AngleUnit::Deg.to_rad(b))?,
+        "rad2deg" => push_checked(vals, AngleUnit::Deg.express_rad(b))?,
+        "cm2in" => push_checked(vals, b / 2.54)?,
+        "in2cm" => push_checked(vals, b * 2.54)?,
+        "m2ft" => push_checked(vals, b * 3.280_839_895)?,
+        "ft2m" => push_checked(vals, b / 3.280_839_895)?,
 
         // --- Complejos ---
         "abs"  => push_checked(vals, Complex64::new(b.abs(), 0.0))?,
@@ -117,66 +186,89 @@ fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(),
         "re"   => push_checked(vals, Complex64::new(b.re, 0.0))?,
         "im"   => push_checked(vals, Complex64::new(b.im, 0.0))?,
 
-        // --- Teoría de Números (parte Real) ---
-        "isprime" => {
-            let n = b.re.round();
-            let res = if n >= 0.0 && is_prime_u128(n as u128) { 1.0 } else { 0.0 };
-            push_checked(vals, Complex64::new(res, 0.0))?
-        }
-        "nextprime" => {
-            let n = b.re.round();
-            if n < 0.0 { return Err("nextprime requiere n >= 0".to_string()); }
-            push_checked(vals, Complex64::new(next_prime_u128(n as u128) as f64, 0.0))?
-        }
+        // --- Teoría de Números ---
         "mcd" => {
             let a = vals.pop().ok_or("mcd requiere 2 argumentos")?;
+            real_args(a, b, func)?;
             push_checked(vals, Complex64::new(num_gcd(safe_i64(a)?, safe_i64(b)?) as f64, 0.0))?
         }
         "mcm" => {
             let a = vals.pop().ok_or("mcm requiere 2 argumentos")?;
+            real_args(a, b, func)?;
             push_checked(vals, Complex64::new(num_lcm(safe_i64(a)?, safe_i64(b)?) as f64, 0.0))?
         }
 
         // --- Combinatoria ---
         "fact" => {
-            let n = safe_unsigned_limit(b, 170)?;
-            let mut acc = 1.0f64;
-            for i in 2..=n { acc *= i as f64; }
-            push_checked(vals, Complex64::new(acc, 0.0))?
+            // Producto exacto hasta 170!; fuera de ahí (no enteros, complejos) Γ(x+1)
+            if let Ok(n) = safe_unsigned_limit(b, 170) {
+                let mut acc = 1.0f64;
+                for i in 2..=n { acc *= i as f64; }
+                push_checked(vals, Complex64::new(acc, 0.0))?
+            } else {
+                push_checked(vals, special::gamma(b + 1.0).map_err(|_| "fact no está definido en los enteros negativos".to_string())?)?
+            }
+        }
+
+        // --- Funciones especiales ---
+        "gamma" => push_checked(vals, special::gamma(b)?)?,
+        "lgamma" => push_checked(vals, special::lgamma(b)?)?,
+        "digamma" => push_checked(vals, special::digamma(b)?)?,
+        "beta" => {
+            let a = vals.pop().ok_or("beta requiere 2 argumentos (a, b)")?;
+            push_checked(vals, special::beta(a, b)?)?
+        }
+        "zeta" => push_checked(vals, special::zeta(b)?)?,
+        "erf" => push_checked(vals, Complex64::new(special::erf(real_arg(b, func)?), 0.0))?,
+        "erfc" => push_checked(vals, Complex64::new(special::erfc(real_arg(b, func)?), 0.0))?,
+        "erfinv" => push_checked(vals, Complex64::new(special::erfinv(real_arg(b, func)?)?, 0.0))?,
+        "besselj" | "bessely" | "besseli" | "besselk" => {
+            let nu = vals.pop().ok_or(format!("{} requiere 2 argumentos (orden, x)", func))?;
+            let (nu, x) = (real_arg(nu, func)?, real_arg(b, func)?);
+            let kind = func.chars().last().unwrap_or('j');
+            let v = if matches!(kind, 'j' | 'y') { bessel::bessel_jy(kind, nu, x)? } else { bessel::bessel_ik(kind, nu, x)? };
+            push_checked(vals, Complex64::new(v, 0.0))?
+        }
+        "airy" => {
+            let k = match vals.pop() { Some(k) => int_arg(k, func)?, None => 0 };
+            if !(0..=3).contains(&k) {
+                return Err("airy(k, x): k debe ser 0 (Ai), 1 (Ai'), 2 (Bi) o 3 (Bi')".to_string());
+            }
+            push_checked(vals, Complex64::new(bessel::airy(k as u8, real_arg(b, func)?), 0.0))?
+        }
+        "lambertw" => {
+            let k = match vals.pop() { Some(k) => int_arg(k, func)?, None => 0 };
+            push_checked(vals, special::lambert_w(k, b)?)?
+        }
+        "ellipk" | "ellipe" => {
+            let (k, e) = special::ellip_ke(real_arg(b, func)?)?;
+            push_checked(vals, Complex64::new(if func == "ellipk" { k } else { e }, 0.0))?
         }
         "comb" | "nCr" => {
             let a = vals.pop().ok_or("nCr requiere 2 argumentos")?;
-            let res = comb_u128(a.re.round() as u64, b.re.round() as u64)?;
-            push_checked(vals, Complex64::new(res as f64, 0.0))?
-        }
-        "perm" | "nPr" => {
-            let a = vals.pop().ok_or("nPr requiere 2 argumentos")?;
-            let res = perm_u128(a.re.round() as u64, b.re.round() as u64)?;
-            push_checked(vals, Complex64::new(res as f64, 0.0))?
+            push_checked(vals, combinatorics::binomial(a, b)?)?
         }
 
-        // --- Estadística / Random / Pct ---
-        "rand" => {
-            let a = vals.pop().ok_or("rand requiere 2 argumentos (min, max)")?;
-            let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
-            let factor = ((nanos % 1_000_000_007) as f64) / 1_000_000_007.0;
-            push_checked(vals, Complex64::new(a.re + factor * (b.re - a.re), 0.0))?
-        }
+        // --- Estadística / Pct ---
+        // Los complejos no están ordenados
         "min" => {
             let a = vals.pop().ok_or("min requiere 2 argumentos")?;
-            push_checked(vals, if a.re < b.re { a } else { b })?
+            let (x, y) = real_args(a, b, func)?;
+            push_checked(vals, if x < y { a } else { b })?
         }
         "max" => {
             let a = vals.pop().ok_or("max requiere 2 argumentos")?;
-            push_checked(vals, if a.re > b.re { a } else { b })?
+            let (x, y) = real_args(a, b, func)?;
+            push_checked(vals, if x > y { a } else { b })?
         }
         "pct" => {
             let a = vals.pop().ok_or("pct requiere 2 argumentos (parte, total)")?;
-            push_checked(vals, Complex64::new((a.re / b.re) * 100.0, 0.0))?
+            if b == Complex64::new(0.0, 0.0) { return Err("División por cero".to_string()); }
+            push_checked(vals, a / b * 100.0)?
         }
         "applypct" => {
             let a = vals.pop().ok_or("applypct requiere 2 argumentos (%, valor)")?;
-            push_checked(vals, Complex64::new((a.re / 100.0) * b.re, 0.0))?
+            push_checked(vals, a / 100.0 * b)?
         }
 
         // --- Regla de tres ---
@@ -193,22 +285,8 @@ fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(),
             push_checked(vals, (a * b_val) / c)?
         }
 
-        // --- Conversiones de Base ---
-        "bin" => {
-            let n = safe_i64(b)?;
-            println!("0b{:b}", n); // Imprime en consola el valor binario
-            push_checked(vals, b)?
-        }
-        "oct" => {
-            let n = safe_i64(b)?;
-            println!("0o{:o}", n);
-            push_checked(vals, b)?
-        }
-        "hex" => {
-            let n = safe_i64(b)?;
-            println!("0x{:x}", n);
-            push_checked(vals, b)?
-        }
+        // --- Conversiones de Base: devuelven el valor; `evaluate` anota la base de salida ---
+        "bin" | "oct" | "hex" => push_checked(vals, b)?,
         // Para convertir de base a decimal, usaremos 2 argumentos: de_base(base, número_en_esa_base)
         //"frombase" => {
         //    let num_str = safe_i64(b)?.to_string();
@@ -226,9 +304,25 @@ fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(),
             if b == Complex64::new(0.0, 0.0) { return Err("División por cero".to_string()); }
             push_checked(vals, a / b)?;
         }
+        "±" => {
+            let a = vals.pop().ok_or("Falta el valor de a ± σ")?;
+            if b.im != 0.0 || b.re.is_nan() || b.re < 0.0 {
+                return Err("La incertidumbre de a ± σ debe ser un real >= 0".to_string());
+            }
+            push_checked(vals, a)?
+        }
+        "∠" => {
+            let r = vals.pop().ok_or("Falta el módulo de r∠θ")?;
+            let theta = unit.to_rad(b).re;
+            if b.im != 0.0 || r.im != 0.0 {
+                return Err("r∠θ requiere módulo y ángulo reales".to_string());
+            }
+            push_checked(vals, Complex64::from_polar(r.re, theta))?
+        }
         "%" | "mod" => {
             let a = vals.pop().ok_or("mod requiere 2 argumentos")?;
-            push_checked(vals, Complex64::new(((a.re % b.re) + b.re) % b.re, 0.0))?
+            let (x, m) = real_args(a, b, "mod")?;
+            push_checked(vals, Complex64::new(((x % m) + m) % m, 0.0))?
         }
 
         _ => return Err(format!("Función '{}' no implementada", func)),
@@ -236,82 +330,348 @@ fn apply_func(vals: &mut Vec<Complex64>, func: &str, is_rad: bool) -> Result<(),
     Ok(())
 }
 
+/// Shunting-yard sobre una lista de tokens (ver `Calculator::compile`).
+fn compile_tokens(tokens: Vec<Token>) -> Result<Vec<Rpn>, String> {
+    let mut out: Vec<Rpn> = Vec::new();
+    let mut ops: Vec<String> = Vec::new();
+    // Por cada '(' abierto: (comas vistas, ¿hay algún argumento?)
+    let mut parens: Vec<(usize, bool)> = Vec::new();
+
+    let prec = |op: &str| match op {
+        "+" | "-" => (1, false),
+        // a ± σ liga más que la suma y menos que el producto: 1 + 2*x ± 0.1 = 1 + ((2*x) ± 0.1)
+        "±" => (2, false),
+        "*" | "/" | "%" => (3, false),
+        // r∠θ liga más que el producto: 2*3∠90 = 2*(3∠90)
+        "∠" => (4, false),
+        "^" => (5, true),
+        _ => (4, false),
+    };
+    let emit = |out: &mut Vec<Rpn>, op: String| {
+        let argc = if is_operator(&op) { 2 } else { 1 };
+        out.push(Rpn::Call(op, argc));
+    };
+
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        if !matches!(token, Token::RParen | Token::Comma)
+            && let Some(top) = parens.last_mut()
+        {
+            top.1 = true;
+        }
+        match token {
+            Token::Number(n) => out.push(Rpn::Num(n)),
+            Token::Int(n) => out.push(Rpn::Int(n)),
+            Token::Var(name) => out.push(Rpn::Var(name)),
+            Token::Func(f) if f == "sum" || f == "prod" => {
+                let mut args = take_call_args(&mut tokens, &f)?;
+                if args.len() != 4 {
+                    return Err(format!("{} requiere 4 argumentos (índice, desde, hasta, expr)", f));
+                }
+                let index = match args[0].as_slice() {
+                    [Token::Var(name)] => name.clone(),
+                    _ => return Err(format!("El primer argumento de {} debe ser el nombre del índice", f)),
+                };
+                let body = compile_tokens(args.pop().unwrap())?;
+                let hi = compile_tokens(args.pop().unwrap())?;
+                let lo = compile_tokens(args.pop().unwrap())?;
+                out.push(Rpn::Reduce(Box::new(Reduction { prod: f == "prod", index, lo, hi, body })));
+            }
+            Token::Func(f) => ops.push(f),
+            Token::LParen => {
+                ops.push("(".to_string());
+                parens.push((0, false));
+            }
+            Token::Comma => {
+                while let Some(op) = ops.last() {
+                    if op == "(" { break; }
+                    let op = ops.pop().unwrap();
+                    emit(&mut out, op);
+                }
+                if let Some(top) = parens.last_mut() {
+                    top.0 += 1;
+                }
+            }
+            Token::RParen => {
+                // 1. Resolver todo hasta encontrar el paréntesis de apertura
+                while let Some(op) = ops.last() {
+                    if op == "(" { break; }
+                    let op = ops.pop().unwrap();
+                    emit(&mut out, op);
+                }
+
+                // 2. Sacar el '('
+                if ops.pop().is_none() {
+                    return Err("Paréntesis desbalanceados".to_string());
+                }
+                let (commas, has_args) = parens.pop().unwrap_or((0, true));
+
+                // 3. ¡CRÍTICO! Si lo que queda en el tope de ops es una FUNCIÓN, aplicarla AHORA.
+                // Esto vincula `sin(x)` inmediatamente antes de procesar un posible `^2` posterior.
+                if let Some(top_op) = ops.last()
+                    && top_op != "("
+                    && !is_operator(top_op)
+                {
+                    let func_name = ops.pop().unwrap();
+                    let argc = if has_args { commas + 1 } else { 0 };
+                    out.push(Rpn::Call(func_name, argc));
+                }
+            }
+            Token::Op(c) => {
+                let s = c.to_string();
+                let (p_curr, right_assoc) = prec(&s);
+                while let Some(top) = ops.last() {
+                    if top == "(" { break; }
+                    let (p_top, _) = prec(top);
+                    if p_top > p_curr || (p_top == p_curr && !right_assoc) {
+                        let op = ops.pop().unwrap();
+                        emit(&mut out, op);
+                    } else { break; }
+                }
+                ops.push(s);
+            }
+        }
+    }
+    while let Some(op) = ops.pop() {
+        if op == "(" {
+            return Err("Paréntesis desbalanceados".to_string());
+        }
+        emit(&mut out, op);
+    }
+    Ok(out)
+}
+
+/// Separa los tokens de una llamada `f(...)` (ya consumido `f`) en sus argumentos.
+fn take_call_args<I>(tokens: &mut I, func: &str) -> Result<Vec<Vec<Token>>, String>
+where
+    I: Iterator<Item = Token>,
+{
+    if tokens.next() != Some(Token::LParen) {
+        return Err(format!("'{}' requiere paréntesis", func));
+    }
+    let mut args = vec![Vec::new()];
+    let mut depth = 0usize;
+    for token in tokens.by_ref() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return Ok(args),
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => {
+                args.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        args.last_mut().unwrap().push(token);
+    }
+    Err("Paréntesis desbalanceados".to_string())
+}
+
+/// Expresión compilada en notación polaca inversa.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Rpn {
+    Num(Complex64),
+    /// Literal entero exacto mayor que 2^53 (en f64 se redondea).
+    Int(i128),
+    Var(String),
+    /// Función u operador con su número de argumentos.
+    Call(String, usize),
+    /// `sum`/`prod` con índice local: el cuerpo se evalúa una vez por término.
+    Reduce(Box<Reduction>),
+}
+
+/// Argumentos compilados de `sum(k, a, b, expr)` / `prod(k, a, b, expr)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Reduction {
+    pub prod: bool,
+    pub index: String,
+    pub lo: Vec<Rpn>,
+    pub hi: Vec<Rpn>,
+    pub body: Vec<Rpn>,
+}
+
+fn is_operator(op: &str) -> bool {
+    matches!(op, "+" | "-" | "*" | "/" | "^" | "%" | "∠" | "±")
+}
+
 impl Calculator {
     pub fn evaluate(&mut self, expr: &str) -> Result<Complex64, String> {
-        let tokens = self.tokenize(expr)?;
-        let mut values: Vec<Complex64> = Vec::new();
-        let mut ops: Vec<String> = Vec::new();
-
-        let prec = |op: &str| match op {
-            "+" | "-" => (1, false),
-            "*" | "/" | "%" => (2, false),
-            "^" => (4, true),
-            _ => (3, false),
+        let rpn = self.compile(expr)?;
+        self.result_radix = match rpn.last() {
+            Some(Rpn::Call(f, 1)) => match f.as_str() {
+                "bin" => Some(2),
+                "oct" => Some(8),
+                "hex" => Some(16),
+                _ => None,
+            },
+            _ => None,
         };
+        self.result_dms = matches!(rpn.last(), Some(Rpn::Call(f, 1)) if f == "dms");
+        // Modo programador: `ans` sigue siendo el patrón anterior hasta que termine la evaluación
+        if let Some(w) = self.word {
+            let res = self.evaluate_word(w, &rpn)?;
+            self.uncertain.remove("ans");
+            self.last_result = res;
+            return Ok(res);
+        }
+        self.word_result = None;
+        self.reset_sources();
+        // Con funciones enteras, aritmética exacta si todo es entero (sin redondeo a f64)
+        let int_funcs = Self::uses_int_funcs(&rpn);
+        let exact = if self.modulus.is_some() || int_funcs { self.run_exact(&rpn) } else { None };
+        if let (None, Some(m)) = (&exact, self.modulus) {
+            return Err(format!("En modo modulo {} la expresión debe ser entera", m));
+        }
+        self.approx_result = false;
+        let (res, uncertain) = match exact {
+            Some(v) => {
+                let v = v?;
+                let f = v.to_f64().unwrap_or(f64::NAN);
+                self.exact_result = (v.bits() > 53).then_some(v);
+                (Complex64::new(f, 0.0), None)
+            }
+            None => {
+                self.exact_result = None;
+                let d = self.run_rpn(&rpn, &[])?;
+                // Funciones enteras que han tenido que pasar a coma flotante: un entero grande
+                // solo es aproximado
+                self.approx_result = int_funcs && d.val.im == 0.0 && d.val.re.abs() > 9_007_199_254_740_992.0;
+                (d.val, Uncertain::from_dual(&d, 0))
+            }
+        };
+        match uncertain {
+            Some(u) => { self.uncertain.insert("ans".to_string(), u); }
+            None => { self.uncertain.remove("ans"); }
+        }
+        self.last_result = res;
+        Ok(res)
+    }
+
+    /// Evalúa `expr` con números duales: cada variable de `seeds` recibe su propia
+    /// componente de derivada, de modo que `eps[k]` es ∂f/∂seeds[k] (exacta).
+    /// No modifica `last_result`.
+    pub fn evaluate_dual(&mut self, expr: &str, seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        let rpn = self.compile(expr)?;
+        self.reset_sources();
+        self.run_rpn(&rpn, seeds)
+    }
+
+    /// Shunting-yard: convierte la expresión en RPN anotando la aridad de cada llamada.
+    pub(crate) fn compile(&self, expr: &str) -> Result<Vec<Rpn>, String> {
+        let tokens = self.tokenize(expr)?;
+        compile_tokens(tokens)
+    }
 
-        for token in tokens {
-            match token {
-                Token::Number(n) => values.push(n),
-                Token::Var(name) => {
-                    if name == "last" || name == "ans" {
-                        values.push(self.last_result);
+    /// Ejecuta una expresión compilada. El valor de cada nodo lo calcula `apply_func`;
+    /// las derivadas (si hay semillas) se propagan con las reglas de `dual::tangent`.
+    pub(crate) fn run_rpn(&mut self, rpn: &[Rpn], seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        let mut values: Vec<Dual> = Vec::new();
+        for item in rpn {
+            match item {
+                Rpn::Num(n) => values.push(Dual::constant(*n)),
+                Rpn::Int(n) => values.push(Dual::constant(Complex64::new(*n as f64, 0.0))),
+                Rpn::Var(name) => {
+                    if let Some((_, v)) = self.scopes.iter().rev().find(|(s, _)| s == name) {
+                        values.push(v.clone());
+                    } else if let Some(k) = seeds.iter().position(|(s, _)| s == name) {
+                        values.push(Dual::seed(seeds[k].1, k, seeds.len()));
+                    } else if let Some(u) = self.uncertainty(name) {
+                        values.push(u.to_dual(seeds.len()));
+                    } else if name == "last" || name == "ans" {
+                        values.push(Dual::constant(self.last_result));
                     } else {
-                        values.push(*self.vars.get(&name).ok_or(format!("Variable '{}' no existe", name))?);
+                        let v = *self.vars.get(name).ok_or(format!("Variable '{}' no existe", name))?;
+                        values.push(Dual::constant(v));
                     }
                 }
-                Token::Func(f) => ops.push(f),
-                Token::LParen => ops.push("(".to_string()),
-                Token::Comma => {
-                    while let Some(op) = ops.last() {
-                        if op == "(" { break; }
-                        let op = ops.pop().unwrap();
-                        apply_func(&mut values, &op, self.is_radians)?;
+                Rpn::Call(func, argc) => {
+                    if values.len() < *argc {
+                        return Err("Faltan argumentos".to_string());
                     }
-                }
-                Token::RParen => {
-                    // 1. Resolver todo hasta encontrar el paréntesis de apertura
-                    while let Some(op) = ops.last() {
-                        if op == "(" { break; }
-                        let op = ops.pop().unwrap();
-                        apply_func(&mut values, &op, self.is_radians)?;
+                    let args = values.split_off(values.len() - argc);
+                    if let Some(f) = self.funcs.get(func) {
+                        let f = f.clone();
+                        values.push(self.call_user_func(func, &f, args, seeds)?);
+                        continue;
                     }
-    
-                    // 2. Sacar el '('
-                    if ops.pop().is_none() {
-                        return Err("Paréntesis desbalanceados".to_string());
+                    let mut vals: Vec<Complex64> = args.iter().map(|d| d.val).collect();
+                    if RANDOM_FUNCS.contains(&func.as_str()) {
+                        values.push(Dual::constant(self.random_call(func, &vals)?));
+                        continue;
                     }
-
-                    // 3. ¡CRÍTICO! Si lo que queda en el tope de ops es una FUNCIÓN, aplicarla AHORA.
-                    // Esto vincula `sin(x)` inmediatamente antes de procesar un posible `^2` posterior.
-                    if let Some(top_op) = ops.last() {
-                        // Necesitamos saber si es función. Como usas Strings, miramos si NO es un operador estándar ni '('
-                        // Una solución más robusta sería mirar tu lista de funciones, pero por ahora:
-                        let is_func = !matches!(top_op.as_str(), "+" | "-" | "*" | "/" | "^" | "%" | "(");
-        
-                        if is_func {
-                            let func_name = ops.pop().unwrap();
-                            apply_func(&mut values, &func_name, self.is_radians)?;
-                        }
+                    apply_func(&mut vals, func, self.angle)?;
+                    if vals.len() != 1 {
+                        return Err(format!("Número de argumentos incorrecto para '{}'", func));
                     }
-                }
-                Token::Op(c) => {
-                    let s = c.to_string();
-                    let (p_curr, right_assoc) = prec(&s);
-                    while let Some(top) = ops.last() {
-                        if top == "(" { break; }
-                        let (p_top, _) = prec(top);
-                        if p_top > p_curr || (p_top == p_curr && !right_assoc) {
-                            let op = ops.pop().unwrap();
-                            apply_func(&mut values, &op, self.is_radians)?;
-                        } else { break; }
+                    let res = self.real_only(func, &args.iter().map(|d| d.val).collect::<Vec<_>>(), vals[0])?;
+                    let eps = dual::propagate(func, &args, res, self.angle)?;
+                    let d = Dual { val: res, eps };
+                    // Cada `±` evaluado es una fuente de error nueva (ver `measure`)
+                    if func == "±" {
+                        values.push(self.add_source(d, args[1].val.re, seeds.len()));
+                    } else {
+                        values.push(d);
                     }
-                    ops.push(s);
                 }
+                Rpn::Reduce(r) => values.push(self.run_reduce(r, seeds)?),
             }
         }
-        while let Some(op) = ops.pop() { apply_func(&mut values, &op, self.is_radians)?; }
         let res = values.pop().ok_or("Error en expresión")?;
-        self.last_result = res;
+        if !values.is_empty() {
+            return Err("Error en expresión: sobran operandos".to_string());
+        }
         Ok(res)
     }
+
+    /// En modo real, el resultado `res` de `func` sobre argumentos reales debe ser real:
+    /// las raíces de índice impar de negativos dan la raíz real y el resto es un error de dominio.
+    pub(crate) fn real_only(&self, func: &str, args: &[Complex64], res: Complex64) -> Result<Complex64, String> {
+        if !self.real || res.im == 0.0 || args.iter().any(|a| a.im != 0.0) {
+            return Ok(res);
+        }
+        if let ("root", [n, x]) = (func, args)
+            && n.re.fract() == 0.0
+            && n.re % 2.0 != 0.0
+        {
+            return Ok(Complex64::new(-(-x.re).powf(1.0 / n.re), 0.0));
+        }
+        let shown: Vec<String> = args.iter().map(|a| format_real(a.re, self.output_format, false)).collect();
+        let call = if is_operator(func) && shown.len() == 2 {
+            format!("{} {} {}", shown[0], func, shown[1])
+        } else {
+            format!("{}({})", func, shown.join(", "))
+        };
+        Err(format!("Error de dominio: {} no es real (modo real)", call))
+    }
+
+    /// Llama a una función de usuario: sus parámetros se ligan como variables locales
+    /// (conservando las derivadas de los argumentos).
+    fn call_user_func(&mut self, name: &str, f: &UserFunc, args: Vec<Dual>, seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        if args.len() != f.params.len() {
+            return Err(format!("'{}' requiere {} argumento(s)", name, f.params.len()));
+        }
+        if self.scopes.len() > MAX_CALL_DEPTH {
+            return Err(format!("Demasiada recursión al llamar a '{}'", name));
+        }
+        let depth = self.scopes.len();
+        self.scopes.extend(f.params.iter().cloned().zip(args));
+        let res = self.run_rpn(&f.rpn, seeds);
+        self.scopes.truncate(depth);
+        res
+    }
+
+    /// Define (o reemplaza) la función `name(params) = body`.
+    pub fn define_func(&mut self, name: &str, params: &[&str], body: &str) -> Result<(), String> {
+        if !is_identifier(name) || params.iter().any(|p| !is_identifier(p)) {
+            return Err(format!("'{}' no es un nombre de función válido", name));
+        }
+        if self.vars.contains_key(name) || self.is_builtin_func(name) {
+            return Err(format!("'{}' ya es una variable o función predefinida", name));
+        }
+        // Se compila antes de registrarla: el cuerpo no puede llamarse a sí mismo
+        let rpn = self.compile(body)?;
+        let params = params.iter().map(|p| p.to_string()).collect();
+        self.funcs.insert(name.to_string(), UserFunc { params, body: body.to_string(), rpn });
+        Ok(())
+    }
 }
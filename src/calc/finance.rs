@@ -0,0 +1,293 @@
+//! Valor temporal del dinero con las convenciones de Excel: signo de caja (lo que se paga
+//! es negativo) y `tipo` 0 = pagos al final del periodo, 1 = al principio.
+
+use super::Calculator;
+
+/// Funciones financieras (argumentos reales).
+pub const FIN_FUNCS: &[&str] = &["pv", "fv", "pmt", "nper", "rate", "npv", "irr", "xirr", "effect", "nominal", "date"];
+
+const MAX_ITER: usize = 100;
+
+/// Argumentos `[obligatorios..., opcionales con su valor por defecto...]`.
+fn args_with_defaults(args: &[f64], required: usize, defaults: &[f64], usage: &str) -> Result<Vec<f64>, String> {
+    if args.len() < required || args.len() > required + defaults.len() {
+        return Err(format!("Uso: {}", usage));
+    }
+    let mut full = args.to_vec();
+    full.extend_from_slice(&defaults[args.len() - required..]);
+    Ok(full)
+}
+
+/// Momento del pago: 0 = final del periodo, 1 = principio.
+fn payment_type(func: &str, t: f64) -> Result<f64, String> {
+    if t != 0.0 && t != 1.0 {
+        return Err(format!("{}: el tipo debe ser 0 (final del periodo) o 1 (principio)", func));
+    }
+    Ok(t)
+}
+
+/// ((1+r)^n − 1)/r, continua en r = 0.
+fn annuity_factor(r: f64, n: f64) -> f64 {
+    if r == 0.0 { n } else { (n * r.ln_1p()).exp_m1() / r }
+}
+
+/// Valor futuro de todos los flujos: pv·(1+r)^n + pmt·(1+r·tipo)·((1+r)^n − 1)/r + fv.
+/// Es cero cuando los argumentos son coherentes (ecuación que resuelve `rate`).
+fn tvm_balance(r: f64, n: f64, pmt: f64, pv: f64, fv: f64, t: f64) -> f64 {
+    pv * (n * r.ln_1p()).exp() + pmt * (1.0 + r * t) * annuity_factor(r, n) + fv
+}
+
+/// Raíz de `f` por Newton (derivada numérica) desde `guess`; si no converge, busca un
+/// cambio de signo en (−1, 10] y lo refina por bisección.
+fn solve_rate(func: &str, guess: f64, f: impl Fn(f64) -> f64) -> Result<f64, String> {
+    let mut r = guess;
+    for _ in 0..MAX_ITER {
+        let (v, h) = (f(r), 1e-7 * (1.0 + r.abs()));
+        let d = (f(r + h) - f(r - h)) / (2.0 * h);
+        if !v.is_finite() || !d.is_finite() || d == 0.0 {
+            break;
+        }
+        let next = r - v / d;
+        if next <= -1.0 {
+            break;
+        }
+        if (next - r).abs() <= 1e-12 * (1.0 + r.abs()) {
+            return Ok(next);
+        }
+        r = next;
+    }
+    // Rejilla logarítmica en 1 + r y bisección en el primer cambio de signo
+    let grid: Vec<f64> = (0..=400).map(|k| (-6.0 + k as f64 * (6.0 + 11f64.ln()) / 400.0).exp() - 1.0).collect();
+    for w in grid.windows(2) {
+        let (mut a, mut b) = (w[0], w[1]);
+        let (mut fa, fb) = (f(a), f(b));
+        if !fa.is_finite() || !fb.is_finite() || fa.signum() == fb.signum() {
+            continue;
+        }
+        for _ in 0..200 {
+            let m = 0.5 * (a + b);
+            let fm = f(m);
+            if fm.signum() == fa.signum() {
+                (a, fa) = (m, fm);
+            } else {
+                b = m;
+            }
+        }
+        return Ok(0.5 * (a + b));
+    }
+    Err(format!("{} no converge: prueba con otro valor inicial", func))
+}
+
+fn check_flows(func: &str, flows: &[f64]) -> Result<(), String> {
+    if !(flows.iter().any(|&v| v > 0.0) && flows.iter().any(|&v| v < 0.0)) {
+        return Err(format!("{} requiere al menos un flujo positivo y uno negativo", func));
+    }
+    Ok(())
+}
+
+/// Valor actual neto de `flows` al tipo `r`; el primer flujo se descuenta un periodo (NPV de Excel).
+pub fn npv(r: f64, flows: &[f64]) -> f64 {
+    flows.iter().enumerate().map(|(k, v)| v / (1.0 + r).powi(k as i32 + 1)).sum()
+}
+
+/// Tasa interna de retorno de flujos periódicos (el primero en t = 0).
+pub fn irr(flows: &[f64]) -> Result<f64, String> {
+    check_flows("irr", flows)?;
+    solve_rate("irr", 0.1, |r| npv(r, flows) * (1.0 + r))
+}
+
+/// TIR con fechas (XIRR de Excel): `(fecha, flujo)` con las fechas en días; años de 365 días.
+pub fn xirr(flows: &[(f64, f64)]) -> Result<f64, String> {
+    let values: Vec<f64> = flows.iter().map(|f| f.1).collect();
+    check_flows("xirr", &values)?;
+    let d0 = flows[0].0;
+    if flows.iter().any(|f| f.0 < d0) {
+        return Err("xirr: ninguna fecha puede ser anterior a la primera".to_string());
+    }
+    solve_rate("xirr", 0.1, |r| flows.iter().map(|(d, v)| v / (1.0 + r).powf((d - d0) / 365.0)).sum())
+}
+
+/// Número de serie de Excel de una fecha (días desde 30/12/1899; válido desde 1/3/1900).
+fn date_serial(y: f64, m: f64, d: f64) -> Result<f64, String> {
+    if [y, m, d].iter().any(|v| v.fract() != 0.0) || !(1..=12).contains(&(m as i64)) || !(1..=31).contains(&(d as i64)) {
+        return Err("Uso: date(año, mes, día) con valores enteros".to_string());
+    }
+    // Días desde el 1/1/1970 (algoritmo civil de H. Hinnant)
+    let (y, m, d) = (y as i64 - (m <= 2.0) as i64, m as i64, d as i64);
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let unix = era * 146_097 + doe - 719_468;
+    Ok((unix + 25_569) as f64)
+}
+
+/// Evalúa una función de `FIN_FUNCS`.
+pub(crate) fn eval_fin(func: &str, args: &[f64]) -> Result<f64, String> {
+    let v = match func {
+        "fv" => {
+            let a = args_with_defaults(args, 3, &[0.0, 0.0], "fv(tasa, nper, pago, [va], [tipo])")?;
+            let (r, n, pmt, pv, t) = (a[0], a[1], a[2], a[3], payment_type(func, a[4])?);
+            -(pv * (n * r.ln_1p()).exp() + pmt * (1.0 + r * t) * annuity_factor(r, n))
+        }
+        "pv" => {
+            let a = args_with_defaults(args, 3, &[0.0, 0.0], "pv(tasa, nper, pago, [vf], [tipo])")?;
+            let (r, n, pmt, fv, t) = (a[0], a[1], a[2], a[3], payment_type(func, a[4])?);
+            -(fv + pmt * (1.0 + r * t) * annuity_factor(r, n)) / (n * r.ln_1p()).exp()
+        }
+        "pmt" => {
+            let a = args_with_defaults(args, 3, &[0.0, 0.0], "pmt(tasa, nper, va, [vf], [tipo])")?;
+            let (r, n, pv, fv, t) = (a[0], a[1], a[2], a[3], payment_type(func, a[4])?);
+            if n == 0.0 {
+                return Err("pmt requiere nper != 0".to_string());
+            }
+            -(pv * (n * r.ln_1p()).exp() + fv) / ((1.0 + r * t) * annuity_factor(r, n))
+        }
+        "nper" => {
+            let a = args_with_defaults(args, 3, &[0.0, 0.0], "nper(tasa, pago, va, [vf], [tipo])")?;
+            let (r, pmt, pv, fv, t) = (a[0], a[1], a[2], a[3], payment_type(func, a[4])?);
+            if r == 0.0 {
+                if pmt == 0.0 {
+                    return Err("nper: con tasa 0 el pago no puede ser 0".to_string());
+                }
+                -(pv + fv) / pmt
+            } else {
+                let p = pmt * (1.0 + r * t);
+                let ratio = (p - fv * r) / (p + pv * r);
+                if ratio <= 0.0 {
+                    return Err("nper: los pagos nunca alcanzan el valor final".to_string());
+                }
+                ratio.ln() / r.ln_1p()
+            }
+        }
+        "rate" => {
+            let a = args_with_defaults(args, 3, &[0.0, 0.0, 0.1], "rate(nper, pago, va, [vf], [tipo], [estimación])")?;
+            let (n, pmt, pv, fv, t) = (a[0], a[1], a[2], a[3], payment_type(func, a[4])?);
+            if n <= 0.0 {
+                return Err("rate requiere nper > 0".to_string());
+            }
+            solve_rate(func, a[5], |r| tvm_balance(r, n, pmt, pv, fv, t))?
+        }
+        "npv" => match args.split_first() {
+            Some((&r, flows)) if !flows.is_empty() => npv(r, flows),
+            _ => return Err("Uso: npv(tasa, v1, v2, ...)".to_string()),
+        },
+        "irr" => {
+            if args.len() < 2 {
+                return Err("Uso: irr(v0, v1, v2, ...)".to_string());
+            }
+            irr(args)?
+        }
+        "xirr" => {
+            if args.len() < 4 || !args.len().is_multiple_of(2) {
+                return Err("Uso: xirr(fecha1, v1, fecha2, v2, ...)".to_string());
+            }
+            let flows: Vec<(f64, f64)> = args.chunks(2).map(|c| (c[0], c[1])).collect();
+            xirr(&flows)?
+        }
+        "effect" | "nominal" => {
+            let [rate, m] = args else {
+                return Err(format!("Uso: {}(tasa, periodos_por_año)", func));
+            };
+            let m = m.trunc();
+            if m < 1.0 {
+                return Err(format!("{}: periodos_por_año debe ser >= 1", func));
+            }
+            if func == "effect" { (m * (rate / m).ln_1p()).exp_m1() } else { m * (rate.ln_1p() / m).exp_m1() }
+        }
+        "date" => match args {
+            [y, m, d] => date_serial(*y, *m, *d)?,
+            _ => return Err("Uso: date(año, mes, día)".to_string()),
+        },
+        _ => return Err(format!("Función financiera '{}' desconocida", func)),
+    };
+    if !v.is_finite() {
+        return Err(format!("{}: resultado no finito", func));
+    }
+    Ok(v)
+}
+
+/// Derivada direccional Σ ∂f/∂aᵢ·daᵢ por diferencias centradas (para `solve`, `deriv`).
+pub(crate) fn derivative(func: &str, a: &[f64], da: &[f64]) -> Result<f64, String> {
+    let mut acc = 0.0;
+    for (k, &d) in da.iter().enumerate() {
+        if d == 0.0 {
+            continue;
+        }
+        let h = 1e-6 * (1.0 + a[k].abs());
+        let (mut lo, mut hi) = (a.to_vec(), a.to_vec());
+        lo[k] -= h;
+        hi[k] += h;
+        acc += (eval_fin(func, &hi)? - eval_fin(func, &lo)?) / (2.0 * h) * d;
+    }
+    Ok(acc)
+}
+
+/// Fila del cuadro de amortización; `payment` = `interest` + `principal` (incluido el extra).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmortRow {
+    pub period: usize,
+    pub payment: f64,
+    pub interest: f64,
+    pub principal: f64,
+    pub balance: f64,
+}
+
+/// Cuadro de amortización francés con cuotas mensuales: préstamo `principal` al tipo nominal
+/// anual `annual_rate` (0.05 = 5 %) a `years` años, más `extra` amortizado cada mes. El extra
+/// acorta el plazo; la última cuota se ajusta para dejar el saldo exactamente en 0.
+pub fn amortization(principal: f64, annual_rate: f64, years: f64, extra: f64) -> Result<Vec<AmortRow>, String> {
+    let n = years * 12.0;
+    if !(principal > 0.0 && principal.is_finite()) {
+        return Err("amort: el capital debe ser positivo".to_string());
+    }
+    if !(annual_rate >= 0.0 && annual_rate.is_finite()) {
+        return Err("amort: el tipo anual debe ser >= 0".to_string());
+    }
+    if !(1.0..=1200.0).contains(&n) || (n - n.round()).abs() > 1e-9 {
+        return Err("amort: el plazo debe ser un número entero de meses entre 1 y 1200".to_string());
+    }
+    if !(extra >= 0.0 && extra.is_finite()) {
+        return Err("amort: el pago extra debe ser >= 0".to_string());
+    }
+    let (r, n) = (annual_rate / 12.0, n.round() as usize);
+    let payment = -eval_fin("pmt", &[r, n as f64, principal])?;
+    let mut rows = Vec::with_capacity(n);
+    let mut balance = principal;
+    for period in 1..=n {
+        let interest = balance * r;
+        let mut amortized = payment - interest + extra;
+        // Última cuota (o saldo residual por redondeo): se cancela todo lo pendiente
+        if period == n || amortized >= balance || balance - amortized <= 1e-9 * principal {
+            amortized = balance;
+        }
+        balance -= amortized;
+        rows.push(AmortRow { period, payment: interest + amortized, interest, principal: amortized, balance });
+        if balance == 0.0 {
+            break;
+        }
+    }
+    Ok(rows)
+}
+
+impl Calculator {
+    /// VAN de la pila al tipo `rate` (el primer valor se descuenta un periodo, como NPV).
+    pub fn stack_npv(&self, rate: f64) -> Result<f64, String> {
+        let flows = super::stats::real_data(&self.memory_stack).map_err(|_| "npv requiere flujos reales en la pila".to_string())?;
+        Ok(npv(rate, &flows))
+    }
+
+    /// TIR de los flujos de la pila (el primero en t = 0).
+    pub fn stack_irr(&self) -> Result<f64, String> {
+        let flows = super::stats::real_data(&self.memory_stack).map_err(|_| "irr requiere flujos reales en la pila".to_string())?;
+        irr(&flows)
+    }
+
+    /// TIR con fechas de la pila de pares: x = fecha (p. ej. `date(2024, 1, 15)`), y = flujo.
+    pub fn pairs_xirr(&self) -> Result<f64, String> {
+        if self.pairs.len() < 2 {
+            return Err("xirr requiere al menos 2 pares (fecha, flujo) en la pila de pares".to_string());
+        }
+        xirr(&self.pairs)
+    }
+}
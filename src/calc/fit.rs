@@ -0,0 +1,340 @@
+use super::plot::BrailleCanvas;
+use super::{fmt_literal, Calculator};
+
+/// Grado máximo del ajuste polinómico.
+pub const MAX_POLY_DEGREE: usize = 10;
+/// Iteraciones máximas de Levenberg-Marquardt (modelos exp y power).
+const LM_MAX_ITER: usize = 200;
+
+/// Modelos de `fit`. Los lineales en sus parámetros se resuelven directamente por QR;
+/// `Exp` y `Power` parten del ajuste linealizado (logaritmos) y se refinan con
+/// Levenberg-Marquardt para minimizar los residuos en la escala original de y.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FitModel {
+    Linear,
+    Poly(usize),
+    Exp,
+    Power,
+    Log,
+}
+
+/// Resultado de un ajuste: parámetros con su error típico (NaN sin grados de libertad),
+/// R², valores ajustados y residuos (y - ŷ) en el orden de los datos.
+#[derive(Debug, Clone)]
+pub struct FitResult {
+    pub model: FitModel,
+    pub params: Vec<f64>,
+    pub std_errors: Vec<f64>,
+    pub r2: f64,
+    pub fitted: Vec<f64>,
+    pub residuals: Vec<f64>,
+}
+
+impl FitModel {
+    /// `linear`, `poly <n>`, `exp`, `power` o `log`. Devuelve el modelo y los argumentos consumidos.
+    pub fn parse(args: &[&str]) -> Result<(FitModel, usize), String> {
+        match args.first().copied() {
+            Some("linear") | Some("lin") => Ok((FitModel::Linear, 1)),
+            Some("poly") => {
+                let n = args.get(1).and_then(|s| s.parse::<usize>().ok()).ok_or("Uso: fit poly <grado>")?;
+                if !(1..=MAX_POLY_DEGREE).contains(&n) {
+                    return Err(format!("El grado debe estar entre 1 y {}", MAX_POLY_DEGREE));
+                }
+                Ok((FitModel::Poly(n), 2))
+            }
+            Some("exp") => Ok((FitModel::Exp, 1)),
+            Some("power") | Some("pow") => Ok((FitModel::Power, 1)),
+            Some("log") => Ok((FitModel::Log, 1)),
+            _ => Err("Modelos: linear, poly <n>, exp, power, log".to_string()),
+        }
+    }
+
+    pub fn formula(&self) -> String {
+        match self {
+            FitModel::Linear => "y = a + b·x".to_string(),
+            FitModel::Poly(n) => format!("y = c0 + c1·x + ... + c{}·x^{}", n, n),
+            FitModel::Exp => "y = a·e^(b·x)".to_string(),
+            FitModel::Power => "y = a·x^b".to_string(),
+            FitModel::Log => "y = a + b·ln(x)".to_string(),
+        }
+    }
+
+    pub fn param_names(&self) -> Vec<String> {
+        match self {
+            FitModel::Poly(n) => (0..=*n).map(|k| format!("c{}", k)).collect(),
+            _ => vec!["a".to_string(), "b".to_string()],
+        }
+    }
+
+    /// Valor del modelo en `x` con parámetros `p`.
+    pub fn eval(&self, p: &[f64], x: f64) -> f64 {
+        match self {
+            FitModel::Linear | FitModel::Poly(_) => p.iter().rev().fold(0.0, |acc, c| acc * x + c),
+            FitModel::Exp => p[0] * (p[1] * x).exp(),
+            FitModel::Power => p[0] * x.powf(p[1]),
+            FitModel::Log => p[0] + p[1] * x.ln(),
+        }
+    }
+
+    /// Fila de la matriz de diseño (modelos lineales en los parámetros).
+    fn basis(&self, x: f64) -> Vec<f64> {
+        match self {
+            FitModel::Linear => vec![1.0, x],
+            FitModel::Poly(n) => (0..=*n as i32).map(|k| x.powi(k)).collect(),
+            FitModel::Log => vec![1.0, x.ln()],
+            FitModel::Exp | FitModel::Power => unreachable!("modelo no lineal"),
+        }
+    }
+
+    /// Jacobiano ∂f/∂p en `x` (modelos no lineales).
+    fn jacobian(&self, p: &[f64], x: f64) -> Vec<f64> {
+        match self {
+            FitModel::Exp => {
+                let e = (p[1] * x).exp();
+                vec![e, p[0] * x * e]
+            }
+            FitModel::Power => {
+                let e = x.powf(p[1]);
+                vec![e, p[0] * e * x.ln()]
+            }
+            _ => self.basis(x),
+        }
+    }
+}
+
+/// Literal entre paréntesis si es negativo (para incrustarlo en una expresión).
+fn lit(v: f64) -> String {
+    if v < 0.0 { format!("({})", fmt_literal(v)) } else { fmt_literal(v) }
+}
+
+/// Combinación lineal `c0 + c1*t1 - c2*t2 ...` omitiendo los términos nulos.
+fn linear_combo(terms: &[(f64, String)]) -> String {
+    let mut out = String::new();
+    for (c, t) in terms {
+        if *c == 0.0 {
+            continue;
+        }
+        let body = match (t.is_empty(), c.abs() == 1.0) {
+            (true, _) => fmt_literal(c.abs()),
+            (false, true) => t.clone(),
+            (false, false) => format!("{}*{}", fmt_literal(c.abs()), t),
+        };
+        if out.is_empty() {
+            out = if *c < 0.0 { format!("-{}", body) } else { body };
+        } else {
+            out.push_str(if *c < 0.0 { " - " } else { " + " });
+            out.push_str(&body);
+        }
+    }
+    if out.is_empty() { "0".to_string() } else { out }
+}
+
+impl FitResult {
+    /// El modelo ajustado como expresión evaluable en la variable `var`.
+    pub fn expr(&self, var: &str) -> String {
+        let p = &self.params;
+        match self.model {
+            FitModel::Linear | FitModel::Poly(_) => {
+                let terms: Vec<(f64, String)> = p
+                    .iter()
+                    .enumerate()
+                    .map(|(k, c)| (*c, match k { 0 => String::new(), 1 => var.to_string(), _ => format!("{}^{}", var, k) }))
+                    .collect();
+                linear_combo(&terms)
+            }
+            FitModel::Exp => format!("{}*exp({}*{})", lit(p[0]), lit(p[1]), var),
+            FitModel::Power => format!("{}*{}^{}", lit(p[0]), var, lit(p[1])),
+            FitModel::Log => linear_combo(&[(p[0], String::new()), (p[1], format!("ln({})", var))]),
+        }
+    }
+
+    /// Dibuja los datos (marcas) y la curva ajustada.
+    pub fn plot(&self, data: &[(f64, f64)]) {
+        let (width, height) = (80usize, 24usize);
+        let x_min = data.iter().map(|d| d.0).fold(f64::INFINITY, f64::min);
+        let x_max = data.iter().map(|d| d.0).fold(f64::NEG_INFINITY, f64::max);
+        let pad = if x_max > x_min { 0.05 * (x_max - x_min) } else { 1.0 };
+        let (x0, x1) = (x_min - pad, x_max + pad);
+
+        let samples = width * 2;
+        let curve: Vec<(f64, f64)> = (0..samples)
+            .map(|k| {
+                let x = x0 + (x1 - x0) * k as f64 / (samples - 1) as f64;
+                (x, self.model.eval(&self.params, x))
+            })
+            .filter(|(_, y)| y.is_finite())
+            .collect();
+        let ys = data.iter().map(|d| d.1).chain(curve.iter().map(|c| c.1));
+        let (y_min, y_max) = ys.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), y| (lo.min(y), hi.max(y)));
+        let (y0, y1) = if (y_max - y_min).abs() < 1e-9 { (y_min - 1.0, y_max + 1.0) } else { (y_min, y_max) };
+
+        let mut canvas = BrailleCanvas::new(width, height, (x0, x1), (y0, y1));
+        for w in curve.windows(2) {
+            canvas.draw_line(w[0], w[1]);
+        }
+        for &(x, y) in data {
+            canvas.plot_marker(x, y);
+        }
+        canvas.print(&format!("Ajuste {}", self.model.formula()));
+    }
+}
+
+/// Mínimos cuadrados min ‖A·c − b‖ por QR de Householder (sin formar AᵀA).
+/// Devuelve los coeficientes y la diagonal de (AᵀA)⁻¹ = R⁻¹R⁻ᵀ para los errores típicos.
+fn lstsq(a: &[Vec<f64>], b: &[f64]) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let (m, p) = (a.len(), a[0].len());
+    let mut r: Vec<Vec<f64>> = a.to_vec();
+    let mut qtb = b.to_vec();
+    let scale = r.iter().flatten().fold(0.0f64, |s, v| s.max(v.abs()));
+
+    for k in 0..p {
+        let norm = (k..m).map(|i| r[i][k] * r[i][k]).sum::<f64>().sqrt();
+        if norm <= 1e-13 * scale {
+            return Err("Datos degenerados: el modelo no está determinado (¿x repetidos o pocos puntos?)".to_string());
+        }
+        let alpha = if r[k][k] > 0.0 { -norm } else { norm };
+        let mut v: Vec<f64> = (k..m).map(|i| r[i][k]).collect();
+        v[0] -= alpha;
+        let vv: f64 = v.iter().map(|x| x * x).sum();
+        for j in k..p {
+            let dot: f64 = r[k..].iter().zip(&v).map(|(row, vi)| vi * row[j]).sum();
+            for (row, vi) in r[k..].iter_mut().zip(&v) {
+                row[j] -= 2.0 * dot / vv * vi;
+            }
+        }
+        let dot: f64 = qtb[k..].iter().zip(&v).map(|(b, vi)| vi * b).sum();
+        for (b, vi) in qtb[k..].iter_mut().zip(&v) {
+            *b -= 2.0 * dot / vv * vi;
+        }
+    }
+
+    // Sustitución regresiva R·c = Qᵀb
+    let mut c = vec![0.0; p];
+    for k in (0..p).rev() {
+        let s: f64 = (k + 1..p).map(|j| r[k][j] * c[j]).sum();
+        c[k] = (qtb[k] - s) / r[k][k];
+    }
+
+    // R⁻¹ (triangular superior), columna a columna
+    let mut rinv = vec![vec![0.0; p]; p];
+    for j in 0..p {
+        rinv[j][j] = 1.0 / r[j][j];
+        for k in (0..j).rev() {
+            let s: f64 = (k + 1..=j).map(|l| r[k][l] * rinv[l][j]).sum();
+            rinv[k][j] = -s / r[k][k];
+        }
+    }
+    let diag = (0..p).map(|k| rinv[k].iter().map(|v| v * v).sum()).collect();
+    Ok((c, diag))
+}
+
+fn sum_sq(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum()
+}
+
+/// Levenberg-Marquardt con amortiguamiento escalado por las columnas del jacobiano.
+/// Cada paso es un problema de mínimos cuadrados aumentado [J; √λ·D]·δ ≈ [r; 0].
+fn levenberg_marquardt(model: FitModel, data: &[(f64, f64)], mut p: Vec<f64>) -> Result<Vec<f64>, String> {
+    let residuals = |p: &[f64]| -> Vec<f64> { data.iter().map(|(x, y)| y - model.eval(p, *x)).collect() };
+    let mut r = residuals(&p);
+    let mut ssr = sum_sq(&r);
+    let mut lambda: f64 = 1e-3;
+
+    for _ in 0..LM_MAX_ITER {
+        let jac: Vec<Vec<f64>> = data.iter().map(|(x, _)| model.jacobian(&p, *x)).collect();
+        let d: Vec<f64> = (0..p.len()).map(|k| jac.iter().map(|row| row[k] * row[k]).sum::<f64>().sqrt()).collect();
+        loop {
+            let mut a = jac.clone();
+            let mut b = r.clone();
+            for (k, dk) in d.iter().enumerate() {
+                let mut row = vec![0.0; p.len()];
+                row[k] = lambda.sqrt() * dk;
+                a.push(row);
+                b.push(0.0);
+            }
+            let (delta, _) = lstsq(&a, &b)?;
+            let trial: Vec<f64> = p.iter().zip(&delta).map(|(pi, di)| pi + di).collect();
+            let r_trial = residuals(&trial);
+            let ssr_trial = sum_sq(&r_trial);
+            if ssr_trial.is_finite() && ssr_trial <= ssr {
+                let small_step = delta.iter().zip(&p).all(|(di, pi)| di.abs() <= 1e-12 * pi.abs().max(1e-300));
+                let converged = small_step || ssr - ssr_trial <= 1e-15 * ssr;
+                p = trial;
+                r = r_trial;
+                ssr = ssr_trial;
+                lambda = (lambda / 10.0).max(1e-12);
+                if converged {
+                    return Ok(p);
+                }
+                break;
+            }
+            lambda *= 10.0;
+            if lambda > 1e16 {
+                return Ok(p);
+            }
+        }
+    }
+    Ok(p)
+}
+
+/// Signo común de todos los y (para linealizar con logaritmos), o error.
+fn common_sign(data: &[(f64, f64)], model: &str) -> Result<f64, String> {
+    if data.iter().all(|d| d.1 > 0.0) {
+        Ok(1.0)
+    } else if data.iter().all(|d| d.1 < 0.0) {
+        Ok(-1.0)
+    } else {
+        Err(format!("El modelo {} requiere que todos los y tengan el mismo signo (y ≠ 0)", model))
+    }
+}
+
+impl Calculator {
+    /// Ajusta `model` a los pares (x, y) de `self.pairs`.
+    pub fn fit(&self, model: FitModel) -> Result<FitResult, String> {
+        let data = &self.pairs;
+        let p = model.param_names().len();
+        if data.len() < p {
+            return Err(format!("Se necesitan al menos {} pares (x, y); hay {}", p, data.len()));
+        }
+        if matches!(model, FitModel::Log | FitModel::Power) && data.iter().any(|d| d.0 <= 0.0) {
+            return Err("Los modelos log y power requieren x > 0".to_string());
+        }
+
+        let (params, diag) = match model {
+            FitModel::Linear | FitModel::Poly(_) | FitModel::Log => {
+                let a: Vec<Vec<f64>> = data.iter().map(|d| model.basis(d.0)).collect();
+                let b: Vec<f64> = data.iter().map(|d| d.1).collect();
+                lstsq(&a, &b)?
+            }
+            FitModel::Exp | FitModel::Power => {
+                // Estimación inicial: recta sobre ln|y| frente a x (exp) o ln x (power)
+                let s = common_sign(data, if model == FitModel::Exp { "exp" } else { "power" })?;
+                let a: Vec<Vec<f64>> = data
+                    .iter()
+                    .map(|d| vec![1.0, if model == FitModel::Exp { d.0 } else { d.0.ln() }])
+                    .collect();
+                let b: Vec<f64> = data.iter().map(|d| (s * d.1).ln()).collect();
+                let (c, _) = lstsq(&a, &b)?;
+                let p = levenberg_marquardt(model, data, vec![s * c[0].exp(), c[1]])?;
+                let jac: Vec<Vec<f64>> = data.iter().map(|(x, _)| model.jacobian(&p, *x)).collect();
+                let (_, diag) = lstsq(&jac, &vec![0.0; data.len()])?;
+                (p, diag)
+            }
+        };
+
+        let fitted: Vec<f64> = data.iter().map(|d| model.eval(&params, d.0)).collect();
+        if fitted.iter().any(|y| !y.is_finite()) {
+            return Err("El ajuste no converge: el modelo no es finito en los datos".to_string());
+        }
+        let residuals: Vec<f64> = data.iter().zip(&fitted).map(|(d, f)| d.1 - f).collect();
+        let ssr = sum_sq(&residuals);
+        let mean = data.iter().map(|d| d.1).sum::<f64>() / data.len() as f64;
+        let sst: f64 = data.iter().map(|d| (d.1 - mean).powi(2)).sum();
+        let r2 = if sst > 0.0 { 1.0 - ssr / sst } else if ssr == 0.0 { 1.0 } else { 0.0 };
+        let dof = data.len() - p;
+        let s2 = if dof > 0 { ssr / dof as f64 } else { f64::NAN };
+        let std_errors = diag.iter().map(|d| (s2 * d).sqrt()).collect();
+
+        Ok(FitResult { model, params, std_errors, r2, fitted, residuals })
+    }
+}
@@ -0,0 +1,306 @@
+//! Presentación de resultados: formato numérico (`fmt`), forma de los complejos (`complex`)
+//! y medidas con incertidumbre.
+
+use super::measure::Uncertain;
+use super::{AngleUnit, Calculator, ComplexFormat, OutputFormat};
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_traits::ToPrimitive;
+
+/// Por debajo de este valor la parte imaginaria se considera nula al mostrar.
+const IM_EPS: f64 = 1e-10;
+
+/// Cifras significativas de la mantisa en los formatos de ingeniería y SI.
+const ENG_DIGITS: usize = 6;
+
+/// Prefijos SI de 10^-24 a 10^24, de tres en tres.
+const SI_PREFIXES: [&str; 17] = ["y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y"];
+
+/// Bits del complemento a dos con que se muestran los negativos en bases no decimales.
+const WORD_BITS: u32 = 64;
+
+const RADIX_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Separa `{:.Ne}` de Rust en mantisa y exponente: "4.700e3" -> ("4.700", 3).
+fn split_exp(s: &str) -> (&str, i32) {
+    let (m, e) = s.split_once('e').unwrap_or((s, "0"));
+    (m, e.parse().unwrap_or(0))
+}
+
+/// Quita los ceros finales de la parte decimal (y el punto si queda solo).
+fn trim_zeros(s: &str) -> &str {
+    if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.') } else { s }
+}
+
+/// Como `%g` de C: `digits` cifras significativas, en notación científica solo si el
+/// exponente es < -4 o >= `digits`.
+fn significant(n: f64, digits: usize) -> String {
+    let sci = format!("{:.*e}", digits - 1, n);
+    let (_, e) = split_exp(&sci);
+    if e < -4 || e >= digits as i32 {
+        sci
+    } else {
+        format!("{:.*}", (digits as i32 - 1 - e) as usize, n)
+    }
+}
+
+/// Mantisa en [1, 1000) y exponente múltiplo de 3, con `ENG_DIGITS` cifras y sin ceros finales.
+/// Se desplaza el punto sobre las cifras de `{:e}` para no introducir errores de redondeo.
+fn engineering(n: f64) -> (String, i32) {
+    let sci = format!("{:.*e}", ENG_DIGITS - 1, n);
+    let (m, e) = split_exp(&sci);
+    let e3 = e.div_euclid(3) * 3;
+    let (sign, m) = m.strip_prefix('-').map_or(("", m), |r| ("-", r));
+    let digits: String = m.chars().filter(|c| *c != '.').collect();
+    let point = 1 + (e - e3) as usize;
+    let mantissa = format!("{}{}.{}", sign, &digits[..point], &digits[point..]);
+    (trim_zeros(&mantissa).to_string(), e3)
+}
+
+/// Inserta un espacio cada `size` cifras contando desde la derecha: "1234567" -> "1 234 567".
+pub(crate) fn group_chars(int: &str, size: usize) -> String {
+    let mut out = String::new();
+    for (k, c) in int.chars().enumerate() {
+        if k > 0 && (int.len() - k).is_multiple_of(size) {
+            out.push(' ');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Separa con espacios los miles de la parte entera inicial: "-1234567.5" -> "-1 234 567.5".
+/// El espacio se ignora al leer expresiones, así que el resultado puede reutilizarse.
+fn group_thousands(s: &str) -> String {
+    let (sign, rest) = s.strip_prefix('-').map_or(("", s), |r| ("-", r));
+    let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let (int, tail) = rest.split_at(end);
+    format!("{}{}{}", sign, group_chars(int, 3), tail)
+}
+
+/// Cifras de `n` en base `radix` (2..=36), con grupos de 4 en las bases 2 y 16 y de 3 en el resto.
+pub(crate) fn radix_digits(mut n: u128, radix: u32, group: bool) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(RADIX_DIGITS[(n % radix as u128) as usize]);
+        n /= radix as u128;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    group_radix(String::from_utf8(digits).unwrap_or_default(), radix, group)
+}
+
+/// Agrupa las cifras `s` de un entero en base `radix` como `radix_digits`.
+fn group_radix(s: String, radix: u32, group: bool) -> String {
+    match (group, radix) {
+        (false, _) => s,
+        (true, 2 | 16) => group_chars(&s, 4),
+        (true, _) => group_chars(&s, 3),
+    }
+}
+
+/// Prefijo de base: 0b, 0o y 0x, o `N#` (como en bash) para las demás.
+fn radix_prefix(radix: u32) -> String {
+    match radix {
+        2 => "0b".to_string(),
+        8 => "0o".to_string(),
+        16 => "0x".to_string(),
+        _ => format!("{}#", radix),
+    }
+}
+
+/// Entero en base `radix`; los negativos que caben en 64 bits se muestran en complemento a dos
+/// de ancho fijo (-1 -> 0xffffffffffffffff), los demás con signo.
+pub fn format_int_radix(n: i128, radix: u32, group: bool) -> String {
+    let min = -(1i128 << (WORD_BITS - 1));
+    if n < 0 && n >= min {
+        let twos = (n + (1i128 << WORD_BITS)) as u128;
+        return format!("{}{}", radix_prefix(radix), radix_digits(twos, radix, group));
+    }
+    let sign = if n < 0 { "-" } else { "" };
+    format!("{}{}{}", sign, radix_prefix(radix), radix_digits(n.unsigned_abs(), radix, group))
+}
+
+/// Entero exacto de cualquier tamaño en base `radix`: como `format_int_radix` si cabe en
+/// i128, si no con signo.
+pub fn format_big_radix(n: &BigInt, radix: u32, group: bool) -> String {
+    if let Some(v) = n.to_i128() {
+        return format_int_radix(v, radix, group);
+    }
+    let sign = if n.sign() == num_bigint::Sign::Minus { "-" } else { "" };
+    format!("{}{}{}", sign, radix_prefix(radix), group_radix(n.magnitude().to_str_radix(radix), radix, group))
+}
+
+/// Real en base `radix`: los enteros como en `format_int_radix`; con decimales, signo y
+/// magnitud y cifras fraccionarias hasta agotar la precisión de f64 ("…" si no termina).
+pub fn format_radix(n: f64, radix: u32, group: bool) -> String {
+    const LIMIT: f64 = 1.7e38; // ~2^127
+    if !n.is_finite() || n.abs() >= LIMIT {
+        return format!("{:e}", n);
+    }
+    if n.fract() == 0.0 {
+        return format_int_radix(n as i128, radix, group);
+    }
+    let a = n.abs();
+    let mut frac = a.fract();
+    let max = (f64::MANTISSA_DIGITS as f64 / (radix as f64).log2()).ceil() as usize;
+    let mut digits = String::new();
+    while frac > 0.0 && digits.len() < max {
+        frac *= radix as f64;
+        let d = frac.trunc();
+        digits.push(RADIX_DIGITS[d as usize] as char);
+        frac -= d;
+    }
+    let more = if frac > 0.0 { "…" } else { "" };
+    let sign = if n < 0.0 { "-" } else { "" };
+    format!("{}{}{}.{}{}", sign, radix_prefix(radix), radix_digits(a.trunc() as u128, radix, group), digits, more)
+}
+
+/// Ángulo en grados como grados, minutos y segundos: `12°30'15.5"` (segundos redondeados a
+/// la centésima, sin ceros finales). El lexer lee esta misma notación.
+pub fn format_dms(deg: f64) -> String {
+    if !deg.is_finite() {
+        return format!("{}°", deg);
+    }
+    let sign = if deg < 0.0 { "-" } else { "" };
+    // Se redondea el total en centésimas de segundo para que el acarreo (59.999" -> 1') sea exacto
+    let cs = (deg.abs() * 360_000.0).round();
+    let d = (cs / 360_000.0).trunc();
+    let m = ((cs - d * 360_000.0) / 6_000.0).trunc();
+    let sec = (cs - d * 360_000.0 - m * 6_000.0) / 100.0;
+    let sec = format!("{:.2}", sec);
+    format!("{}{}°{}'{}\"", sign, d, m, trim_zeros(&sec))
+}
+
+/// Formatea un real según el formato numérico activo; `group` separa los miles.
+pub fn format_real(n: f64, fmt: OutputFormat, group: bool) -> String {
+    if !n.is_finite() {
+        return format!("{}", n);
+    }
+    let s = match fmt {
+        OutputFormat::Decimal => format!("{}", n),      // Comportamiento estándar
+        OutputFormat::Scientific => format!("{:.6e}", n), // Científico con 6 decimales
+        OutputFormat::Fixed(d) => format!("{:.*}", d, n),
+        OutputFormat::Significant(d) => significant(n, d),
+        OutputFormat::Engineering if n == 0.0 => "0".to_string(),
+        OutputFormat::Engineering => match engineering(n) {
+            (m, 0) => m,
+            (m, e) => format!("{}e{}", m, e),
+        },
+        OutputFormat::Si if n == 0.0 => "0".to_string(),
+        OutputFormat::Si => {
+            let (m, e) = engineering(n);
+            match SI_PREFIXES.get(((e + 24) / 3) as usize) {
+                Some(p) if (-24..=24).contains(&e) => format!("{}{}", m, p),
+                _ => format!("{}e{}", m, e),
+            }
+        }
+    };
+    if group { group_thousands(&s) } else { s }
+}
+
+/// "9.81 ± 0.02": σ con una cifra significativa (dos si empieza por 1) y el valor redondeado
+/// a la misma posición decimal; "(6.022 ± 0.003)e23" para magnitudes muy grandes o pequeñas.
+pub fn format_measurement(value: f64, sigma: f64) -> String {
+    if sigma == 0.0 || !sigma.is_finite() || !value.is_finite() {
+        return format!("{} ± {}", value, sigma);
+    }
+    let e = sigma.log10().floor() as i32;
+    let digits = if sigma / 10f64.powi(e) < 2.0 { 2 } else { 1 };
+    // Exponente de la última cifra que se muestra
+    let place = e + 1 - digits;
+    let round = |x: f64| (x / 10f64.powi(place)).round() * 10f64.powi(place);
+    let scale = if value == 0.0 { e } else { value.abs().log10().floor() as i32 }.max(e);
+    if (-3..6).contains(&scale) {
+        let d = (-place).max(0) as usize;
+        return format!("{:.*} ± {:.*}", d, round(value), d, round(sigma));
+    }
+    let d = (scale - place) as usize;
+    let unit = 10f64.powi(scale);
+    format!("({:.*} ± {:.*})e{}", d, round(value) / unit, d, round(sigma) / unit, scale)
+}
+
+/// Formatea un complejo: `a + bi`, polar `r∠θ` (θ en la unidad angular `unit`) o
+/// exponencial `r·e^(iθ)` (θ siempre en radianes), con `f` para cada número real.
+/// Los reales se muestran sin más.
+fn format_complex(val: Complex64, style: ComplexFormat, unit: AngleUnit, f: impl Fn(f64) -> String) -> String {
+    if val.im.abs() < IM_EPS {
+        return f(val.re);
+    }
+    match style {
+        ComplexFormat::Rectangular => {
+            let sign = if val.im >= 0.0 { "+" } else { "-" };
+            format!("{} {} {}i", f(val.re), sign, f(val.im.abs()))
+        }
+        ComplexFormat::Polar => {
+            let theta = val.arg() * unit.per_radian();
+            format!("{}∠{}", f(val.norm()), f(theta))
+        }
+        ComplexFormat::Exponential => format!("{}·e^(i{})", f(val.norm()), f(val.arg())),
+    }
+}
+
+impl Calculator {
+    /// Texto de `val` en la base `radix` con los demás ajustes de presentación actuales.
+    fn format_in(&self, val: Complex64, radix: u32) -> String {
+        format_complex(val, self.complex_format, self.angle, |n| self.format_real_in(n, radix))
+    }
+
+    fn format_real_in(&self, n: f64, radix: u32) -> String {
+        if radix == 10 {
+            format_real(n, self.output_format, self.digit_grouping)
+        } else {
+            format_radix(n, radix, self.digit_grouping)
+        }
+    }
+
+    /// Texto de `val` con los ajustes de presentación actuales (`fmt`, `complex`, `base`).
+    pub fn format(&self, val: Complex64) -> String {
+        self.format_in(val, self.radix)
+    }
+
+    /// Texto de un real con el formato numérico actual.
+    pub fn format_real(&self, n: f64) -> String {
+        self.format_real_in(n, self.radix)
+    }
+
+    /// Texto de un valor con incertidumbre: los reales como `format_measurement`, los
+    /// complejos con el formato actual entre paréntesis seguido de `± σ`.
+    pub fn format_uncertain(&self, u: &Uncertain) -> String {
+        if u.value.im.abs() < IM_EPS {
+            format_measurement(u.value.re, u.sigma())
+        } else {
+            format!("({}) ± {}", self.format_in(u.value, 10), format_real(u.sigma(), OutputFormat::Significant(2), false))
+        }
+    }
+
+    /// Texto del resultado del último `evaluate`: en la base pedida por `bin`/`oct`/`hex`
+    /// si la expresión es una de esas llamadas, en grados, minutos y segundos si es `dms(...)`,
+    /// con todas sus cifras si es un entero exacto o un resultado del modo programador, con «≈»
+    /// si es un entero grande aproximado, y con su incertidumbre si es una medida.
+    pub fn format_result(&self, val: Complex64) -> String {
+        let radix = self.result_radix.unwrap_or(self.radix);
+        // Modo programador: decimal con el signo de la palabra, o su patrón de bits en otra base
+        if let (Some(w), Some(p)) = (self.word, self.word_result) {
+            return if radix == 10 { w.decimal(p) } else { format!("{}{}", radix_prefix(radix), radix_digits(p, radix, self.digit_grouping)) };
+        }
+        if self.result_dms && val.im == 0.0 {
+            return format_dms(self.angle.convert(val, AngleUnit::Deg).re);
+        }
+        if let Some(u) = self.uncertainty("ans").filter(|u| u.value == val && radix == 10) {
+            return self.format_uncertain(u);
+        }
+        match &self.exact_result {
+            Some(n) if radix == 10 => n.to_string(),
+            Some(n) => format_big_radix(n, radix, self.digit_grouping),
+            // Entero aproximado: en notación científica, sin rellenar con ceros cifras que no se conocen
+            None if self.approx_result && radix == 10 => match self.output_format {
+                OutputFormat::Decimal | OutputFormat::Fixed(_) => format!("≈ {:e}", val.re),
+                fmt => format!("≈ {}", format_real(val.re, fmt, self.digit_grouping)),
+            },
+            None => self.format_in(val, radix),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Reconocimiento de formas cerradas (`identify`): racionales pequeños, múltiplos racionales
+//! de constantes, raíces cuadradas, logaritmos y combinaciones (a + b·c)/d halladas con PSLQ.
+
+use std::f64::consts::{E, LN_2, PI, SQRT_2};
+
+/// Denominador (y numerador) máximo de los racionales que se consideran "pequeños".
+const MAX_DEN: i64 = 1000;
+/// Coeficiente máximo de las relaciones enteras de PSLQ.
+const MAX_COEFF: f64 = 100.0;
+const PSLQ_MAX_ITER: usize = 200;
+/// Número máximo de sugerencias.
+const MAX_RESULTS: usize = 5;
+/// Tolerancia relativa por defecto de `identify`: deja margen al error de redondeo de f64.
+pub const DEFAULT_TOL: f64 = 1e-12;
+
+/// Constantes para los múltiplos racionales `p/q·c`.
+const MULTIPLES: [(&str, f64); 4] = [("π", PI), ("π²", PI * PI), ("e", E), ("ln(2)", LN_2)];
+
+/// Constantes para las combinaciones (a + b·c)/d de PSLQ.
+const PSLQ_BASIS: [(&str, f64); 6] = [
+    ("√2", SQRT_2),
+    ("√3", 1.732_050_807_568_877_2),
+    ("√5", 2.236_067_977_499_79),
+    ("π", PI),
+    ("e", E),
+    ("ln(2)", LN_2),
+];
+
+/// Forma cerrada candidata: su expresión, su valor y su distancia al número buscado.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedForm {
+    pub expr: String,
+    pub value: f64,
+    pub error: f64,
+    /// Tamaño de la expresión (cifras de sus enteros y tipo): menor es más simple.
+    complexity: usize,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn digits(n: i64) -> usize {
+    n.unsigned_abs().to_string().len()
+}
+
+/// Convergente de menor denominador de la fracción continua de `x` con |x − p/q| <= tol,
+/// si lo hay con q y |p| <= `MAX_DEN`.
+pub fn rational(x: f64, tol: f64) -> Option<(i64, i64)> {
+    let (mut h0, mut h1, mut k0, mut k1) = (0i64, 1i64, 1i64, 0i64);
+    let mut r = x;
+    for _ in 0..64 {
+        let a = r.floor();
+        if !a.is_finite() || a.abs() > MAX_DEN as f64 * 2.0 {
+            return None;
+        }
+        let a = a as i64;
+        let (h, k) = (a * h1 + h0, a * k1 + k0);
+        if k > MAX_DEN || h.abs() > MAX_DEN * MAX_DEN {
+            return None;
+        }
+        if (x - h as f64 / k as f64).abs() <= tol {
+            return Some((h, k));
+        }
+        (h0, h1, k0, k1) = (h1, h, k1, k);
+        let f = r - a as f64;
+        if f == 0.0 {
+            return None;
+        }
+        r = 1.0 / f;
+    }
+    None
+}
+
+/// "p/q" con el signo delante; "p" si q = 1.
+fn fraction(p: i64, q: i64) -> String {
+    if q == 1 { p.to_string() } else { format!("{}/{}", p, q) }
+}
+
+/// p/q·c como "3π/4", "-π", "2e".
+fn multiple(p: i64, q: i64, c: &str) -> String {
+    let num = match p {
+        1 => c.to_string(),
+        -1 => format!("-{}", c),
+        _ => format!("{}{}", p, c),
+    };
+    if q == 1 { num } else { format!("{}/{}", num, q) }
+}
+
+/// (s/q)·√m reducido, con m libre de cuadrados: √(n/d) = √(n·d)/d.
+fn sqrt_form(n: i64, d: i64, negative: bool) -> Option<(String, usize)> {
+    let mut m = n.checked_mul(d)?;
+    if m <= 0 || m > 1_000_000 {
+        return None;
+    }
+    let mut s = 1i64;
+    let mut f = 2i64;
+    while f * f <= m {
+        while m % (f * f) == 0 {
+            m /= f * f;
+            s *= f;
+        }
+        f += 1;
+    }
+    if m == 1 {
+        return None; // racional: ya lo encuentra `rational`
+    }
+    let g = gcd(s, d);
+    let (s, d) = (s / g, d / g);
+    let sign = if negative { "-" } else { "" };
+    let num = if s == 1 { format!("{}√{}", sign, m) } else { format!("{}{}√{}", sign, s, m) };
+    let expr = if d == 1 { num } else { format!("{}/{}", num, d) };
+    Some((expr, digits(s) + digits(m) + digits(d) + 1))
+}
+
+/// Reducción de Hermite de la fila `i` de H sobre las columnas `max_j`, ..., 0, aplicada
+/// también a `y` y a las columnas de B (cuyas columnas son las relaciones candidatas).
+fn hermite_reduce(h: &mut [Vec<f64>], y: &mut [f64], b: &mut [Vec<f64>], i: usize, max_j: usize) {
+    for j in (0..=max_j.min(i - 1)).rev() {
+        let t = (h[i][j] / h[j][j]).round();
+        if t == 0.0 {
+            continue;
+        }
+        y[j] += t * y[i];
+        let (upper, lower) = h.split_at_mut(i);
+        for (hi, hj) in lower[0][..=j].iter_mut().zip(&upper[j][..=j]) {
+            *hi -= t * hj;
+        }
+        for row in b.iter_mut() {
+            row[j] += t * row[i];
+        }
+    }
+}
+
+/// Relación entera Σ aᵢ·xᵢ = 0 con |aᵢ| <= `MAX_COEFF` por PSLQ (Ferguson–Bailey) en f64.
+pub fn pslq(x: &[f64], tol: f64) -> Option<Vec<i64>> {
+    let n = x.len();
+    let norm = x.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if n < 2 || norm == 0.0 || !norm.is_finite() {
+        return None;
+    }
+    let gamma = (4.0f64 / 3.0).sqrt();
+    let mut y: Vec<f64> = x.iter().map(|v| v / norm).collect();
+    // s[k] = ‖y[k..]‖
+    let s: Vec<f64> = (0..n).map(|k| y[k..].iter().map(|v| v * v).sum::<f64>().sqrt()).collect();
+    let mut h = vec![vec![0.0; n - 1]; n];
+    for i in 0..n {
+        for j in 0..(n - 1).min(i + 1) {
+            h[i][j] = if i == j { s[j + 1] / s[j] } else { -y[i] * y[j] / (s[j] * s[j + 1]) };
+        }
+    }
+    let mut b: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for i in 1..n {
+        hermite_reduce(&mut h, &mut y, &mut b, i, i - 1);
+    }
+    let weight = |h: &[Vec<f64>], i: usize| gamma.powi(i as i32 + 1) * h[i][i].abs();
+    for _ in 0..PSLQ_MAX_ITER {
+        let m = (0..n - 1).max_by(|&p, &q| weight(&h, p).total_cmp(&weight(&h, q))).unwrap_or(0);
+        y.swap(m, m + 1);
+        h.swap(m, m + 1);
+        for row in b.iter_mut() {
+            row.swap(m, m + 1);
+        }
+        // Rotación para devolver H a forma trapezoidal inferior
+        if m + 2 < n {
+            let t0 = h[m][m].hypot(h[m][m + 1]);
+            let (t1, t2) = (h[m][m] / t0, h[m][m + 1] / t0);
+            for row in h.iter_mut().skip(m) {
+                let (t3, t4) = (row[m], row[m + 1]);
+                row[m] = t1 * t3 + t2 * t4;
+                row[m + 1] = -t2 * t3 + t1 * t4;
+            }
+        }
+        for i in m + 1..n {
+            hermite_reduce(&mut h, &mut y, &mut b, i, m + 1);
+        }
+        if b.iter().flatten().any(|v| v.abs() > 1e9) {
+            return None;
+        }
+        if let Some(j) = (0..n).min_by(|&p, &q| y[p].abs().total_cmp(&y[q].abs()))
+            && y[j].abs() <= tol
+        {
+            let rel: Vec<f64> = b.iter().map(|row| row[j]).collect();
+            return rel.iter().all(|v| v.abs() <= MAX_COEFF).then(|| rel.iter().map(|v| *v as i64).collect());
+        }
+    }
+    None
+}
+
+/// (a + b·c)/d como "(1 + √5)/2", "2 - π", "(3e - 1)/4"; con a = 0, como `multiple`.
+fn linear_form(a: i64, b: i64, c: &str, d: i64) -> String {
+    if a == 0 {
+        return multiple(b, d, c);
+    }
+    let term = match b.abs() {
+        1 => c.to_string(),
+        k => format!("{}{}", k, c),
+    };
+    let body = match (a < 0, b < 0) {
+        (false, false) => format!("{} + {}", a, term),
+        (false, true) => format!("{} - {}", a, term),
+        (true, false) => format!("{} - {}", term, -a),
+        (true, true) => format!("{} - {}", a, term),
+    };
+    if d == 1 { body } else { format!("({})/{}", body, d) }
+}
+
+/// Formas cerradas de `x` a distancia <= `tol`·|x|, de la más simple a la más compleja.
+/// La tolerancia es relativa para que un valor diminuto no se confunda con 0, y entre
+/// formas del mismo valor (0 y ln(1)) solo se muestra la más simple.
+pub fn identify(x: f64, tol: f64) -> Vec<ClosedForm> {
+    if !x.is_finite() {
+        return Vec::new();
+    }
+    let tol = tol * x.abs();
+    let mut found: Vec<ClosedForm> = Vec::new();
+    let mut push = |expr: String, value: f64, complexity: usize| {
+        let error = (x - value).abs();
+        if error <= tol && value.is_finite() && !found.iter().any(|f| f.expr == expr) {
+            found.push(ClosedForm { expr, value, error, complexity });
+        }
+    };
+
+    // Racionales pequeños
+    if let Some((p, q)) = rational(x, tol) {
+        push(fraction(p, q), p as f64 / q as f64, digits(p) + digits(q));
+    }
+    // Múltiplos racionales de constantes
+    for (name, c) in MULTIPLES {
+        if let Some((p, q)) = rational(x / c, tol / c)
+            && p != 0
+        {
+            push(multiple(p, q, name), p as f64 / q as f64 * c, digits(p) + digits(q) + 1);
+        }
+    }
+    // Raíces cuadradas: x² racional
+    if let Some((p, q)) = rational(x * x, 2.0 * x.abs() * tol)
+        && let Some((expr, complexity)) = sqrt_form(p, q, x < 0.0)
+    {
+        push(expr, x.signum() * (p as f64 / q as f64).sqrt(), complexity);
+    }
+    // Logaritmos: e^x racional
+    if x != 0.0
+        && let Some((p, q)) = rational(x.exp(), x.exp() * tol)
+        && p > 0
+    {
+        let expr = match (p, q) {
+            (p, 1) => format!("ln({})", p),
+            (1, q) => format!("-ln({})", q),
+            (p, q) => format!("ln({}/{})", p, q),
+        };
+        push(expr, (p as f64 / q as f64).ln(), digits(p) + digits(q) + 2);
+    }
+    // Relaciones enteras d·x − a − b·c = 0 (PSLQ)
+    for (name, c) in PSLQ_BASIS {
+        if let Some(rel) = pslq(&[x, 1.0, c], tol.max(1e-14))
+            && rel[0] != 0
+            && rel[2] != 0
+        {
+            let sign = rel[0].signum();
+            let (d, a, b) = (rel[0] * sign, -rel[1] * sign, -rel[2] * sign);
+            let g = gcd(gcd(d, a), b);
+            let (d, a, b) = (d / g, a / g, b / g);
+            push(linear_form(a, b, name, d), (a as f64 + b as f64 * c) / d as f64, digits(a) + digits(b) + digits(d) + 2);
+        }
+    }
+
+    found.sort_by(|p, q| p.complexity.cmp(&q.complexity).then(p.error.total_cmp(&q.error)));
+    found.dedup_by(|q, p| (p.value - q.value).abs() <= 8.0 * f64::EPSILON * p.value.abs().max(q.value.abs()));
+    found.truncate(MAX_RESULTS);
+    found
+}
@@ -1,4 +1,22 @@
-use super::{token::Token, Calculator};
+use super::{combinatorics::COMB_FUNCS, distributions::DIST_FUNCS, finance::FIN_FUNCS, numtheory::INT_FUNCS, random::RANDOM_FUNCS, stats::STAT_FUNCS, token::Token, Calculator};
+
+/// Funciones predefinidas (además de las estadísticas de `STAT_FUNCS`, las
+/// distribuciones de `DIST_FUNCS`, las aleatorias de `RANDOM_FUNCS`, las enteras
+/// de `INT_FUNCS`, las combinatorias de `COMB_FUNCS` y las financieras de `FIN_FUNCS`).
+const FUNCS: &[&str] = &[
+    "sin","cos","tan","asin","acos","atan","atan2","hypot",
+    "sqrt","cbrt","root","log","ln","cis",
+    "mcd","mcm","exp","arg","conj","re","im","pow",
+    "floor","ceil","abs","round","trunc","sign",
+    "sinh","cosh","tanh","asinh","acosh","atanh",
+    "deg2rad","rad2deg","deg","rad","grad","dms","cm2in","in2cm","m2ft","ft2m",
+    "fact","comb","perm","nCr","nPr",
+    "exp","log10","log2","pow","min","max","mod",
+    "pct","applypct","r3d","r3i",
+    "bin", "oct", "hex", "sum", "prod",
+    "gamma","lgamma","digamma","beta","zeta","erf","erfc","erfinv",
+    "besselj","bessely","besseli","besselk","airy","lambertw","ellipk","ellipe",
+];
 
 /// Lee un número en formato:
 ///   123
@@ -30,10 +48,10 @@ where
         } else if (c == 'e' || c == 'E') && !saw_exp && saw_digit {
             saw_exp = true;
             s.push(chars.next().unwrap());
-            if let Some(&sign) = chars.peek() {
-                if sign == '+' || sign == '-' {
-                    s.push(chars.next().unwrap());
-                }
+            if let Some(&sign) = chars.peek()
+                && (sign == '+' || sign == '-')
+            {
+                s.push(chars.next().unwrap());
             }
         } else {
             break;
@@ -43,19 +61,163 @@ where
     s
 }
 
+/// Literal entero en base 2, 8 o 16 (`0b1010`, `0o17`, `0xff`) si `chars` empieza por uno;
+/// si no, no consume nada.
+fn read_radix_literal<I>(chars: &mut std::iter::Peekable<I>) -> Option<Result<i128, String>>
+where
+    I: Iterator<Item = char> + Clone,
+{
+    let mut ahead = chars.clone();
+    if ahead.next() != Some('0') {
+        return None;
+    }
+    let (radix, prefix) = match ahead.next() {
+        Some(p @ ('x' | 'X')) => (16, p),
+        Some(p @ ('o' | 'O')) => (8, p),
+        Some(p @ ('b' | 'B')) => (2, p),
+        _ => return None,
+    };
+    if !ahead.peek().is_some_and(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let mut digits = String::new();
+    while let Some(&c) = ahead.peek() {
+        if !c.is_ascii_alphanumeric() {
+            break;
+        }
+        digits.push(c);
+        ahead.next();
+    }
+    *chars = ahead;
+    Some(i128::from_str_radix(&digits, radix).map_err(|e| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => format!("Literal fuera de rango: 0{}{}", prefix, digits),
+        _ => format!("Número inválido: 0{}{}", prefix, digits),
+    }))
+}
+
+/// Token de un literal numérico (ya leído en `s`): los enteros que no caben exactamente
+/// en f64 se conservan enteros para la aritmética exacta.
+fn number_token(calc: &Calculator, s: &str) -> Result<Token, String> {
+    if !s.contains(['.', 'e', 'E'])
+        && let Ok(n) = s.parse::<i128>()
+        && n.unsigned_abs() > 1 << 53
+    {
+        return Ok(Token::Int(n));
+    }
+    calc.parse_number_str(s).map(Token::Number)
+}
+
+/// Lee `N` seguido de `mark` (o de `alt`) si `chars` empieza así; si no, no consume nada.
+fn read_marked<I>(chars: &mut std::iter::Peekable<I>, mark: char, alt: char) -> Option<Result<f64, String>>
+where
+    I: Iterator<Item = char> + Clone,
+{
+    let mut ahead = chars.clone();
+    let s = read_number(&mut ahead, None);
+    if s.is_empty() || !matches!(ahead.next(), Some(c) if c == mark || c == alt) {
+        return None;
+    }
+    *chars = ahead;
+    Some(s.parse::<f64>().map_err(|_| format!("Número inválido: {s}{mark}")))
+}
+
+/// Sufijo de unidad angular tras el literal `value`: `°` con minutos y segundos opcionales
+/// (`12°30'15"`, también con ′ y ″), `deg`, `rad` o `grad`. Devuelve la función de unidad y
+/// el valor en esa unidad; si no hay sufijo, no consume nada.
+fn read_angle_suffix<I>(chars: &mut std::iter::Peekable<I>, value: f64) -> Option<Result<(&'static str, f64), String>>
+where
+    I: Iterator<Item = char> + Clone,
+{
+    if chars.peek() == Some(&'°') {
+        chars.next();
+        let minutes = match read_marked(chars, '\'', '′').transpose() {
+            Ok(m) => m.unwrap_or(0.0),
+            Err(e) => return Some(Err(e)),
+        };
+        let seconds = match read_marked(chars, '"', '″').transpose() {
+            Ok(s) => s.unwrap_or(0.0),
+            Err(e) => return Some(Err(e)),
+        };
+        if minutes >= 60.0 || seconds >= 60.0 {
+            return Some(Err("Minutos y segundos de arco deben ser menores que 60".to_string()));
+        }
+        // El signo del literal afecta a todo el ángulo: -12°30' = -(12 + 30/60)
+        let deg = value.abs() + minutes / 60.0 + seconds / 3600.0;
+        return Some(Ok(("deg", if value.is_sign_negative() { -deg } else { deg })));
+    }
+    let mut ahead = chars.clone();
+    let mut word = String::new();
+    while let Some(c) = ahead.peek().copied().filter(|c| c.is_ascii_alphanumeric()) {
+        word.push(c);
+        ahead.next();
+    }
+    let unit = match word.as_str() {
+        "deg" => "deg",
+        "rad" => "rad",
+        "grad" => "grad",
+        _ => return None,
+    };
+    *chars = ahead;
+    Some(Ok((unit, value)))
+}
+
+/// Añade el token de un literal, como `unidad(valor)` si lleva sufijo angular.
+fn push_literal<I>(tokens: &mut Vec<Token>, token: Token, chars: &mut std::iter::Peekable<I>) -> Result<(), String>
+where
+    I: Iterator<Item = char> + Clone,
+{
+    if let Token::Number(n) = token
+        && let Some(suffix) = read_angle_suffix(chars, n.re)
+    {
+        let (unit, value) = suffix?;
+        tokens.extend([Token::Func(unit.to_string()), Token::LParen, Token::Number(num_complex::Complex64::new(value, 0.0)), Token::RParen]);
+    } else {
+        tokens.push(token);
+    }
+    Ok(())
+}
+
+/// Constantes y nombres del último resultado: no se pueden asignar.
+pub const RESERVED_NAMES: &[&str] = &["pi", "e", "tau", "phi", "golden", "c", "i", "inf", "ans", "last"];
+
+/// Nombre válido de variable o función: letra ASCII seguida de letras, dígitos o '_'.
+pub fn is_identifier(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Token de un entero exacto: `Int` solo si f64 no lo representa sin redondeo.
+fn int_token(n: i128) -> Token {
+    if n.unsigned_abs() > 1 << 53 { Token::Int(n) } else { Token::Number(num_complex::Complex64::new(n as f64, 0.0)) }
+}
+
 impl Calculator {
+    /// ¿Es `name` una función predefinida (no redefinible por el usuario)?
+    pub(crate) fn is_builtin_func(&self, name: &str) -> bool {
+        FUNCS.contains(&name)
+            || STAT_FUNCS.contains(&name)
+            || DIST_FUNCS.contains(&name)
+            || RANDOM_FUNCS.contains(&name)
+            || INT_FUNCS.contains(&name)
+            || COMB_FUNCS.contains(&name)
+            || FIN_FUNCS.contains(&name)
+    }
+
     pub fn tokenize(&self, expr: &str) -> Result<Vec<Token>, String> {
         let mut tokens = Vec::new();
-        let cleaned = expr.replace(' ', "");
+        let cleaned = expr.replace(' ', "").replace("+/-", "±");
         let mut chars = cleaned.chars().peekable();
         let mut last_token_was_op = true;
 
         while let Some(&c) = chars.peek() {
             match c {
                 '0'..='9' | '.' => {
-                    let s = read_number(&mut chars, None);
-                    let n = self.parse_number_str(&s)?;
-                    tokens.push(Token::Number(n));
+                    match read_radix_literal(&mut chars) {
+                        Some(n) => tokens.push(int_token(n?)),
+                        None => {
+                            let s = read_number(&mut chars, None);
+                            push_literal(&mut tokens, number_token(self, &s)?, &mut chars)?;
+                        }
+                    }
                     last_token_was_op = false;
                 }
 
@@ -63,9 +225,13 @@ impl Calculator {
                     chars.next();
                     if let Some(&nextc) = chars.peek() {
                         if nextc.is_ascii_digit() || nextc == '.' {
-                            let s = read_number(&mut chars, Some('-'));
-                            let n = self.parse_number_str(&s)?;
-                            tokens.push(Token::Number(n));
+                            match read_radix_literal(&mut chars) {
+                                Some(n) => tokens.push(int_token(-n?)),
+                                None => {
+                                    let s = read_number(&mut chars, Some('-'));
+                                    push_literal(&mut tokens, number_token(self, &s)?, &mut chars)?;
+                                }
+                            }
                             last_token_was_op = false;
                         } else {
                             tokens.push(Token::Number(num_complex::Complex64::new(-1.0, 0.0)));
@@ -79,7 +245,9 @@ impl Calculator {
                     }
                 }
 
-                '+' | '-' | '*' | '/' | '^' | '%' => {
+                // '∠': forma polar r∠θ (fasor), con θ en la unidad angular activa
+                // '±': medida con incertidumbre (también se puede escribir +/-)
+                '+' | '-' | '*' | '/' | '^' | '%' | '∠' | '±' => {
                     tokens.push(Token::Op(chars.next().unwrap()));
                     last_token_was_op = true;
                 }
@@ -105,27 +273,17 @@ impl Calculator {
                 'a'..='z' | 'A'..='Z' => {
                     let mut name = String::new();
                     while let Some(&c2) = chars.peek() {
-                        if c2.is_alphanumeric() {
+                        if c2.is_alphanumeric() || c2 == '_' {
                             name.push(chars.next().unwrap());
                         } else {
                             break;
                         }
                     }
 
-                    let funcs = [
-                        "sin","cos","tan","asin","acos","atan","atan2","hypot",
-                        "sqrt","cbrt","root","log","ln",
-                        "mcd","mcm","exp","arg","conj","re","im","pow",
-                        "floor","ceil","abs","round","trunc","sign",
-                        "sinh","cosh","tanh","asinh","acosh","atanh",
-                        "deg2rad","rad2deg","cm2in","in2cm","m2ft","ft2m",
-                        "fact","comb","perm","nCr","nPr",
-                        "exp","log10","log2","pow","min","max","mod","isprime",
-                        "nextprime","rand","pct","applypct","r3d","r3i",
-                        "bin", "oct", "hex",
-                    ];
-
-                    if funcs.contains(&name.as_str()) {
+                    // Los nombres de las tablas añadidas (mean, var, rate, ...) solo son funciones
+                    // si van seguidos de '(': así no ocultan variables del usuario con ese nombre
+                    let call = chars.peek() == Some(&'(');
+                    if FUNCS.contains(&name.as_str()) || self.funcs.contains_key(&name) || (call && self.is_builtin_func(&name)) {
                         tokens.push(Token::Func(name));
                         last_token_was_op = true;
                     } else {
@@ -0,0 +1,85 @@
+//! Medidas con incertidumbre (`9.81 ± 0.02`) y su propagación lineal de primer orden.
+//!
+//! Cada `±` evaluado es una fuente de error independiente. El valor la lleva como una
+//! componente dual más, ya multiplicada por su σ, de modo que las reglas de `dual::tangent`
+//! dan ∂f/∂xₖ·σₖ para cualquier función, y una misma medida usada dos veces (`g*g`) queda
+//! correlacionada consigo misma. La incertidumbre típica es √Σ(∂f/∂xₖ·σₖ)² y la cota en el
+//! peor caso (aritmética de intervalos linealizada) es Σ|∂f/∂xₖ|·σₖ.
+
+use super::dual::Dual;
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Sensibilidades de un valor guardado a cada fuente `±`, junto con el valor al que
+/// pertenecen (si la variable cambia por otra vía, dejan de aplicarse).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uncertain {
+    pub value: Complex64,
+    pub eps: Vec<Complex64>,
+}
+
+impl Uncertain {
+    /// Parte de incertidumbre de `d`, cuyas fuentes empiezan en la componente `offset`
+    /// (las anteriores son semillas de derivación). `None` si no depende de ninguna.
+    pub(crate) fn from_dual(d: &Dual, offset: usize) -> Option<Self> {
+        let eps = d.eps.get(offset..).unwrap_or_default();
+        let last = eps.iter().rposition(|e| e.norm() != 0.0)?;
+        Some(Uncertain { value: d.val, eps: eps[..=last].to_vec() })
+    }
+
+    /// Valor dual con las fuentes a partir de la componente `offset`.
+    pub(crate) fn to_dual(&self, offset: usize) -> Dual {
+        let mut eps = vec![Complex64::new(0.0, 0.0); offset];
+        eps.extend_from_slice(&self.eps);
+        Dual { val: self.value, eps }
+    }
+
+    /// Incertidumbre típica (suma cuadrática de las contribuciones).
+    pub fn sigma(&self) -> f64 {
+        self.eps.iter().map(|e| e.norm_sqr()).sum::<f64>().sqrt()
+    }
+
+    /// Semiancho del intervalo en el peor caso: todas las fuentes en su extremo desfavorable.
+    pub fn worst_case(&self) -> f64 {
+        self.eps.iter().map(|e| e.norm()).sum()
+    }
+
+    /// Número de fuentes de las que depende.
+    pub fn sources(&self) -> usize {
+        self.eps.iter().filter(|e| e.norm() != 0.0).count()
+    }
+}
+
+impl Calculator {
+    /// Incertidumbre de la variable `name` (`ans` para el último resultado), si la tiene.
+    pub fn uncertainty(&self, name: &str) -> Option<&Uncertain> {
+        let value = if name == "ans" || name == "last" { self.last_result } else { *self.vars.get(name)? };
+        let key = if name == "last" { "ans" } else { name };
+        self.uncertain.get(key).filter(|u| u.value == value)
+    }
+
+    /// Asigna `name = val`; si `val` es el último resultado, conserva su incertidumbre.
+    pub fn assign(&mut self, name: &str, val: Complex64) {
+        self.vars.insert(name.to_string(), val);
+        match self.uncertainty("ans").filter(|u| u.value == val).cloned() {
+            Some(u) => { self.uncertain.insert(name.to_string(), u); }
+            None => { self.uncertain.remove(name); }
+        }
+    }
+
+    /// Las fuentes nuevas de una evaluación se numeran tras las de los valores guardados.
+    pub(crate) fn reset_sources(&mut self) {
+        self.next_source = self.uncertain.values().map(|u| u.eps.len()).max().unwrap_or(0);
+    }
+
+    /// `a ± σ`: el valor `a` con una fuente de error nueva de desviación `sigma`.
+    pub(crate) fn add_source(&mut self, mut a: Dual, sigma: f64, offset: usize) -> Dual {
+        let k = offset + self.next_source;
+        self.next_source += 1;
+        if a.eps.len() <= k {
+            a.eps.resize(k + 1, Complex64::new(0.0, 0.0));
+        }
+        a.eps[k] += sigma;
+        a
+    }
+}
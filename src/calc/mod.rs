@@ -1,24 +1,174 @@
+pub mod bessel;
+pub mod calculus;
+pub mod combinatorics;
+pub mod distributions;
+pub mod dual;
 pub mod eval;
+pub mod finance;
+pub mod format;
+pub mod fit;
+pub mod identify;
 pub mod lexer;
+pub mod measure;
+pub mod numtheory;
+pub mod ode;
 pub mod plot;
+pub mod programmer;
+pub mod random;
+pub mod series;
+pub mod special;
+pub mod stats;
+pub mod taylor;
 pub mod token;
 
+#[cfg(test)]
+mod tests;
+
 use std::collections::HashMap;
+use dual::Dual;
+use measure::Uncertain;
+use programmer::WordMode;
+use random::Rng;
+use num_bigint::BigInt;
 use num_complex::Complex64;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OutputFormat {
     Decimal,
     Scientific,
+    /// `fmt fix N`: N decimales fijos
+    Fixed(usize),
+    /// `fmt sig N`: N cifras significativas (como `%g`)
+    Significant(usize),
+    /// `fmt eng`: exponente múltiplo de 3
+    Engineering,
+    /// `fmt si`: prefijos SI (4.7k, 22µ)
+    Si,
+}
+
+/// Unidad angular activa (`mode rad|deg|grad`): la de los argumentos de sin/cos/tan/cis/∠
+/// y los resultados de asin/acos/atan/atan2 y del ángulo de la forma polar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AngleUnit {
+    Rad,
+    Deg,
+    /// Gradianes (gon): 400 por vuelta
+    Grad,
+}
+
+impl AngleUnit {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "rad" => Some(AngleUnit::Rad),
+            "deg" => Some(AngleUnit::Deg),
+            "grad" | "gon" => Some(AngleUnit::Grad),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AngleUnit::Rad => "RAD",
+            AngleUnit::Deg => "DEG",
+            AngleUnit::Grad => "GRAD",
+        }
+    }
+
+    /// Unidades por vuelta completa.
+    fn per_turn(self) -> f64 {
+        match self {
+            AngleUnit::Rad => std::f64::consts::TAU,
+            AngleUnit::Deg => 360.0,
+            AngleUnit::Grad => 400.0,
+        }
+    }
+
+    /// Unidades por radián.
+    pub fn per_radian(self) -> f64 {
+        self.per_turn() / std::f64::consts::TAU
+    }
+
+    /// Ángulo `z` en esta unidad, expresado en la unidad `to`. Se escala el complejo entero,
+    /// de modo que las funciones inversas deshacen exactamente a las directas.
+    pub fn convert(self, z: Complex64, to: AngleUnit) -> Complex64 {
+        if self == to { z } else { z * (to.per_turn() / self.per_turn()) }
+    }
+
+    /// Ángulo `z` expresado en esta unidad, en radianes.
+    pub fn to_rad(self, z: Complex64) -> Complex64 {
+        self.convert(z, AngleUnit::Rad)
+    }
+
+    /// Ángulo `z` en radianes, expresado en esta unidad.
+    pub fn express_rad(self, z: Complex64) -> Complex64 {
+        AngleUnit::Rad.convert(z, self)
+    }
+}
+
+/// Forma de mostrar los complejos (comando `complex`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComplexFormat {
+    /// `a + bi`
+    Rectangular,
+    /// `r∠θ`, con θ en la unidad angular activa
+    Polar,
+    /// `r·e^(iθ)`, con θ en radianes
+    Exponential,
+}
+
+/// Función definida en tiempo de ejecución (p. ej. el modelo guardado por `fit`).
+#[derive(Debug, Clone)]
+pub struct UserFunc {
+    pub params: Vec<String>,
+    pub body: String,
+    pub(crate) rpn: Vec<eval::Rpn>,
 }
 
 pub struct Calculator {
     pub memory_stack: Vec<Complex64>, // Cambio a Complex
     pub vars: HashMap<String, Complex64>, // Cambio a Complex
     pub history_file: String,
-    pub is_radians: bool,
+    pub angle: AngleUnit,
     pub last_result: Complex64, // Cambio a Complex
     pub output_format: OutputFormat,
+    pub complex_format: ComplexFormat,
+    /// Separar los miles con espacios (`fmt group`).
+    pub digit_grouping: bool,
+    /// Base en que se muestran los resultados (`base N`, 2..=36).
+    pub radix: u32,
+    /// Sugerir una forma cerrada tras cada resultado real (`identify on`).
+    pub identify: bool,
+    /// Modo real estricto (`real on`): un resultado no real a partir de argumentos reales
+    /// es un error de dominio en lugar de un complejo.
+    pub real: bool,
+    /// Base pedida por el último `evaluate` con `bin`/`oct`/`hex` como llamada exterior.
+    pub result_radix: Option<u32>,
+    /// El último `evaluate` era `dms(...)`: el resultado se muestra en grados, minutos y segundos.
+    pub result_dms: bool,
+    /// Funciones de usuario, llamables en expresiones como las predefinidas.
+    pub funcs: HashMap<String, UserFunc>,
+    /// Pila de pares (x, y) para ajustes y regresión.
+    pub pairs: Vec<(f64, f64)>,
+    /// Variables locales activas: índices de `sum`/`prod` y parámetros de funciones de
+    /// usuario (el más interno al final); ocultan a `vars`.
+    pub(crate) scopes: Vec<(String, Dual)>,
+    /// Generador de `rand`, `randn`, ... (se fija con `seed`).
+    pub rng: Rng,
+    /// Valor exacto del último `evaluate` entero que no cabe sin redondeo en f64 (|n| > 2^53).
+    pub exact_result: Option<BigInt>,
+    /// El último `evaluate` usó funciones enteras pero tuvo que pasar a coma flotante y su
+    /// resultado es un entero grande solo aproximado (se muestra como tal).
+    pub approx_result: bool,
+    /// Módulo activo (`modulo n`): las expresiones se evalúan en enteros mod n.
+    pub modulus: Option<i128>,
+    /// Modo programador (`prog i32`): tamaño de palabra, signo y desbordamiento.
+    pub word: Option<WordMode>,
+    /// Patrón de bits del último `evaluate` en modo programador.
+    pub word_result: Option<u128>,
+    /// Incertidumbres (`±`) de las variables y, con la clave `ans`, del último resultado.
+    pub uncertain: HashMap<String, Uncertain>,
+    /// Siguiente fuente `±` libre en la evaluación en curso.
+    pub(crate) next_source: usize,
 }
 
 impl Calculator {
@@ -31,15 +181,34 @@ impl Calculator {
         vars.insert("golden".to_string(), Complex64::new(1.618033988749895, 0.0));
         vars.insert("c".to_string(), Complex64::new(299792458.0, 0.0));
         vars.insert("i".to_string(), Complex64::new(0.0, 1.0));
+        vars.insert("inf".to_string(), Complex64::new(f64::INFINITY, 0.0));
 
         Calculator {
             memory_stack: Vec::new(),
             vars,
             history_file: "historial.txt".to_string(),
-            is_radians: true, // Por defecto en Radianes
+            angle: AngleUnit::Rad, // Por defecto en Radianes
             last_result: Complex64::new(0.0, 0.0),
             // 3. Inicializamos en Decimal por defecto
             output_format: OutputFormat::Decimal, 
+            complex_format: ComplexFormat::Rectangular,
+            digit_grouping: false,
+            radix: 10,
+            identify: false,
+            real: false,
+            result_radix: None,
+            result_dms: false,
+            funcs: HashMap::new(),
+            pairs: Vec::new(),
+            scopes: Vec::new(),
+            rng: Rng::from_time(),
+            exact_result: None,
+            approx_result: false,
+            modulus: None,
+            word: None,
+            word_result: None,
+            uncertain: HashMap::new(),
+            next_source: 0,
         }
     }
 
@@ -54,10 +223,23 @@ impl Calculator {
         self.vars.insert("golden".to_string(), Complex64::new(1.618033988749895, 0.0));
         self.vars.insert("c".to_string(), Complex64::new(299792458.0, 0.0));
         self.vars.insert("i".to_string(), Complex64::new(0.0, 1.0));
+        self.vars.insert("inf".to_string(), Complex64::new(f64::INFINITY, 0.0));
         
         self.last_result = Complex64::new(0.0, 0.0);
-        self.is_radians = true;
+        self.angle = AngleUnit::Rad;
         self.output_format = OutputFormat::Decimal; // Reset también el formato
+        self.complex_format = ComplexFormat::Rectangular;
+        self.digit_grouping = false;
+        self.radix = 10;
+        self.identify = false;
+        self.real = false;
+        self.funcs.clear();
+        self.pairs.clear();
+        self.scopes.clear();
+        self.modulus = None;
+        self.word = None;
+        self.word_result = None;
+        self.uncertain.clear();
     }
     
     pub(crate) fn parse_number_str(&self, s: &str) -> Result<Complex64, String> {
@@ -66,3 +248,8 @@ impl Calculator {
             .map_err(|_| format!("Número inválido: {s}"))
     }
 }
+
+/// Número en un formato que el propio lexer vuelve a leer (para expresiones generadas).
+pub(crate) fn fmt_literal(v: f64) -> String {
+    if v != 0.0 && (v.abs() < 1e-4 || v.abs() >= 1e16) { format!("{:e}", v) } else { format!("{}", v) }
+}
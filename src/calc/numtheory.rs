@@ -0,0 +1,364 @@
+//! Teoría de números con enteros exactos. Las expresiones que llaman a alguna función de
+//! `INT_FUNCS` se evalúan primero en enteros de precisión arbitraria (así `isprime(2^127-1)`
+//! no pasa por f64 ni desborda en 2^127); las funciones reciben i128. Si algo no es entero
+//! exacto se recurre a la evaluación normal en coma flotante.
+//! En modo `modulo n` todas las expresiones siguen este camino y + - * / ^ se reducen mod n.
+
+use super::{combinatorics, eval::Rpn, Calculator};
+use crate::math_ext::{
+    egcd_i128, factor_u128, inv_mod_u128, is_prime_u128, jacobi_u128, mul_mod_u128, next_prime_u128,
+    nth_prime, pow_mod_u128, prev_prime_u128, prime_pi, sqrt_mod_prime,
+};
+use num_bigint::BigInt;
+use num_complex::Complex64;
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+/// Funciones de argumentos y resultado enteros.
+pub const INT_FUNCS: &[&str] = &[
+    "isprime", "nextprime", "prevprime", "primepi", "nthprime", "totient", "sigma", "moebius", "isperfect",
+    "powmod", "invmod", "jacobi", "legendre", "sqrtmod",
+];
+
+/// Mayor n admitido por `primepi` (y mayor primo de `nthprime`): cálculo interactivo.
+pub const PRIMEPI_LIMIT: i128 = 1_000_000_000_000;
+
+/// 2^127: los enteros exactos se pasan a las funciones como i128.
+const I128_BOUND: f64 = 1.701_411_834_604_692_3e38;
+
+/// Tamaño máximo (en bits, unas 19700 cifras) de un intermedio exacto; por encima se pasa
+/// a coma flotante.
+pub(crate) const MAX_EXACT_BITS: u64 = 1 << 16;
+
+/// Entero a partir de un valor f64 que lo es exactamente (|v| < 2^127). Por encima de 2^53
+/// f64 solo representa algunos enteros, pero el que representa se toma tal cual (1e18).
+pub(crate) fn exact_int(v: Complex64, func: &str) -> Result<i128, String> {
+    if v.im != 0.0 || v.re.fract() != 0.0 || v.re.abs() >= I128_BOUND {
+        return Err(format!("{} requiere enteros exactos (|n| < 2^127)", func));
+    }
+    Ok(v.re as i128)
+}
+
+/// Intermedio exacto acotado en tamaño (`None` si hay que pasar a coma flotante).
+fn bounded(v: BigInt) -> Option<BigInt> {
+    (v.bits() <= MAX_EXACT_BITS).then_some(v)
+}
+
+fn positive(n: i128, func: &str) -> Result<u128, String> {
+    if n < 1 {
+        return Err(format!("{} requiere n >= 1", func));
+    }
+    Ok(n as u128)
+}
+
+fn modulus(m: i128, func: &str) -> Result<u128, String> {
+    if m < 1 {
+        return Err(format!("{} requiere un módulo >= 1", func));
+    }
+    Ok(m as u128)
+}
+
+fn odd_prime(p: i128, func: &str) -> Result<u128, String> {
+    if p < 3 || !is_prime_u128(p as u128) {
+        return Err(format!("{} requiere p primo impar", func));
+    }
+    Ok(p as u128)
+}
+
+/// a⁻¹ mod m, con error si mcd(a, m) ≠ 1.
+fn inverse(a: i128, m: i128) -> Result<u128, String> {
+    inv_mod_u128(floor_mod(a, m) as u128, m as u128).ok_or_else(|| format!("{} no es invertible módulo {}", a, m))
+}
+
+/// a^e mod m; un exponente negativo usa el inverso de a.
+fn pow_mod(a: i128, e: i128, m: i128) -> Result<i128, String> {
+    let base = if e < 0 { inverse(a, m)? } else { floor_mod(a, m) as u128 };
+    Ok(pow_mod_u128(base, e.unsigned_abs(), m as u128) as i128)
+}
+
+/// `a op b` en modo `modulo m` (op es + - * / o potencia; el exponente no se reduce).
+fn mod_op(op: &str, a: i128, b: i128, m: i128) -> Result<i128, String> {
+    let n = m as u128;
+    let (ra, rb) = (floor_mod(a, m) as u128, floor_mod(b, m) as u128);
+    let v = match op {
+        "+" => (ra + rb) % n,
+        "-" => (ra + (n - rb)) % n,
+        "*" => mul_mod_u128(ra, rb, n),
+        "/" => mul_mod_u128(ra, inverse(b, m)?, n),
+        _ => return pow_mod(a, b, m),
+    };
+    Ok(v as i128)
+}
+
+/// Teorema chino del resto: x ≡ rᵢ (mod mᵢ). Admite módulos no coprimos si el sistema
+/// es compatible. Devuelve (x, M) con 0 <= x < M = mcm(mᵢ).
+pub fn crt(residues: &[i128], moduli: &[i128]) -> Result<(i128, i128), String> {
+    if residues.len() != moduli.len() || residues.is_empty() {
+        return Err("crt requiere dos listas no vacías de la misma longitud".to_string());
+    }
+    let overflow = || "crt: el módulo combinado es demasiado grande".to_string();
+    let (mut x, mut m) = (0i128, 1i128);
+    for (&r, &mi) in residues.iter().zip(moduli) {
+        modulus(mi, "crt")?;
+        let r = floor_mod(r, mi);
+        // x + m·k ≡ r (mod mi)  ⇔  (m/g)·k ≡ (r − x)/g (mod mi/g)
+        let (g, _, _) = egcd_i128(m, mi);
+        let diff = r - floor_mod(x, mi);
+        if diff % g != 0 {
+            return Err(format!("crt: el sistema no tiene solución (x ≡ {} mod {})", r, mi));
+        }
+        let step = mi / g;
+        let k = if step == 1 {
+            0
+        } else {
+            let inv = inverse(m / g, step)?;
+            mul_mod_u128(floor_mod(diff / g, step) as u128, inv, step as u128) as i128
+        };
+        let lcm = m.checked_mul(step).ok_or_else(overflow)?;
+        x = m.checked_mul(k).and_then(|mk| mk.checked_add(x)).ok_or_else(overflow)?;
+        m = lcm;
+        x = floor_mod(x, m);
+    }
+    Ok((x, m))
+}
+
+/// Factorización de |n| (n ≠ 0).
+pub fn factorize(n: i128) -> Vec<(u128, u32)> {
+    factor_u128(n.unsigned_abs())
+}
+
+/// Divisores positivos de n ≥ 1, en orden creciente.
+pub fn divisors(n: u128) -> Vec<u128> {
+    let mut divs = vec![1u128];
+    for (p, e) in factor_u128(n) {
+        let len = divs.len();
+        let mut pk = 1;
+        for _ in 0..e {
+            pk *= p;
+            for i in 0..len {
+                divs.push(divs[i] * pk);
+            }
+        }
+    }
+    divs.sort_unstable();
+    divs
+}
+
+/// σ_k(n) = Σ d^k sobre los divisores de n (producto sobre la factorización).
+fn sigma(n: u128, k: u32) -> Option<u128> {
+    let mut acc: u128 = 1;
+    for (p, e) in factor_u128(n) {
+        if k == 0 {
+            acc = acc.checked_mul(e as u128 + 1)?;
+            continue;
+        }
+        let pk = p.checked_pow(k)?;
+        let (mut term, mut sum) = (1u128, 1u128);
+        for _ in 0..e {
+            term = term.checked_mul(pk)?;
+            sum = sum.checked_add(term)?;
+        }
+        acc = acc.checked_mul(sum)?;
+    }
+    Some(acc)
+}
+
+/// Evalúa una función de `INT_FUNCS` sobre enteros exactos.
+pub(crate) fn int_call(func: &str, args: &[i128]) -> Result<i128, String> {
+    let arity = |n: usize| {
+        if args.len() == n { Ok(()) } else { Err(format!("{} requiere {} argumento(s)", func, n)) }
+    };
+    let overflow = || format!("{}: resultado demasiado grande", func);
+    match func {
+        "isprime" => {
+            arity(1)?;
+            Ok((args[0] > 0 && is_prime_u128(args[0] as u128)) as i128)
+        }
+        "nextprime" => {
+            arity(1)?;
+            let p = next_prime_u128(args[0].max(0) as u128);
+            i128::try_from(p).map_err(|_| overflow())
+        }
+        "prevprime" => {
+            arity(1)?;
+            let n = u128::try_from(args[0]).unwrap_or(0);
+            let p = prev_prime_u128(n).ok_or_else(|| format!("prevprime: no hay primos menores que {}", args[0]))?;
+            Ok(p as i128)
+        }
+        "primepi" => {
+            arity(1)?;
+            if args[0] > PRIMEPI_LIMIT {
+                return Err(format!("primepi admite n <= {}", PRIMEPI_LIMIT));
+            }
+            Ok(prime_pi(args[0].max(0) as u64) as i128)
+        }
+        "nthprime" => {
+            arity(1)?;
+            let k = positive(args[0], func)?;
+            // π(10^12) = 37607912018
+            if k > 37_607_912_018 {
+                return Err(format!("nthprime admite k <= π({}) = 37607912018", PRIMEPI_LIMIT));
+            }
+            Ok(nth_prime(k as u64) as i128)
+        }
+        "totient" => {
+            arity(1)?;
+            let n = positive(args[0], func)?;
+            let phi = factor_u128(n).iter().fold(n, |acc, (p, _)| acc / p * (p - 1));
+            Ok(phi as i128)
+        }
+        "sigma" => {
+            if args.is_empty() || args.len() > 2 {
+                return Err("Uso: sigma(n) o sigma(n, k)".to_string());
+            }
+            let n = positive(args[0], func)?;
+            let k = args.get(1).copied().unwrap_or(1);
+            let k = u32::try_from(k).map_err(|_| "sigma(n, k) requiere k >= 0".to_string())?;
+            sigma(n, k).and_then(|s| i128::try_from(s).ok()).ok_or_else(overflow)
+        }
+        "moebius" => {
+            arity(1)?;
+            let f = factor_u128(positive(args[0], func)?);
+            Ok(if f.iter().any(|&(_, e)| e > 1) { 0 } else if f.len().is_multiple_of(2) { 1 } else { -1 })
+        }
+        "isperfect" => {
+            arity(1)?;
+            Ok((args[0] > 1 && sigma(args[0] as u128, 1) == Some(2 * args[0] as u128)) as i128)
+        }
+        "powmod" => {
+            arity(3)?;
+            modulus(args[2], func)?;
+            pow_mod(args[0], args[1], args[2])
+        }
+        "invmod" => {
+            arity(2)?;
+            modulus(args[1], func)?;
+            inverse(args[0], args[1]).map(|v| v as i128)
+        }
+        "jacobi" => {
+            arity(2)?;
+            if args[1] < 1 || args[1] % 2 == 0 {
+                return Err("jacobi(a, n) requiere n impar positivo".to_string());
+            }
+            Ok(jacobi_u128(floor_mod(args[0], args[1]) as u128, args[1] as u128) as i128)
+        }
+        "legendre" => {
+            arity(2)?;
+            let p = odd_prime(args[1], func)?;
+            Ok(jacobi_u128(floor_mod(args[0], args[1]) as u128, p) as i128)
+        }
+        "sqrtmod" => {
+            arity(2)?;
+            if args[1] < 2 || !is_prime_u128(args[1] as u128) {
+                return Err("sqrtmod(a, p) requiere p primo".to_string());
+            }
+            sqrt_mod_prime(floor_mod(args[0], args[1]) as u128, args[1] as u128)
+                .map(|r| r as i128)
+                .ok_or_else(|| format!("{} no es residuo cuadrático módulo {}", args[0], args[1]))
+        }
+        _ => Err(format!("Función '{}' no implementada", func)),
+    }
+}
+
+/// Resto con el signo del divisor, como `%` en coma flotante.
+fn floor_mod(a: i128, b: i128) -> i128 {
+    let r = a % b;
+    if r != 0 && (r < 0) != (b < 0) { r + b } else { r }
+}
+
+impl Calculator {
+    /// ¿La expresión compilada llama a alguna función entera (o combinatoria)?
+    pub(crate) fn uses_int_funcs(rpn: &[Rpn]) -> bool {
+        rpn.iter().any(|r| matches!(r, Rpn::Call(f, _) if INT_FUNCS.contains(&f.as_str()) || combinatorics::is_exact(f)))
+    }
+
+    /// Evalúa la RPN en enteros exactos. `None` si contiene algo que no es entero
+    /// (decimales, otras funciones, divisiones no exactas) o pasa de `MAX_EXACT_BITS` bits.
+    pub(crate) fn run_exact(&self, rpn: &[Rpn]) -> Option<Result<BigInt, String>> {
+        let mut stack: Vec<BigInt> = Vec::new();
+        // Argumentos de las funciones enteras: i128, o error si no caben
+        let small = |f: &str, args: &[BigInt]| {
+            args.iter().map(|a| a.to_i128().ok_or_else(|| format!("{} admite enteros con |n| < 2^127", f))).collect::<Result<Vec<_>, _>>()
+        };
+        for item in rpn {
+            let v = match item {
+                Rpn::Num(n) => BigInt::from(exact_int(*n, "").ok()?),
+                Rpn::Int(n) => BigInt::from(*n),
+                // `ans` conserva todas sus cifras si el último resultado fue un entero exacto
+                Rpn::Var(name) if name == "last" || name == "ans" => match &self.exact_result {
+                    Some(e) if self.last_result.im == 0.0 && e.to_f64() == Some(self.last_result.re) => e.clone(),
+                    _ => BigInt::from(exact_int(self.last_result, "").ok()?),
+                },
+                Rpn::Var(name) => BigInt::from(exact_int(*self.vars.get(name)?, "").ok()?),
+                Rpn::Call(f, argc) => {
+                    if stack.len() < *argc || self.funcs.contains_key(f) {
+                        return None;
+                    }
+                    let args = stack.split_off(stack.len() - argc);
+                    match (f.as_str(), args.as_slice()) {
+                        (op @ ("+" | "-" | "*" | "/" | "^" | "pow"), [a, b]) if self.modulus.is_some() => {
+                            match mod_op(op, a.to_i128()?, b.to_i128()?, self.modulus?) {
+                                Ok(v) => BigInt::from(v),
+                                Err(e) => return Some(Err(e)),
+                            }
+                        }
+                        ("+", [a, b]) => bounded(a + b)?,
+                        ("-", [a, b]) => bounded(a - b)?,
+                        ("*", [a, b]) => bounded(a * b)?,
+                        ("/", [a, b]) if !b.is_zero() && (a % b).is_zero() => a / b,
+                        ("%" | "mod", [a, b]) if !b.is_zero() => a.mod_floor(b),
+                        ("^" | "pow", [a, b]) => {
+                            let e = b.to_u32()?;
+                            if a.bits().saturating_mul(e as u64) > MAX_EXACT_BITS {
+                                return None;
+                            }
+                            a.pow(e)
+                        }
+                        ("bin" | "oct" | "hex", [a]) => a.clone(),
+                        ("mcd", [a, b]) => a.gcd(b),
+                        ("mcm", [a, b]) => a.lcm(b).abs(),
+                        (f, args) if INT_FUNCS.contains(&f) => match small(f, args).and_then(|args| int_call(f, &args)) {
+                            Ok(v) => BigInt::from(v),
+                            Err(e) => return Some(Err(e)),
+                        },
+                        (f, args) if combinatorics::is_exact(f) => match small(f, args).and_then(|args| combinatorics::exact(f, &args)) {
+                            Ok(v) => v?,
+                            Err(e) => return Some(Err(e)),
+                        },
+                        _ => return None,
+                    }
+                }
+                Rpn::Reduce(_) => return None,
+            };
+            stack.push(v);
+        }
+        match (stack.as_slice(), self.modulus) {
+            ([v], Some(m)) => Some(Ok(v.mod_floor(&BigInt::from(m)))),
+            ([v], None) => Some(Ok(v.clone())),
+            _ => None,
+        }
+    }
+
+    /// Activa (`Some(n)`, n >= 2) o desactiva (`None`) el modo `modulo n`.
+    pub fn set_modulus(&mut self, m: Option<i128>) -> Result<(), String> {
+        if m.is_some_and(|m| m < 2) {
+            return Err("El módulo debe ser un entero >= 2".to_string());
+        }
+        if m.is_some() {
+            self.word = None;
+        }
+        self.modulus = m;
+        Ok(())
+    }
+
+    /// Evalúa una expresión que debe dar un entero exacto (argumento de `factor`, `divisors`).
+    pub fn eval_integer(&mut self, expr: &str) -> Result<i128, String> {
+        let rpn = self.compile(expr)?;
+        let err = || "Se requiere un entero exacto (|n| < 2^127)".to_string();
+        if let Some(v) = self.run_exact(&rpn) {
+            return v?.to_i128().ok_or_else(err);
+        }
+        let v = self.run_rpn(&rpn, &[])?.val;
+        exact_int(v, "").map_err(|_| err())
+    }
+}
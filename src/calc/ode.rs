@@ -0,0 +1,228 @@
+use super::lexer::{is_identifier, RESERVED_NAMES};
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Problema de valor inicial `y' = f(t, y)` (una o varias ecuaciones de primer orden).
+#[derive(Debug, Clone)]
+pub struct OdeSpec {
+    pub indep: String,
+    pub t0: f64,
+    pub t1: f64,
+    pub names: Vec<String>,
+    pub rhs: Vec<String>,
+    pub y0: Vec<f64>,
+    pub rtol: f64,
+    pub atol: f64,
+    /// Filas de la tabla de salida (incluye los dos extremos).
+    pub rows: usize,
+}
+
+/// Solución: la tabla en puntos equiespaciados y la trayectoria completa (pasos aceptados).
+#[derive(Debug, Clone)]
+pub struct OdeSolution {
+    pub table: Vec<(f64, Vec<f64>)>,
+    pub trajectory: Vec<(f64, Vec<f64>)>,
+    pub accepted: usize,
+    pub rejected: usize,
+}
+
+const ODE_MAX_STEPS: usize = 100_000;
+
+// Tablero de Butcher de Dormand–Prince 5(4)
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 6]; 7] = [
+    [0.0; 6],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+// Diferencia entre los pesos de orden 5 y de orden 4 (estimación del error local)
+const DP_E: [f64; 7] = [
+    71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0,
+];
+
+/// Divide por comas que no estén dentro de paréntesis.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0usize);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+impl Calculator {
+    /// Interpreta `y' = f(t,y), ..., y(t0)=v, ..., t=t0..t1 [, rtol=.., atol=.., n=..]`.
+    pub fn parse_ode(&mut self, input: &str) -> Result<OdeSpec, String> {
+        let cleaned = input.replace(' ', "");
+        let mut names = Vec::new();
+        let mut rhs = Vec::new();
+        let mut initial: Vec<(String, String, String)> = Vec::new();
+        let mut range: Option<(String, String, String)> = None;
+        let (mut rtol, mut atol, mut rows) = (1e-6, 1e-9, 11usize);
+
+        for part in split_top_level(&cleaned).into_iter().filter(|p| !p.is_empty()) {
+            let (lhs, value) = part.split_once('=').ok_or(format!("Parte no reconocida: '{}'", part))?;
+            if let Some(name) = lhs.strip_suffix('\'') {
+                if !is_identifier(name) {
+                    return Err(format!("Nombre de incógnita inválido: '{}'", name));
+                }
+                // El estado final se guarda en las variables: no puede pisar una constante
+                if RESERVED_NAMES.contains(&name) {
+                    return Err(format!("'{}' es una constante reservada", name));
+                }
+                names.push(name.to_string());
+                rhs.push(value.to_string());
+            } else if let Some((name, t)) = lhs.strip_suffix(')').and_then(|l| l.split_once('(')) {
+                initial.push((name.to_string(), t.to_string(), value.to_string()));
+            } else if let Some((a, b)) = value.split_once("..") {
+                if !is_identifier(lhs) {
+                    return Err(format!("Variable independiente inválida: '{}'", lhs));
+                }
+                range = Some((lhs.to_string(), a.to_string(), b.to_string()));
+            } else {
+                let v = self.eval_with(value, &[])?.re;
+                match lhs {
+                    "rtol" if v > 0.0 => rtol = v,
+                    "atol" if v > 0.0 => atol = v,
+                    "n" if v >= 2.0 => rows = v as usize,
+                    _ => return Err(format!("Opción no reconocida: '{}'", part)),
+                }
+            }
+        }
+
+        if names.is_empty() {
+            return Err("Falta al menos una ecuación del tipo y' = ...".to_string());
+        }
+        let (indep, a, b) = range.ok_or("Falta el intervalo, p. ej. t=0..10")?;
+        if names.contains(&indep) {
+            return Err(format!("'{}' no puede ser a la vez incógnita y variable independiente", indep));
+        }
+        let t0 = self.eval_with(&a, &[])?.re;
+        let t1 = self.eval_with(&b, &[])?.re;
+        if !t0.is_finite() || !t1.is_finite() || t0 == t1 {
+            return Err("El intervalo de integración no es válido".to_string());
+        }
+
+        let mut y0 = Vec::with_capacity(names.len());
+        for name in &names {
+            let (_, t, v) = initial
+                .iter()
+                .find(|(n, _, _)| n == name)
+                .ok_or(format!("Falta la condición inicial {}({})=...", name, t0))?;
+            if (self.eval_with(t, &[])?.re - t0).abs() > 1e-12 * t0.abs().max(1.0) {
+                return Err(format!("La condición inicial de '{}' debe darse en {}={}", name, indep, t0));
+            }
+            y0.push(self.eval_with(v, &[])?.re);
+        }
+        if let Some((n, _, _)) = initial.iter().find(|(n, _, _)| !names.contains(n)) {
+            return Err(format!("Condición inicial para una incógnita desconocida: '{}'", n));
+        }
+
+        Ok(OdeSpec { indep, t0, t1, names, rhs, y0, rtol, atol, rows })
+    }
+
+    /// Evalúa el lado derecho f(t, y) de todas las ecuaciones.
+    fn ode_rhs(&mut self, spec: &OdeSpec, t: f64, y: &[f64]) -> Result<Vec<f64>, String> {
+        let mut bindings: Vec<(&str, Complex64)> = vec![(spec.indep.as_str(), Complex64::new(t, 0.0))];
+        bindings.extend(spec.names.iter().zip(y).map(|(n, v)| (n.as_str(), Complex64::new(*v, 0.0))));
+        spec.rhs
+            .iter()
+            .map(|expr| {
+                let v = self.eval_with(expr, &bindings)?.re;
+                if v.is_finite() { Ok(v) } else { Err(format!("f({}={}) no es finita", spec.indep, t)) }
+            })
+            .collect()
+    }
+
+    /// Integra con Runge–Kutta adaptativo de Dormand–Prince 5(4) y control de error
+    /// `atol + rtol·|y|` en norma RMS.
+    pub fn solve_ode(&mut self, spec: &OdeSpec) -> Result<OdeSolution, String> {
+        let n = spec.names.len();
+        let dir = (spec.t1 - spec.t0).signum();
+        let span = (spec.t1 - spec.t0).abs();
+        let mut t = spec.t0;
+        let mut y = spec.y0.clone();
+        let mut k1 = self.ode_rhs(spec, t, &y)?;
+        let mut h = span / 100.0;
+        let (mut accepted, mut rejected) = (0usize, 0usize);
+
+        let mut table = vec![(t, y.clone())];
+        let mut trajectory = vec![(t, y.clone())];
+
+        for row in 1..spec.rows {
+            let t_out = spec.t0 + dir * span * row as f64 / (spec.rows - 1) as f64;
+            while dir * (t_out - t) > 1e-14 * span {
+                if accepted + rejected >= ODE_MAX_STEPS {
+                    return Err(format!("Demasiados pasos en {}={} (¿ecuación rígida?)", spec.indep, t));
+                }
+                let step = h.min((t_out - t).abs());
+                let mut k = vec![k1.clone()];
+                for s in 1..7 {
+                    let ys: Vec<f64> = (0..n)
+                        .map(|i| y[i] + dir * step * (0..s).map(|j| DP_A[s][j] * k[j][i]).sum::<f64>())
+                        .collect();
+                    k.push(self.ode_rhs(spec, t + dir * step * DP_C[s], &ys)?);
+                }
+                // La etapa 6 se evalúa en y_new (propiedad FSAL): k[6] = f(t+h, y_new)
+                let y_new: Vec<f64> = (0..n)
+                    .map(|i| y[i] + dir * step * (0..6).map(|j| DP_A[6][j] * k[j][i]).sum::<f64>())
+                    .collect();
+                let err = ((0..n)
+                    .map(|i| {
+                        let e = step * (0..7).map(|j| DP_E[j] * k[j][i]).sum::<f64>();
+                        let sc = spec.atol + spec.rtol * y[i].abs().max(y_new[i].abs());
+                        (e / sc).powi(2)
+                    })
+                    .sum::<f64>()
+                    / n as f64)
+                    .sqrt();
+
+                let factor = if err == 0.0 { 5.0 } else { (0.9 * err.powf(-0.2)).clamp(0.2, 5.0) };
+                if err <= 1.0 {
+                    t += dir * step;
+                    y = y_new;
+                    k1 = k.swap_remove(6);
+                    accepted += 1;
+                    trajectory.push((t, y.clone()));
+                    // Si el paso se recortó para llegar a t_out, se conserva el h previsto
+                    h = if step < h { h.max(step * factor) } else { step * factor }.min(span);
+                } else {
+                    rejected += 1;
+                    h = step * factor;
+                }
+                if h < 1e-14 * span.max(t.abs()) {
+                    return Err(format!("Paso demasiado pequeño en {}={} (¿singularidad?)", spec.indep, t));
+                }
+            }
+            t = t_out;
+            table.push((t, y.clone()));
+        }
+
+        Ok(OdeSolution { table, trajectory, accepted, rejected })
+    }
+
+    /// Resuelve el problema y guarda el estado final en las variables de las incógnitas
+    /// (y la primera en `ans`).
+    pub fn ode(&mut self, spec: &OdeSpec) -> Result<OdeSolution, String> {
+        let sol = self.solve_ode(spec)?;
+        let (_, y_end) = sol.table.last().unwrap();
+        for (name, v) in spec.names.iter().zip(y_end) {
+            self.vars.insert(name.clone(), Complex64::new(*v, 0.0));
+        }
+        self.last_result = Complex64::new(y_end[0], 0.0);
+        Ok(sol)
+    }
+}
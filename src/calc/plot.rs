@@ -23,14 +23,13 @@ impl Calculator {
         let mut width = 80usize;
         let mut height = 24usize;
 
-        if parts.len() >= 2 {
-            if let (Ok(w), Ok(h)) = (parts[parts.len()-2].parse::<usize>(), parts[parts.len()-1].parse::<usize>()) {
-                if w > 10 && h > 5 { 
-                    width = w;
-                    height = h;
-                    parts.truncate(parts.len() - 2);
-                }
-            }
+        if parts.len() >= 2
+            && let (Ok(w), Ok(h)) = (parts[parts.len()-2].parse::<usize>(), parts[parts.len()-1].parse::<usize>())
+            && w > 10 && h > 5
+        {
+            width = w;
+            height = h;
+            parts.truncate(parts.len() - 2);
         }
 
         if parts.len() >= 2 {
@@ -67,7 +66,6 @@ impl Calculator {
 
         // --- 2. Configuración de Resolución Braille ---
         let pixel_width = width * 2;
-        let pixel_height = height * 4;
 
         // --- 3. Auto-escala Y ---
         let (y_min, y_max) = if let (Some(y1), Some(y2)) = (y_min_opt, y_max_opt) {
@@ -80,8 +78,10 @@ impl Calculator {
                     let x = x_min + t * (x_max - x_min);
                     self.vars.insert("x".to_string(), Complex64::new(x, 0.0));
                     
-                    if let Ok(res) = self.evaluate(expr) {
-                        if res.re.is_finite() { all_y.push(res.re); }
+                    if let Ok(res) = self.evaluate(expr)
+                        && res.re.is_finite()
+                    {
+                        all_y.push(res.re);
                     }
                 }
             }
@@ -95,43 +95,8 @@ impl Calculator {
             if (max - min).abs() < 1e-9 { (min - 1.0, max + 1.0) } else { (min, max) }
         };
 
-        let y_range = y_max - y_min;
-
         // --- 4. Renderizado a Grid ---
-        let mut grid = vec![vec![0u8; width]; height];
-
-        let set_pixel = |px: usize, py: usize, grid: &mut Vec<Vec<u8>>| {
-            if px >= pixel_width || py >= pixel_height { return; }
-            let col_char = px / 2;
-            let row_char = (pixel_height - 1 - py) / 4; 
-
-            if row_char >= height || col_char >= width { return; }
-
-            let dx = px % 2;
-            let dy = (pixel_height - 1 - py) % 4;
-
-            let mask = match (dx, dy) {
-                (0, 0) => 0x01, (1, 0) => 0x08,
-                (0, 1) => 0x02, (1, 1) => 0x10,
-                (0, 2) => 0x04, (1, 2) => 0x20,
-                (0, 3) => 0x40, (1, 3) => 0x80,
-                _ => 0,
-            };
-            grid[row_char][col_char] |= mask;
-        };
-
-        // Ejes
-        let zero_y_ratio = (0.0 - y_min) / y_range;
-        if zero_y_ratio >= 0.0 && zero_y_ratio <= 1.0 {
-            let py_zero = (zero_y_ratio * (pixel_height - 1) as f64).round() as usize;
-            for px in 0..pixel_width { set_pixel(px, py_zero, &mut grid); }
-        }
-
-        let zero_x_ratio = (0.0 - x_min) / (x_max - x_min);
-        if zero_x_ratio >= 0.0 && zero_x_ratio <= 1.0 {
-            let px_zero = (zero_x_ratio * (pixel_width - 1) as f64).round() as usize;
-            for py in 0..pixel_height { set_pixel(px_zero, py, &mut grid); }
-        }
+        let mut canvas = BrailleCanvas::new(width, height, (x_min, x_max), (y_min, y_max));
 
         // Funciones
         for &expr in &exprs {
@@ -141,53 +106,152 @@ impl Calculator {
                 self.vars.insert("x".to_string(), Complex64::new(x, 0.0));
 
                 if let Ok(res) = self.evaluate(expr) {
-                    let y = res.re;
-                    if y >= y_min && y <= y_max {
-                        let y_ratio = (y - y_min) / y_range;
-                        let py = (y_ratio * (pixel_height - 1) as f64).round() as usize;
-                        set_pixel(px, py, &mut grid);
-                    }
+                    canvas.plot_point(x, res.re);
                 }
             }
         }
 
         // --- 5. Imprimir Resultado ---
-        println!("\nPlot (Braille): {:?} [X: {:.2} a {:.2}, Y: {:.2} a {:.2}]", exprs, x_min, x_max, y_min, y_max);
-        
+        canvas.print(&format!("Plot (Braille): {:?}", exprs));
+
+        self.last_result = saved_last;
+        self.restore_x(saved_x);
+    }
+
+    fn restore_x(&mut self, saved: Option<Complex64>) {
+        if let Some(old) = saved {
+            self.vars.insert("x".to_string(), old);
+        } else {
+            self.vars.remove("x");
+        }
+    }
+}
+
+/// Lienzo de texto Braille (2x4 puntos por carácter) con ejes y etiquetas.
+pub(crate) struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    grid: Vec<Vec<u8>>,
+}
+
+impl BrailleCanvas {
+    /// Crea el lienzo y dibuja los ejes x=0 / y=0 si caen dentro del rango.
+    pub(crate) fn new(width: usize, height: usize, x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        let mut canvas = BrailleCanvas { width, height, x_range, y_range, grid: vec![vec![0u8; width]; height] };
+        let (pixel_width, pixel_height) = (width * 2, height * 4);
+
+        // Ejes
+        let zero_y_ratio = (0.0 - y_range.0) / (y_range.1 - y_range.0);
+        if (0.0..=1.0).contains(&zero_y_ratio) {
+            let py_zero = (zero_y_ratio * (pixel_height - 1) as f64).round() as usize;
+            for px in 0..pixel_width { canvas.set_pixel(px, py_zero); }
+        }
+
+        let zero_x_ratio = (0.0 - x_range.0) / (x_range.1 - x_range.0);
+        if (0.0..=1.0).contains(&zero_x_ratio) {
+            let px_zero = (zero_x_ratio * (pixel_width - 1) as f64).round() as usize;
+            for py in 0..pixel_height { canvas.set_pixel(px_zero, py); }
+        }
+        canvas
+    }
+
+    fn set_pixel(&mut self, px: usize, py: usize) {
+        let (pixel_width, pixel_height) = (self.width * 2, self.height * 4);
+        if px >= pixel_width || py >= pixel_height { return; }
+        let col_char = px / 2;
+        let row_char = (pixel_height - 1 - py) / 4;
+
+        if row_char >= self.height || col_char >= self.width { return; }
+
+        let dx = px % 2;
+        let dy = (pixel_height - 1 - py) % 4;
+
+        let mask = match (dx, dy) {
+            (0, 0) => 0x01, (1, 0) => 0x08,
+            (0, 1) => 0x02, (1, 1) => 0x10,
+            (0, 2) => 0x04, (1, 2) => 0x20,
+            (0, 3) => 0x40, (1, 3) => 0x80,
+            _ => 0,
+        };
+        self.grid[row_char][col_char] |= mask;
+    }
+
+    /// Coordenadas de píxel (sin redondear) de un punto en unidades del gráfico.
+    fn to_pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        let px = (x - x_min) / (x_max - x_min) * (self.width * 2 - 1) as f64;
+        let py = (y - y_min) / (y_max - y_min) * (self.height * 4 - 1) as f64;
+        (px, py)
+    }
+
+    /// Marca un punto si cae dentro del rango visible.
+    pub(crate) fn plot_point(&mut self, x: f64, y: f64) {
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        if x.is_finite() && y.is_finite() && x >= x_min && x <= x_max && y >= y_min && y <= y_max {
+            let (px, py) = self.to_pixel(x, y);
+            self.set_pixel(px.round() as usize, py.round() as usize);
+        }
+    }
+
+    /// Marca un punto de datos como una pequeña cruz (más visible que un solo píxel).
+    pub(crate) fn plot_marker(&mut self, x: f64, y: f64) {
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        if !(x.is_finite() && y.is_finite() && x >= x_min && x <= x_max && y >= y_min && y <= y_max) {
+            return;
+        }
+        let (px, py) = self.to_pixel(x, y);
+        let (px, py) = (px.round() as usize, py.round() as usize);
+        self.set_pixel(px, py);
+        self.set_pixel(px + 1, py);
+        self.set_pixel(px, py + 1);
+        self.set_pixel(px.saturating_sub(1), py);
+        self.set_pixel(px, py.saturating_sub(1));
+    }
+
+    /// Dibuja un segmento entre dos puntos (recortando lo que queda fuera del rango).
+    pub(crate) fn draw_line(&mut self, a: (f64, f64), b: (f64, f64)) {
+        let (pa, pb) = (self.to_pixel(a.0, a.1), self.to_pixel(b.0, b.1));
+        let steps = (pb.0 - pa.0).abs().max((pb.1 - pa.1).abs()).ceil().clamp(1.0, 4096.0) as usize;
+        for k in 0..=steps {
+            let t = k as f64 / steps as f64;
+            self.plot_point(a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+        }
+    }
+
+    pub(crate) fn print(&self, title: &str) {
+        let (width, height) = (self.width, self.height);
+        let (x_min, x_max) = self.x_range;
+        let (y_min, y_max) = self.y_range;
+        println!("\n{} [X: {:.2} a {:.2}, Y: {:.2} a {:.2}]", title, x_min, x_max, y_min, y_max);
+
         // CORRECCIÓN: Indentación de 7 espacios para alinear con la etiqueta del eje Y
         println!("       ┌{}┐", "─".repeat(width));
-        
-        for (i, row) in grid.iter().enumerate() {
+
+        for (i, row) in self.grid.iter().enumerate() {
             let mut line = String::with_capacity(width);
             for &mask in row {
                 let c = char::from_u32(0x2800 + mask as u32).unwrap_or(' ');
                 line.push(c);
             }
-            
+
             // Etiqueta eje Y a la izquierda (ancho fijo de 7 chars: 6 nums + 1 espacio)
             let label = if i == 0 { format!("{:>6.2} ", y_max) }
                        else if i == height - 1 { format!("{:>6.2} ", y_min) }
                        else if i == height / 2 { format!("{:>6.2} ", (y_max + y_min)/2.0) }
                        else { "       ".to_string() };
-            
+
             println!("{}│{}│", label, line);
         }
-        
+
         // CORRECCIÓN: Indentación de 7 espacios para el borde inferior
         println!("       └{}┘", "─".repeat(width));
-        
+
         // Etiquetas eje X (ya tenían 7 espacios, se mantienen igual)
         println!("       {:<width$}{:.2}", format!("{:.2}", x_min), x_max, width=width - format!("{:.2}", x_max).len());
-
-        self.last_result = saved_last;
-        self.restore_x(saved_x);
-    }
-
-    fn restore_x(&mut self, saved: Option<Complex64>) {
-        if let Some(old) = saved {
-            self.vars.insert("x".to_string(), old);
-        } else {
-            self.vars.remove("x");
-        }
     }
 }
@@ -0,0 +1,213 @@
+//! Modo programador (`prog`): enteros de C con tamaño de palabra fijo (8..128 bits), con o
+//! sin signo, y desbordamiento circular (wrap) o saturado. La división trunca hacia cero y
+//! `%` es el resto de C (con el signo del dividendo).
+//!
+//! Los valores se guardan como patrón de bits (`u128` enmascarado a la palabra); el signo
+//! solo interviene al operar y al mostrar.
+
+use super::eval::Rpn;
+use super::format::{group_chars, radix_digits};
+use super::numtheory::exact_int;
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Qué hacer cuando un resultado no cabe en la palabra.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    /// Módulo 2^bits, como los enteros sin signo de C (y los con signo en la práctica).
+    Wrap,
+    /// Se queda en el mínimo o el máximo representable.
+    Saturate,
+}
+
+/// Tipo entero activo: `i32`, `u8 sat`, ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WordMode {
+    pub bits: u32,
+    pub signed: bool,
+    pub overflow: Overflow,
+}
+
+impl WordMode {
+    /// Lee `i8`..`i128` / `u8`..`u128` seguido opcionalmente de `wrap` o `sat`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        const USAGE: &str = "Uso: prog <i|u><8|16|32|64|128> [wrap|sat]  (p. ej. prog u8 sat)";
+        let parts: Vec<&str> = spec.split_whitespace().collect();
+        let (ty, overflow) = match parts.as_slice() {
+            [ty] => (*ty, Overflow::Wrap),
+            [ty, "wrap"] => (*ty, Overflow::Wrap),
+            [ty, "sat"] => (*ty, Overflow::Saturate),
+            _ => return Err(USAGE.to_string()),
+        };
+        let signed = match ty.chars().next() {
+            Some('i') => true,
+            Some('u') => false,
+            _ => return Err(USAGE.to_string()),
+        };
+        match ty[1..].parse::<u32>() {
+            Ok(bits @ (8 | 16 | 32 | 64 | 128)) => Ok(WordMode { bits, signed, overflow }),
+            _ => Err(USAGE.to_string()),
+        }
+    }
+
+    /// Nombre corto para el prompt: "i32", "u8 sat".
+    pub fn label(&self) -> String {
+        let sat = if self.overflow == Overflow::Saturate { " sat" } else { "" };
+        format!("{}{}{}", if self.signed { 'i' } else { 'u' }, self.bits, sat)
+    }
+
+    fn mask(&self) -> u128 {
+        u128::MAX >> (128 - self.bits)
+    }
+
+    /// Valor con signo de un patrón (extensión de signo desde `bits`).
+    fn signed_value(&self, p: u128) -> i128 {
+        let shift = 128 - self.bits;
+        ((p << shift) as i128) >> shift
+    }
+
+    fn signed_range(&self) -> (i128, i128) {
+        let max = (self.mask() >> 1) as i128;
+        (-max - 1, max)
+    }
+
+    /// Patrón de un entero cualquiera, ajustado a la palabra según `overflow`.
+    pub fn fit(&self, v: i128) -> u128 {
+        match (self.overflow, self.signed) {
+            (Overflow::Wrap, _) => v as u128 & self.mask(),
+            (Overflow::Saturate, true) => {
+                let (lo, hi) = self.signed_range();
+                v.clamp(lo, hi) as u128 & self.mask()
+            }
+            (Overflow::Saturate, false) => (v.max(0) as u128).min(self.mask()),
+        }
+    }
+
+    /// Valor del patrón como número (con o sin signo).
+    pub fn value(&self, p: u128) -> f64 {
+        if self.signed { self.signed_value(p) as f64 } else { p as f64 }
+    }
+
+    /// Texto decimal del patrón.
+    pub fn decimal(&self, p: u128) -> String {
+        if self.signed { self.signed_value(p).to_string() } else { p.to_string() }
+    }
+
+    /// Operador binario de C sobre dos patrones.
+    pub fn apply(&self, op: &str, a: u128, b: u128) -> Result<u128, String> {
+        let sat = self.overflow == Overflow::Saturate;
+        if matches!(op, "/" | "%") && b == 0 {
+            return Err("División por cero".to_string());
+        }
+        let r = if self.signed {
+            let (x, y) = (self.signed_value(a), self.signed_value(b));
+            // Saturar en i128 y después a la palabra da el mismo resultado que saturar directamente
+            let v = match op {
+                "+" => if sat { x.saturating_add(y) } else { x.wrapping_add(y) },
+                "-" => if sat { x.saturating_sub(y) } else { x.wrapping_sub(y) },
+                "*" => if sat { x.saturating_mul(y) } else { x.wrapping_mul(y) },
+                "/" => if sat { x.saturating_div(y) } else { x.wrapping_div(y) },
+                "%" => x.wrapping_rem(y),
+                "^" | "pow" => {
+                    let e = u32::try_from(y).map_err(|_| "En modo programador el exponente debe estar entre 0 y 2^32".to_string())?;
+                    if sat { x.saturating_pow(e) } else { x.wrapping_pow(e) }
+                }
+                _ => return Err(format!("Operador '{}' no disponible en modo programador", op)),
+            };
+            if sat {
+                let (lo, hi) = self.signed_range();
+                v.clamp(lo, hi) as u128
+            } else {
+                v as u128
+            }
+        } else {
+            let v = match op {
+                "+" => if sat { a.saturating_add(b) } else { a.wrapping_add(b) },
+                "-" => if sat { a.saturating_sub(b) } else { a.wrapping_sub(b) },
+                "*" => if sat { a.saturating_mul(b) } else { a.wrapping_mul(b) },
+                "/" => a / b,
+                "%" => a % b,
+                "^" | "pow" => {
+                    let e = u32::try_from(b).map_err(|_| "En modo programador el exponente debe estar entre 0 y 2^32".to_string())?;
+                    if sat { a.saturating_pow(e) } else { a.wrapping_pow(e) }
+                }
+                _ => return Err(format!("Operador '{}' no disponible en modo programador", op)),
+            };
+            if sat { v.min(self.mask()) } else { v }
+        };
+        Ok(r & self.mask())
+    }
+
+    /// Las cuatro vistas clásicas del patrón: HEX y BIN con el ancho de la palabra
+    /// (BIN en grupos de 4), DEC con signo si procede y OCT.
+    pub fn views(&self, p: u128) -> [(&'static str, String); 4] {
+        let hex = format!("{:0>w$}", radix_digits(p, 16, false), w = self.bits as usize / 4);
+        let bin = format!("{:0>w$}", radix_digits(p, 2, false), w = self.bits as usize);
+        [
+            ("HEX", format!("0x{}", group_chars(&hex, 4))),
+            ("DEC", self.decimal(p)),
+            ("OCT", format!("0o{}", radix_digits(p, 8, false))),
+            ("BIN", format!("0b{}", group_chars(&bin, 4))),
+        ]
+    }
+}
+
+impl Calculator {
+    /// Activa (o desactiva con `None`) el modo programador; desactiva el modo modulo.
+    /// Al cambiar de tipo, `ans` deja de ser un patrón de bits.
+    pub fn set_word(&mut self, word: Option<WordMode>) {
+        if word.is_some() {
+            self.modulus = None;
+        }
+        if self.word != word {
+            self.word_result = None;
+        }
+        self.word = word;
+    }
+
+    /// Evalúa la RPN con la aritmética de la palabra `w`.
+    pub(crate) fn run_word(&self, w: WordMode, rpn: &[Rpn]) -> Result<u128, String> {
+        let not_int = || format!("En modo programador ({}) la expresión debe ser entera", w.label());
+        let mut stack: Vec<u128> = Vec::new();
+        for item in rpn {
+            let v = match item {
+                Rpn::Num(n) => w.fit(exact_int(*n, "").map_err(|_| not_int())?),
+                Rpn::Int(n) => w.fit(*n),
+                Rpn::Var(name) if name == "last" || name == "ans" => match self.word_result {
+                    Some(p) => p,
+                    None => w.fit(exact_int(self.last_result, "").map_err(|_| not_int())?),
+                },
+                Rpn::Var(name) => {
+                    let v = *self.vars.get(name).ok_or(format!("Variable '{}' no existe", name))?;
+                    w.fit(exact_int(v, "").map_err(|_| not_int())?)
+                }
+                Rpn::Call(f, argc) => {
+                    if stack.len() < *argc {
+                        return Err("Error en expresión: faltan operandos".to_string());
+                    }
+                    let args = stack.split_off(stack.len() - argc);
+                    match (f.as_str(), args.as_slice()) {
+                        ("bin" | "oct" | "hex", [a]) => *a,
+                        (op, [a, b]) => w.apply(op, *a, *b)?,
+                        _ => return Err(format!("'{}' no está disponible en modo programador (solo + - * / % ^)", f)),
+                    }
+                }
+                Rpn::Reduce(_) => return Err("sum/prod no están disponibles en modo programador".to_string()),
+            };
+            stack.push(v);
+        }
+        match stack.as_slice() {
+            [v] => Ok(*v),
+            _ => Err("Error en expresión: sobran operandos".to_string()),
+        }
+    }
+
+    /// Valor de `evaluate` en modo programador: guarda el patrón en `word_result`.
+    pub(crate) fn evaluate_word(&mut self, w: WordMode, rpn: &[Rpn]) -> Result<Complex64, String> {
+        let p = self.run_word(w, rpn)?;
+        self.word_result = Some(p);
+        self.exact_result = None;
+        self.approx_result = false;
+        Ok(Complex64::new(w.value(p), 0.0))
+    }
+}
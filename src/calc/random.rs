@@ -0,0 +1,125 @@
+use super::{distributions, Calculator};
+use num_complex::Complex64;
+
+/// Funciones aleatorias: necesitan el generador de la calculadora, así que se evalúan
+/// fuera de `apply_func` y su derivada es siempre nula.
+pub const RANDOM_FUNCS: &[&str] = &[
+    "rand", "randint", "randn", "choice",
+    "normrnd", "trnd", "chi2rnd", "frnd", "exprnd", "unifrnd", "binomrnd", "poissrnd", "geomrnd", "hygernd",
+];
+
+/// Generador xoshiro256** sembrado con splitmix64: solo aritmética entera, así que la
+/// misma semilla da la misma secuencia en cualquier plataforma.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    seed: u64,
+    s: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut sm = seed;
+        let mut splitmix = || {
+            sm = sm.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = sm;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng { seed, s: [splitmix(), splitmix(), splitmix(), splitmix()] }
+    }
+
+    /// Semilla a partir del reloj (sesiones sin `seed` explícito).
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::new(nanos)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniforme en [0, 1) con 53 bits.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Uniforme en (0, 1) abierto: apto para invertir una cdf sin obtener ±∞.
+    fn open01(&mut self) -> f64 {
+        ((self.next_u64() >> 11) as f64 + 0.5) * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Entero uniforme en [0, n) sin sesgo (método de Lemire).
+    pub fn below(&mut self, n: u64) -> u64 {
+        let mut m = self.next_u64() as u128 * n as u128;
+        if (m as u64) < n {
+            let threshold = n.wrapping_neg() % n;
+            while (m as u64) < threshold {
+                m = self.next_u64() as u128 * n as u128;
+            }
+        }
+        (m >> 64) as u64
+    }
+}
+
+/// Entero exacto en f64 (|v| ≤ 2^53).
+fn exact_int(v: f64, func: &str) -> Result<i64, String> {
+    if v.fract() != 0.0 || v.abs() > (1u64 << 53) as f64 {
+        return Err(format!("{} requiere enteros (|n| <= 2^53)", func));
+    }
+    Ok(v as i64)
+}
+
+impl Calculator {
+    /// Reinicia el generador: la misma semilla reproduce la misma secuencia.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Evalúa una función de `RANDOM_FUNCS` con sus argumentos ya calculados.
+    pub(crate) fn random_call(&mut self, func: &str, args: &[Complex64]) -> Result<Complex64, String> {
+        if func == "choice" {
+            if args.is_empty() {
+                return Err("Uso: choice(x1, x2, ...)".to_string());
+            }
+            return Ok(args[self.rng.below(args.len() as u64) as usize]);
+        }
+        if args.iter().any(|v| v.im.abs() > 1e-10) {
+            return Err(format!("{} requiere argumentos reales", func));
+        }
+        let a: Vec<f64> = args.iter().map(|v| v.re).collect();
+        let v = match (func, a.len()) {
+            ("rand", 0) => self.rng.next_f64(),
+            ("rand", 2) => a[0] + self.rng.next_f64() * (a[1] - a[0]),
+            ("rand", _) => return Err("Uso: rand() o rand(min, max)".to_string()),
+            ("randint", 2) => {
+                let (lo, hi) = (exact_int(a[0], func)?, exact_int(a[1], func)?);
+                if lo > hi {
+                    return Err("randint: se requiere a <= b".to_string());
+                }
+                (lo + self.rng.below((hi - lo) as u64 + 1) as i64) as f64
+            }
+            ("randint", _) => return Err("Uso: randint(a, b)".to_string()),
+            ("randn", 0 | 2) => distributions::sample("normrnd", &a, self.rng.open01())?,
+            ("randn", _) => return Err("Uso: randn() o randn(mu, sigma)".to_string()),
+            _ => distributions::sample(func, &a, self.rng.open01())?,
+        };
+        Ok(Complex64::new(v, 0.0))
+    }
+}
@@ -0,0 +1,255 @@
+use super::dual::{self, Dual};
+use super::eval::Reduction;
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Máximo de términos de una suma/producto finito.
+const MAX_TERMS_FINITE: i64 = 10_000_000;
+/// Máximo de términos cuando el límite superior es `inf`.
+const MAX_TERMS_INFINITE: usize = 1_000_000;
+/// Términos consecutivos despreciables para dar una serie por convergida.
+const SMALL_RUN: usize = 5;
+/// Sumas parciales que se guardan para el épsilon de Wynn.
+const WYNN_WINDOW: usize = 40;
+/// Términos a partir de los cuales, si no tienden a 0, la serie se da por divergente.
+const DIVERGENCE_CHECK: usize = 4096;
+
+/// Máximos de |aₖ| en los bloques (n/8, n/4], (n/4, n/2] y (n/2, n] de los n primeros
+/// términos (por bloques, para no confundir oscilaciones como sin(k)/k con divergencia).
+fn tail_blocks(mags: &[f64]) -> (f64, f64, f64) {
+    let n = mags.len();
+    let max = |r: std::ops::Range<usize>| mags[r].iter().fold(0.0f64, |m, v| m.max(*v));
+    (max(n / 8..n / 4), max(n / 4..n / 2), max(n / 2..n))
+}
+
+/// Los términos tienden a 0: sus máximos por bloques decrecen y la extrapolación de
+/// Aitken de esos máximos no deja un residuo comparable al último (descarta aₖ → c ≠ 0,
+/// como (-1)^k o 1 + 1/k). Sin esto no se acepta ninguna estimación acelerada.
+fn terms_vanish(mags: &[f64]) -> bool {
+    if mags.len() < 16 {
+        return false;
+    }
+    let (a, b, c) = tail_blocks(mags);
+    if c == 0.0 {
+        return true;
+    }
+    if !(c < b && b < a) {
+        return false;
+    }
+    let den = a + c - 2.0 * b;
+    den <= 0.0 || (a * c - b * b) / den <= 0.8 * c
+}
+
+/// Límite entero de un índice; `None` si es +∞.
+fn int_bound(v: Complex64, what: &str) -> Result<Option<i64>, String> {
+    if v.im != 0.0 {
+        return Err(format!("El límite {} debe ser real", what));
+    }
+    if v.re == f64::INFINITY {
+        return Ok(None);
+    }
+    if !v.re.is_finite() || (v.re - v.re.round()).abs() > 1e-9 || v.re.abs() > 9e15 {
+        return Err(format!("El límite {} debe ser un entero", what));
+    }
+    Ok(Some(v.re.round() as i64))
+}
+
+/// Componentes (valor y derivadas) de un dual, para extrapolar cada una por separado.
+fn components(d: &Dual, n: usize) -> Vec<Complex64> {
+    std::iter::once(d.val).chain((0..n).map(|k| d.d(k))).collect()
+}
+
+fn from_components(c: Vec<Complex64>) -> Dual {
+    Dual { val: c[0], eps: c[1..].to_vec() }
+}
+
+/// Épsilon de Wynn sobre una sucesión de sumas parciales: devuelve la última estimación
+/// de una columna par (las columnas impares son auxiliares).
+fn wynn_epsilon(s: &[Complex64]) -> Complex64 {
+    let mut prev = vec![Complex64::new(0.0, 0.0); s.len() + 1];
+    let mut cur = s.to_vec();
+    let mut best = *s.last().unwrap();
+    let mut col = 0;
+    while cur.len() >= 2 {
+        let mut next = Vec::with_capacity(cur.len() - 1);
+        for j in 0..cur.len() - 1 {
+            let diff = cur[j + 1] - cur[j];
+            if diff.norm() == 0.0 {
+                return if col % 2 == 0 { cur[j + 1] } else { best };
+            }
+            next.push(prev[j + 1] + diff.inv());
+        }
+        col += 1;
+        if col % 2 == 0 {
+            best = *next.last().unwrap();
+        }
+        prev = cur;
+        cur = next;
+    }
+    best
+}
+
+/// Exponente q de la cola de una serie de términos algebraicos, S − Sₙ ≈ C·n^(−q), a partir
+/// de |aₖ| ≈ n^(−q−1) en n/4, n/2 y n (un paso de Richardson quita el sesgo O(1/n) del
+/// cociente); los valores casi enteros se redondean. `None` si la cola no decrece como potencia.
+fn tail_exponent(mags: &[f64]) -> Option<f64> {
+    let n = mags.len();
+    let (a1, a2, a3) = (mags[n / 4 - 1], mags[n / 2 - 1], mags[n - 1]);
+    if !(a1 > 0.0 && a2 > 0.0 && a3 > 0.0) {
+        return None;
+    }
+    let q = 2.0 * (a2 / a3).log2() - (a1 / a2).log2() - 1.0;
+    let q = if (q - q.round()).abs() < 1e-6 { q.round() } else { q };
+    (q.is_finite() && q > 0.05).then_some(q)
+}
+
+/// Extrapolación de Richardson sobre sumas parciales tomadas en n, 2n, 4n, ... con error
+/// C₁·n^(−q) + C₂·n^(−q−1) + ... (convergencia algebraica de Σ 1/k^p con q = p − 1, o de
+/// productos infinitos). Devuelve las tres últimas estimaciones diagonales.
+fn richardson(s: &[Complex64], q: f64) -> [Complex64; 3] {
+    let mut tab: Vec<Vec<Complex64>> = Vec::with_capacity(s.len());
+    for (i, &v) in s.iter().enumerate() {
+        let mut row = vec![v];
+        for j in 1..=i {
+            let f = 2f64.powf(q + (j - 1) as f64);
+            let t = row[j - 1] + (row[j - 1] - tab[i - 1][j - 1]) / (f - 1.0);
+            row.push(t);
+        }
+        tab.push(row);
+    }
+    let n = tab.len();
+    [tab[n - 3][n - 3], tab[n - 2][n - 2], tab[n - 1][n - 1]]
+}
+
+impl Calculator {
+    /// Evalúa `sum(k, a, b, expr)` / `prod(k, a, b, expr)`. El índice es local: se resuelve
+    /// antes que cualquier variable del usuario con el mismo nombre, sin modificarla.
+    pub(crate) fn run_reduce(&mut self, r: &Reduction, seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        let name = if r.prod { "prod" } else { "sum" };
+        let lo = int_bound(self.run_rpn(&r.lo, seeds)?.val, "inferior")?
+            .ok_or(format!("El límite inferior de {} no puede ser infinito", name))?;
+        let hi = int_bound(self.run_rpn(&r.hi, seeds)?.val, "superior")?;
+        let identity = Dual::constant(Complex64::new(if r.prod { 1.0 } else { 0.0 }, 0.0));
+
+        let Some(hi) = hi else {
+            return self.run_infinite(r, lo, identity, seeds);
+        };
+        if hi < lo {
+            return Ok(identity);
+        }
+        if hi - lo >= MAX_TERMS_FINITE {
+            return Err(format!("{}: demasiados términos (máximo {})", name, MAX_TERMS_FINITE));
+        }
+        let mut acc = identity;
+        for k in lo..=hi {
+            let t = self.reduce_term(r, k, seeds)?;
+            acc = self.combine(&acc, &t, r.prod)?;
+        }
+        Ok(acc)
+    }
+
+    /// Un término del cuerpo con el índice ligado a `k`.
+    fn reduce_term(&mut self, r: &Reduction, k: i64, seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        self.scopes.push((r.index.clone(), Dual::constant(Complex64::new(k as f64, 0.0))));
+        let res = self.run_rpn(&r.body, seeds);
+        self.scopes.pop();
+        res
+    }
+
+    fn combine(&self, acc: &Dual, t: &Dual, prod: bool) -> Result<Dual, String> {
+        let (op, val) = if prod { ("*", acc.val * t.val) } else { ("+", acc.val + t.val) };
+        if !val.re.is_finite() || !val.im.is_finite() {
+            return Err("Resultado no finito (NaN/Inf) en la serie.".to_string());
+        }
+        let eps = dual::propagate(op, &[acc.clone(), t.clone()], val, self.angle)?;
+        Ok(Dual { val, eps })
+    }
+
+    /// Serie/producto hasta `inf`: se para cuando los términos dejan de aportar. Las series
+    /// alternadas se aceleran con el épsilon de Wynn y las de convergencia lenta (sumas
+    /// monótonas y productos) con Richardson sobre el exponente estimado de la cola; se
+    /// acepta la estimación cuando las consecutivas coinciden y los términos tienden a 0.
+    /// Agotados los términos sin converger no se afirma divergencia: solo se informa.
+    fn run_infinite(&mut self, r: &Reduction, lo: i64, identity: Dual, seeds: &[(&str, Complex64)]) -> Result<Dual, String> {
+        let ncomp = seeds.len();
+        let mut acc = identity;
+        let mut small_run = 0usize;
+        let mut alt_run = 0usize;
+        let mut prev_sign = 0.0;
+        let mut window: Vec<Dual> = Vec::new();
+        let mut prev_wynn: Option<Vec<Complex64>> = None;
+        let mut checkpoints: Vec<Dual> = Vec::new();
+        let mut next_checkpoint = 16usize;
+        let mut mags: Vec<f64> = Vec::new();
+        let what = if r.prod { "El producto" } else { "La serie" };
+
+        let close = |a: &[Complex64], b: &[Complex64], tol: f64| {
+            a.iter().zip(b).all(|(x, y)| (x - y).norm() <= tol * x.norm().max(1e-300))
+        };
+
+        for n in 1..=MAX_TERMS_INFINITE {
+            let t = self.reduce_term(r, lo + n as i64 - 1, seeds)?;
+            acc = self.combine(&acc, &t, r.prod)?;
+
+            // 1. Términos despreciables frente al acumulado
+            let delta = if r.prod { (t.val - 1.0).norm() } else { t.val.norm() };
+            mags.push(delta);
+            let tiny = delta <= 1e-16 * acc.val.norm() && (0..ncomp).all(|k| {
+                let dk = if r.prod { t.d(k) * acc.val } else { t.d(k) };
+                dk.norm() <= 1e-16 * acc.d(k).norm().max(acc.val.norm())
+            });
+            small_run = if tiny { small_run + 1 } else { 0 };
+            if small_run >= SMALL_RUN {
+                return Ok(acc);
+            }
+
+            // 2. Series alternadas: épsilon de Wynn sobre las últimas sumas parciales
+            if !r.prod {
+                let sign = t.val.re.signum();
+                alt_run = if t.val.re != 0.0 && sign == -prev_sign { alt_run + 1 } else { 0 };
+                prev_sign = sign;
+                window.push(acc.clone());
+                if window.len() > WYNN_WINDOW {
+                    window.remove(0);
+                }
+                if alt_run >= 10 && window.len() >= 20 && n % 10 == 0 {
+                    let est: Vec<Complex64> = (0..=ncomp)
+                        .map(|c| wynn_epsilon(&window.iter().map(|d| components(d, ncomp)[c]).collect::<Vec<_>>()))
+                        .collect();
+                    if let Some(prev) = &prev_wynn
+                        && close(&est, prev, 1e-13)
+                        && terms_vanish(&mags)
+                    {
+                        return Ok(from_components(est));
+                    }
+                    prev_wynn = Some(est);
+                }
+            }
+
+            // 3. Convergencia algebraica: Richardson en n^(−q) con n = 16, 32, 64, ...
+            if n == next_checkpoint {
+                next_checkpoint *= 2;
+                checkpoints.push(acc.clone());
+                if n >= DIVERGENCE_CHECK && !terms_vanish(&mags) {
+                    return Err(format!("{} no converge: sus términos no tienden a {}", what, if r.prod { 1 } else { 0 }));
+                }
+                if alt_run == 0
+                    && checkpoints.len() >= 4
+                    && terms_vanish(&mags)
+                    && let Some(q) = tail_exponent(&mags)
+                {
+                    let est: Vec<[Complex64; 3]> = (0..=ncomp)
+                        .map(|c| richardson(&checkpoints.iter().map(|d| components(d, ncomp)[c]).collect::<Vec<_>>(), q))
+                        .collect();
+                    let diag = |i: usize| est.iter().map(|e| e[i]).collect::<Vec<_>>();
+                    // Tres estimaciones coincidentes: con dos, una cola con exponentes no
+                    // espaciados en enteros puede estabilizarse en un límite falso
+                    if close(&diag(2), &diag(1), 1e-12) && close(&diag(1), &diag(0), 1e-10) {
+                        return Ok(from_components(diag(2)));
+                    }
+                }
+            }
+        }
+        Err(format!("{}: no se alcanzó la convergencia tras {} términos", what, MAX_TERMS_INFINITE))
+    }
+}
@@ -0,0 +1,562 @@
+//! Funciones especiales. Las reales sirven de base a las distribuciones: log-gamma,
+//! gamma y beta incompletas regularizadas, y las densidades "saddle point" de Loader
+//! (binomial y Poisson con precisión relativa completa incluso en las colas). Las de
+//! usuario (gamma, beta, erf, zeta, digamma, Lambert W, elípticas) admiten argumento
+//! complejo donde tiene sentido.
+
+use super::distributions;
+use num_complex::Complex64;
+use std::f64::consts::{E, PI};
+
+/// ln(√(2π))
+const LN_SQRT_2PI: f64 = 0.918_938_533_204_672_8;
+const EPS: f64 = 1e-16;
+const TINY: f64 = 1e-300;
+const MAX_ITER: usize = 100_000;
+
+/// Coeficientes de Lanczos (g = 607/128, 14 términos), error relativo < 1e-15.
+const LANCZOS: [f64; 14] = [
+    57.156_235_665_862_92,
+    -59.597_960_355_475_49,
+    14.136_097_974_741_746,
+    -0.491_913_816_097_620_2,
+    3.399_464_998_481_189e-5,
+    4.652_362_892_704_858e-5,
+    -9.837_447_530_487_956e-5,
+    1.580_887_032_249_125e-4,
+    -2.102_644_417_241_048_8e-4,
+    2.174_396_181_152_126_5e-4,
+    -1.643_181_065_367_639e-4,
+    8.441_822_398_385_275e-5,
+    -2.619_083_840_158_140_8e-5,
+    3.689_918_265_953_162_5e-6,
+];
+
+/// ln Γ(x) para x > 0.
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    let mut y = x;
+    let tmp = x + 5.242_187_5;
+    let tmp = (x + 0.5) * tmp.ln() - tmp;
+    let mut ser = 0.999_999_999_999_997_1;
+    for c in LANCZOS {
+        y += 1.0;
+        ser += c / y;
+    }
+    tmp + (2.506_628_274_631_000_5 * ser / x).ln()
+}
+
+/// Error de la aproximación de Stirling: ln Γ(n+1) − [(n+½)·ln n − n + ln √(2π)].
+/// Solo importa su error absoluto (entra en un exponente).
+pub(crate) fn stirlerr(n: f64) -> f64 {
+    const S0: f64 = 1.0 / 12.0;
+    const S1: f64 = 1.0 / 360.0;
+    const S2: f64 = 1.0 / 1260.0;
+    const S3: f64 = 1.0 / 1680.0;
+    const S4: f64 = 1.0 / 1188.0;
+    if n <= 15.0 {
+        return ln_gamma(n + 1.0) - (n + 0.5) * n.ln() + n - LN_SQRT_2PI;
+    }
+    let nn = n * n;
+    if n > 500.0 {
+        (S0 - S1 / nn) / n
+    } else if n > 80.0 {
+        (S0 - (S1 - S2 / nn) / nn) / n
+    } else if n > 35.0 {
+        (S0 - (S1 - (S2 - S3 / nn) / nn) / nn) / n
+    } else {
+        (S0 - (S1 - (S2 - (S3 - S4 / nn) / nn) / nn) / nn) / n
+    }
+}
+
+/// Desviación x·ln(x/np) + np − x sin cancelación cuando x ≈ np.
+pub(crate) fn bd0(x: f64, np: f64) -> f64 {
+    if (x - np).abs() < 0.1 * (x + np) {
+        let mut v = (x - np) / (x + np);
+        let mut s = (x - np) * v;
+        let mut ej = 2.0 * x * v;
+        v *= v;
+        for j in 1..1000 {
+            ej *= v;
+            let s1 = s + ej / (2 * j + 1) as f64;
+            if s1 == s {
+                return s1;
+            }
+            s = s1;
+        }
+        s
+    } else {
+        x * (x / np).ln() + np - x
+    }
+}
+
+/// λ^x·e^(−λ)/Γ(x+1), también para x real.
+pub(crate) fn dpois_raw(x: f64, lambda: f64) -> f64 {
+    if lambda == 0.0 {
+        return if x == 0.0 { 1.0 } else { 0.0 };
+    }
+    if x < 0.0 {
+        return 0.0;
+    }
+    if x == 0.0 {
+        return (-lambda).exp();
+    }
+    (-stirlerr(x) - bd0(x, lambda)).exp() / (2.0 * PI * x).sqrt()
+}
+
+/// C(n, x)·p^x·q^(n−x) con q = 1 − p pasado aparte (evita perder precisión).
+pub(crate) fn dbinom_raw(x: f64, n: f64, p: f64, q: f64) -> f64 {
+    if p == 0.0 {
+        return if x == 0.0 { 1.0 } else { 0.0 };
+    }
+    if q == 0.0 {
+        return if x == n { 1.0 } else { 0.0 };
+    }
+    if x == 0.0 {
+        if n == 0.0 {
+            return 1.0;
+        }
+        let lc = if p < 0.1 { -bd0(n, n * q) - n * p } else { n * q.ln() };
+        return lc.exp();
+    }
+    if x == n {
+        let lc = if q < 0.1 { -bd0(n, n * p) - n * q } else { n * p.ln() };
+        return lc.exp();
+    }
+    if x < 0.0 || x > n {
+        return 0.0;
+    }
+    // bd0 supone p + q = 1; n·(p + q − 1) corrige el redondeo de p y q cuando n es grande
+    let excess = if p > q { (p - 1.0) + q } else { (q - 1.0) + p };
+    let lc = stirlerr(n) - stirlerr(x) - stirlerr(n - x) - bd0(x, n * p) - bd0(n - x, n * q) + n * excess;
+    let lf = (2.0 * PI).ln() + x.ln() + (-x / n).ln_1p();
+    (lc - 0.5 * lf).exp()
+}
+
+/// Gamma incompleta regularizada (P(a, x), Q(a, x)). La que se calcula directamente
+/// (serie si x < a+1, fracción continua si no) tiene precisión relativa completa.
+pub(crate) fn gamma_inc(a: f64, x: f64) -> (f64, f64) {
+    if x <= 0.0 {
+        return (0.0, 1.0);
+    }
+    if x.is_infinite() {
+        return (1.0, 0.0);
+    }
+    // x^a·e^(−x)/Γ(a+1)
+    let prefix = if a < 10.0 { (a * x.ln() - x - ln_gamma(a + 1.0)).exp() } else { dpois_raw(a, x) };
+    if x < a + 1.0 {
+        let (mut term, mut sum) = (1.0, 1.0);
+        for n in 1..MAX_ITER {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term < sum * EPS {
+                break;
+            }
+        }
+        let p = prefix * sum;
+        (p, 1.0 - p)
+    } else {
+        // Lentz modificado
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..MAX_ITER {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let del = d * c;
+            h *= del;
+            if (del - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        let q = prefix * a * h;
+        (1.0 - q, q)
+    }
+}
+
+/// Beta incompleta regularizada (I_x(a, b), 1 − I_x(a, b)) con y = 1 − x pasado aparte.
+pub(crate) fn beta_inc(a: f64, b: f64, x: f64, y: f64) -> (f64, f64) {
+    if x <= 0.0 {
+        return (0.0, 1.0);
+    }
+    if y <= 0.0 {
+        return (1.0, 0.0);
+    }
+    if x > (a + 1.0) / (a + b + 2.0) {
+        let (i, ic) = beta_inc(b, a, y, x);
+        return (ic, i);
+    }
+    // x^a·y^b / (a·B(a, b))
+    let front = dbinom_raw(a, a + b, x, y) * b / (a + b);
+
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..MAX_ITER {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for aa in [m * (b - m) * x / ((qam + m2) * (a + m2)), -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2))] {
+            d = 1.0 + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    let i = front * h;
+    (i, 1.0 - i)
+}
+
+/// sin(πx) exacto (cero) en los enteros.
+pub(crate) fn sin_pi(x: f64) -> f64 {
+    let n = x.round();
+    let s = (PI * (x - n)).sin();
+    if n % 2.0 == 0.0 { s } else { -s }
+}
+
+/// cos(πx) exacto (cero) en los semienteros.
+pub(crate) fn cos_pi(x: f64) -> f64 {
+    sin_pi(x + 0.5)
+}
+
+fn is_pole(z: Complex64) -> bool {
+    z.im == 0.0 && z.re <= 0.0 && z.re.fract() == 0.0
+}
+
+/// ln Γ(z) principal para Re z > 0 (Lanczos complejo).
+fn ln_gamma_lanczos(z: Complex64) -> Complex64 {
+    let tmp = z + 5.242_187_5;
+    let tmp = (z + 0.5) * tmp.ln() - tmp;
+    let mut ser = Complex64::new(0.999_999_999_999_997_1, 0.0);
+    let mut y = z;
+    for c in LANCZOS {
+        y += 1.0;
+        ser += c / y;
+    }
+    tmp + (2.506_628_274_631_000_5 * ser).ln() - z.ln()
+}
+
+/// Γ(x) real: producto exacto en los enteros, recurrencia a [1, 2) y reflexión para x < ½.
+fn gamma_real(x: f64) -> f64 {
+    if x < 0.5 {
+        return PI / (sin_pi(x) * gamma_real(1.0 - x));
+    }
+    if x > 171.7 {
+        return f64::INFINITY;
+    }
+    if x.fract() == 0.0 {
+        return (2..x as u64).fold(1.0, |acc, k| acc * k as f64);
+    }
+    let (mut acc, mut y) = (1.0, x);
+    while y >= 2.0 {
+        y -= 1.0;
+        acc *= y;
+    }
+    if y < 1.0 {
+        acc /= y;
+        y += 1.0;
+    }
+    acc * ln_gamma(y).exp()
+}
+
+/// Γ(z) para z complejo; error en los polos (enteros ≤ 0).
+pub(crate) fn gamma(z: Complex64) -> Result<Complex64, String> {
+    if is_pole(z) {
+        return Err("gamma tiene polos en los enteros <= 0".to_string());
+    }
+    if z.im == 0.0 {
+        return Ok(Complex64::new(gamma_real(z.re), 0.0));
+    }
+    if z.re < 0.5 {
+        let one = Complex64::new(1.0, 0.0);
+        return Ok(PI / ((PI * z).sin() * ln_gamma_lanczos(one - z).exp()));
+    }
+    Ok(ln_gamma_lanczos(z).exp())
+}
+
+/// ln Γ: para x real, ln|Γ(x)|; para z complejo, la rama principal (continua fuera
+/// del semieje real negativo, como `loggamma` de mpmath/SciPy).
+pub(crate) fn lgamma(z: Complex64) -> Result<Complex64, String> {
+    if is_pole(z) {
+        return Err("lgamma tiene polos en los enteros <= 0".to_string());
+    }
+    if z.im == 0.0 {
+        let x = z.re;
+        let v = if x > 0.0 { ln_gamma(x) } else { PI.ln() - sin_pi(x).abs().ln() - ln_gamma(1.0 - x) };
+        return Ok(Complex64::new(v, 0.0));
+    }
+    if z.re >= 0.5 {
+        return Ok(ln_gamma_lanczos(z));
+    }
+    // ln Γ(z) = ln Γ(z+n) − Σ ln(z+k): recorre la rama sin saltos de 2πi
+    let n = (0.5 - z.re).ceil();
+    if n > 1e6 {
+        return Err("lgamma: |Re z| demasiado grande con parte imaginaria".to_string());
+    }
+    let mut acc = Complex64::new(0.0, 0.0);
+    for k in 0..n as u64 {
+        acc += (z + k as f64).ln();
+    }
+    Ok(ln_gamma_lanczos(z + n) - acc)
+}
+
+/// B(a, b) = Γ(a)Γ(b)/Γ(a+b).
+pub(crate) fn beta(a: Complex64, b: Complex64) -> Result<Complex64, String> {
+    if is_pole(a) || is_pole(b) {
+        return Err("beta tiene polos cuando a o b es un entero <= 0".to_string());
+    }
+    if a.im == 0.0 && b.im == 0.0 && a.re > 0.0 && b.re > 0.0 {
+        let (x, y) = (a.re, b.re);
+        let v = if x + y < 171.0 {
+            gamma_real(x) / gamma_real(x + y) * gamma_real(y)
+        } else {
+            (ln_gamma(x) + ln_gamma(y) - ln_gamma(x + y)).exp()
+        };
+        return Ok(Complex64::new(v, 0.0));
+    }
+    if is_pole(a + b) {
+        return Ok(Complex64::new(0.0, 0.0));
+    }
+    Ok(gamma(a)? * gamma(b)? / gamma(a + b)?)
+}
+
+/// Función de error real (vía la gamma incompleta: erf x = P(½, x²)).
+pub(crate) fn erf(x: f64) -> f64 {
+    if x.abs() < 1e-8 {
+        return 2.0 / PI.sqrt() * x * (1.0 - x * x / 3.0);
+    }
+    let (p, _) = gamma_inc(0.5, x * x);
+    p.copysign(x)
+}
+
+/// Complementaria, con precisión relativa completa en la cola derecha.
+pub(crate) fn erfc(x: f64) -> f64 {
+    if x.abs() < 1e-8 {
+        return 1.0 - erf(x);
+    }
+    let (p, q) = gamma_inc(0.5, x * x);
+    if x > 0.0 { q } else { 1.0 + p }
+}
+
+/// Inversa de erf en (−1, 1). Cerca de ±1 se parte de la inversa normal aplicada a
+/// 1 − |y| (exacta en aritmética flotante) y se pule con Halley sobre erfc.
+pub(crate) fn erfinv(y: f64) -> Result<f64, String> {
+    if y.is_nan() || y.abs() > 1.0 {
+        return Err("erfinv requiere -1 <= y <= 1".to_string());
+    }
+    if y.abs() == 1.0 {
+        return Ok(f64::INFINITY.copysign(y));
+    }
+    if y == 0.0 {
+        return Ok(y);
+    }
+    let k = 2.0 / PI.sqrt();
+    if y.abs() < 0.5 {
+        let mut x = y / k * (1.0 + PI * y * y / 12.0);
+        for _ in 0..20 {
+            let f = erf(x) - y;
+            let dx = f / (k * (-x * x).exp() + x * f);
+            x -= dx;
+            if dx.abs() <= 1e-17 * x.abs() {
+                break;
+            }
+        }
+        return Ok(x);
+    }
+    let q = 1.0 - y.abs();
+    let mut x = -distributions::eval_dist("norminv", &[0.5 * q])? / 2f64.sqrt();
+    for _ in 0..20 {
+        let f = erfc(x) - q;
+        let dx = f / (-k * (-x * x).exp() + x * f);
+        x -= dx;
+        if dx.abs() <= 1e-17 * x.abs() {
+            break;
+        }
+    }
+    Ok(x.copysign(y))
+}
+
+/// Números de Bernoulli B_2, B_4, …, B_24.
+const BERNOULLI: [f64; 12] = [
+    1.0 / 6.0,
+    -1.0 / 30.0,
+    1.0 / 42.0,
+    -1.0 / 30.0,
+    5.0 / 66.0,
+    -691.0 / 2730.0,
+    7.0 / 6.0,
+    -3617.0 / 510.0,
+    43867.0 / 798.0,
+    -174611.0 / 330.0,
+    854513.0 / 138.0,
+    -236364091.0 / 2730.0,
+];
+
+/// ζ(s) de Riemann: Euler–Maclaurin para Re s ≥ 0 y ecuación funcional para Re s < 0.
+pub(crate) fn zeta(s: Complex64) -> Result<Complex64, String> {
+    let one = Complex64::new(1.0, 0.0);
+    if s == one {
+        return Err("zeta tiene un polo en s = 1".to_string());
+    }
+    if s.re < 0.0 {
+        // ζ(s) = 2^s π^(s−1) sin(πs/2) Γ(1−s) ζ(1−s); ceros triviales exactos
+        let sin = if s.im == 0.0 { Complex64::new(sin_pi(0.5 * s.re), 0.0) } else { (0.5 * PI * s).sin() };
+        if sin == Complex64::new(0.0, 0.0) {
+            return Ok(sin);
+        }
+        let pre = Complex64::new(2.0, 0.0).powc(s) * Complex64::new(PI, 0.0).powc(s - 1.0) * sin;
+        return Ok(pre * gamma(one - s)? * zeta(one - s)?);
+    }
+    let n = (12.0 + s.norm()).ceil();
+    let mut sum = Complex64::new(0.0, 0.0);
+    for k in 1..n as u64 {
+        sum += (-s * (k as f64).ln()).exp();
+    }
+    let n_s = (-s * n.ln()).exp();
+    sum += n_s * n / (s - 1.0) + 0.5 * n_s;
+    // Σ B_2j/(2j)! · s(s+1)…(s+2j−2) · N^(−s−2j+1)
+    let mut poch = s / n;
+    let mut fact = 2.0;
+    for (j, b) in BERNOULLI.iter().enumerate() {
+        let term = n_s * poch * (b / fact);
+        sum += term;
+        if term.norm() <= 1e-17 * sum.norm() {
+            break;
+        }
+        let m = 2.0 * j as f64 + 1.0;
+        poch *= (s + m) * (s + m + 1.0) / (n * n);
+        fact *= (m + 2.0) * (m + 3.0);
+    }
+    Ok(sum)
+}
+
+/// ψ(z) = Γ'(z)/Γ(z): reflexión para Re z < ½, recurrencia hasta Re z ≥ 10 y serie asintótica.
+pub(crate) fn digamma(z: Complex64) -> Result<Complex64, String> {
+    if is_pole(z) {
+        return Err("digamma tiene polos en los enteros <= 0".to_string());
+    }
+    let one = Complex64::new(1.0, 0.0);
+    if z.re < 0.5 {
+        // ψ(z) = ψ(1−z) − π cot(πz); cot tiene periodo 1, así que se reduce el argumento
+        let w = z - z.re.round();
+        let cot = if w.im == 0.0 {
+            Complex64::new(cos_pi(w.re) / sin_pi(w.re), 0.0)
+        } else {
+            (PI * w).cos() / (PI * w).sin()
+        };
+        return Ok(digamma(one - z)? - PI * cot);
+    }
+    let (mut acc, mut z) = (Complex64::new(0.0, 0.0), z);
+    while z.re < 10.0 {
+        acc -= one / z;
+        z += 1.0;
+    }
+    let z2 = one / (z * z);
+    let mut pw = z2;
+    let mut series = z.ln() - 0.5 / z;
+    for (k, b) in BERNOULLI.iter().take(8).enumerate() {
+        series -= pw * (b / (2.0 * (k + 1) as f64));
+        pw *= z2;
+    }
+    Ok(acc + series)
+}
+
+/// Rama k de la W de Lambert (w·e^w = z) por iteración de Halley.
+pub(crate) fn lambert_w(k: i64, z: Complex64) -> Result<Complex64, String> {
+    let zero = Complex64::new(0.0, 0.0);
+    if z == zero {
+        return if k == 0 { Ok(zero) } else { Err("lambertw: W_k(0) = -inf para k != 0".to_string()) };
+    }
+    let branch_pt = Complex64::new(-1.0 / E, 0.0);
+    // Desarrollo en p = √(2(ez+1)) alrededor del punto de ramificación −1/e
+    let p = (2.0 * (E * z + 1.0)).sqrt();
+    let near = (z - branch_pt).norm() < 0.3;
+    let mut w = if k == 0 && (z - branch_pt).norm() < 1.0 {
+        -1.0 + p - p * p / 3.0 + 11.0 / 72.0 * p * p * p
+    } else if near && ((k == -1 && z.im >= 0.0) || (k == 1 && z.im < 0.0)) {
+        // W_(−1) (arriba y en el eje) y W_1 (abajo) comparten el punto de ramificación con W_0
+        -1.0 - p - p * p / 3.0 - 11.0 / 72.0 * p * p * p
+    } else if k == 0 {
+        (1.0 + z).ln()
+    } else if k == -1 && z.im == 0.0 && z.re < 0.0 && z.re >= -1.0 / E {
+        // W_(−1) real en [−1/e, 0): partir de ln(−x) − ln(−ln(−x))
+        let l1 = (-z.re).ln();
+        Complex64::new(l1 - (-l1).ln(), 0.0)
+    } else {
+        let l1 = z.ln() + Complex64::new(0.0, 2.0 * PI * k as f64);
+        let l2 = l1.ln();
+        l1 - l2 + l2 / l1
+    };
+    for _ in 0..100 {
+        // Halley sobre f = w·e^w − z, dividido por e^w para no desbordar con |w| grande
+        let f = w - z * (-w).exp();
+        let w1 = w + 1.0;
+        if w1.norm() < 1e-300 {
+            break;
+        }
+        let dw = f / (w1 - (w + 2.0) * f / (2.0 * w1));
+        w -= dw;
+        if dw.norm() <= 1e-15 * (1.0 + w.norm()) {
+            break;
+        }
+    }
+    // Sobre el segmento real donde la rama es real, descartar el residuo imaginario
+    let real_branch = z.im == 0.0 && z.re >= -1.0 / E && (k == 0 || (k == -1 && z.re < 0.0));
+    if real_branch {
+        w.im = 0.0;
+    }
+    Ok(w)
+}
+
+/// Integrales elípticas completas (K(m), E(m)) con parámetro m ≤ 1, por la media
+/// aritmético-geométrica.
+pub(crate) fn ellip_ke(m: f64) -> Result<(f64, f64), String> {
+    if m.is_nan() || m > 1.0 {
+        return Err("ellipk/ellipe requieren m <= 1".to_string());
+    }
+    if m == 1.0 {
+        return Ok((f64::INFINITY, 1.0));
+    }
+    // E = K·(1 − Σ 2^(n−1) c_n²), con c_0² = m y c_(n+1) = c_n²/(4 a_(n+1))
+    let (mut a, mut b) = (1.0, (1.0 - m).sqrt());
+    let mut c = m;
+    let mut pow2 = 0.5;
+    let mut sum = 0.5 * m;
+    let mut first = true;
+    for _ in 0..64 {
+        let an = 0.5 * (a + b);
+        b = (a * b).sqrt();
+        a = an;
+        c = if first { m / (4.0 * a) } else { c * c / (4.0 * a) };
+        first = false;
+        pow2 *= 2.0;
+        sum += pow2 * c * c;
+        if c.abs() <= 1e-17 * a {
+            break;
+        }
+    }
+    let k = PI / (2.0 * a);
+    Ok((k, k * (1.0 - sum)))
+}
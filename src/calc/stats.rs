@@ -0,0 +1,272 @@
+use super::Calculator;
+use num_complex::Complex64;
+
+/// Funciones estadísticas variádicas: `median(1, 2, 3)`, `std(...)`, ...
+/// Los mismos nombres son comandos de la REPL que operan sobre la pila.
+pub const STAT_FUNCS: &[&str] = &[
+    "count", "mean", "median", "moda", "var", "varp", "std", "stdp", "quartile", "iqr", "skew", "kurt", "gmean", "hmean",
+];
+
+/// Resumen descriptivo de un conjunto de datos reales (comando `stats`).
+/// Los estadísticos que no existen para esos datos (n pequeño, valores no positivos) son `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub modes: Vec<f64>,
+    pub min: f64,
+    pub max: f64,
+    pub var_sample: Option<f64>,
+    pub var_pop: f64,
+    pub std_sample: Option<f64>,
+    pub std_pop: f64,
+    pub q1: f64,
+    pub q3: f64,
+    pub iqr: f64,
+    pub skewness: Option<f64>,
+    pub kurtosis: Option<f64>,
+    pub geometric_mean: Option<f64>,
+    pub harmonic_mean: Option<f64>,
+}
+
+/// Datos reales a partir de complejos; la estadística de orden no tiene sentido en ℂ.
+pub fn real_data(vals: &[Complex64]) -> Result<Vec<f64>, String> {
+    if vals.is_empty() {
+        return Err("Sin datos".to_string());
+    }
+    if vals.iter().any(|v| v.im.abs() > 1e-10) {
+        return Err("La estadística requiere valores reales".to_string());
+    }
+    if vals.iter().any(|v| !v.re.is_finite()) {
+        return Err("Datos no finitos (NaN/Inf)".to_string());
+    }
+    Ok(vals.iter().map(|v| v.re).collect())
+}
+
+fn sorted(data: &[f64]) -> Vec<f64> {
+    let mut s = data.to_vec();
+    s.sort_by(f64::total_cmp);
+    s
+}
+
+pub fn mean(data: &[f64]) -> f64 {
+    data.iter().sum::<f64>() / data.len() as f64
+}
+
+/// Cuantil p (0..=1) por interpolación lineal entre estadísticos de orden
+/// (método por defecto de NumPy y QUARTILE.INC de Excel). `s` debe estar ordenado.
+fn quantile_sorted(s: &[f64], p: f64) -> f64 {
+    let pos = p * (s.len() - 1) as f64;
+    let (lo, frac) = (pos.floor() as usize, pos.fract());
+    if lo + 1 < s.len() { s[lo] + frac * (s[lo + 1] - s[lo]) } else { s[lo] }
+}
+
+/// Valores más frecuentes (vacío si todos aparecen una sola vez).
+pub fn modes(data: &[f64]) -> Vec<f64> {
+    let s = sorted(data);
+    let mut runs: Vec<(f64, usize)> = Vec::new();
+    for v in s {
+        match runs.last_mut() {
+            Some((last, n)) if *last == v => *n += 1,
+            _ => runs.push((v, 1)),
+        }
+    }
+    let best = runs.iter().map(|r| r.1).max().unwrap_or(0);
+    if best < 2 {
+        return Vec::new();
+    }
+    runs.into_iter().filter(|r| r.1 == best).map(|r| r.0).collect()
+}
+
+/// Suma de potencias centradas Σ (x - media)^k (dos pasadas, sin cancelación).
+fn central_sum(data: &[f64], k: i32) -> f64 {
+    let m = mean(data);
+    data.iter().map(|x| (x - m).powi(k)).sum()
+}
+
+/// Varianza muestral (n-1) o poblacional (n).
+pub fn variance(data: &[f64], sample: bool) -> Result<f64, String> {
+    let n = data.len() as f64;
+    if sample && data.len() < 2 {
+        return Err("La varianza muestral requiere al menos 2 datos".to_string());
+    }
+    Ok(central_sum(data, 2) / if sample { n - 1.0 } else { n })
+}
+
+/// Asimetría muestral ajustada (SKEW de Excel).
+pub fn skewness(data: &[f64]) -> Result<f64, String> {
+    let n = data.len() as f64;
+    if data.len() < 3 {
+        return Err("La asimetría requiere al menos 3 datos".to_string());
+    }
+    let s = variance(data, true)?.sqrt();
+    if s == 0.0 {
+        return Err("Asimetría indefinida: todos los datos son iguales".to_string());
+    }
+    Ok(n / ((n - 1.0) * (n - 2.0)) * central_sum(data, 3) / s.powi(3))
+}
+
+/// Curtosis en exceso muestral (KURT de Excel).
+pub fn kurtosis(data: &[f64]) -> Result<f64, String> {
+    let n = data.len() as f64;
+    if data.len() < 4 {
+        return Err("La curtosis requiere al menos 4 datos".to_string());
+    }
+    let s2 = variance(data, true)?;
+    if s2 == 0.0 {
+        return Err("Curtosis indefinida: todos los datos son iguales".to_string());
+    }
+    let lead = n * (n + 1.0) / ((n - 1.0) * (n - 2.0) * (n - 3.0));
+    let tail = 3.0 * (n - 1.0).powi(2) / ((n - 2.0) * (n - 3.0));
+    Ok(lead * central_sum(data, 4) / (s2 * s2) - tail)
+}
+
+/// Media geométrica (en logaritmos, sin desbordamiento).
+pub fn geometric_mean(data: &[f64]) -> Result<f64, String> {
+    if data.iter().any(|x| *x <= 0.0) {
+        return Err("La media geométrica requiere datos positivos".to_string());
+    }
+    Ok((data.iter().map(|x| x.ln()).sum::<f64>() / data.len() as f64).exp())
+}
+
+pub fn harmonic_mean(data: &[f64]) -> Result<f64, String> {
+    if data.iter().any(|x| *x <= 0.0) {
+        return Err("La media armónica requiere datos positivos".to_string());
+    }
+    Ok(data.len() as f64 / data.iter().map(|x| 1.0 / x).sum::<f64>())
+}
+
+/// Evalúa la función estadística `name` sobre `data`.
+/// `quartile(k, ...)` toma el número de cuartil (0..=4) como primer argumento.
+pub fn stat(name: &str, data: &[f64]) -> Result<f64, String> {
+    if data.is_empty() {
+        return Err(format!("{} requiere al menos un dato", name));
+    }
+    let s = sorted(data);
+    match name {
+        "count" => Ok(data.len() as f64),
+        "mean" => Ok(mean(data)),
+        "median" => Ok(quantile_sorted(&s, 0.5)),
+        "moda" => modes(data).first().copied().ok_or("No hay moda: todos los valores son distintos".to_string()),
+        "var" => variance(data, true),
+        "varp" => variance(data, false),
+        "std" => variance(data, true).map(f64::sqrt),
+        "stdp" => variance(data, false).map(f64::sqrt),
+        "quartile" => {
+            let (k, rest) = data.split_first().unwrap();
+            if rest.is_empty() || k.fract() != 0.0 || !(0.0..=4.0).contains(k) {
+                return Err("Uso: quartile(k, x1, x2, ...) con k = 0..4".to_string());
+            }
+            Ok(quantile_sorted(&sorted(rest), k / 4.0))
+        }
+        "iqr" => Ok(quantile_sorted(&s, 0.75) - quantile_sorted(&s, 0.25)),
+        "skew" => skewness(data),
+        "kurt" => kurtosis(data),
+        "gmean" => geometric_mean(data),
+        "hmean" => harmonic_mean(data),
+        _ => Err(format!("Función estadística '{}' desconocida", name)),
+    }
+}
+
+/// Derivada direccional Σ ∂f/∂xᵢ·dxᵢ de la función estadística `name` en `data`.
+/// Los cuantiles derivan la interpolación entre los datos que eligen y la moda promedia
+/// las derivadas de sus repeticiones (estadísticos de orden: derivables a trozos).
+pub fn stat_derivative(name: &str, data: &[f64], d: &[f64]) -> Result<f64, String> {
+    let r = stat(name, data)?;
+    let n = data.len() as f64;
+    // Misma interpolación que `quantile_sorted`, sobre las derivadas ordenadas como sus datos
+    let quantile = |data: &[f64], d: &[f64], p: f64| {
+        let mut idx: Vec<usize> = (0..data.len()).collect();
+        idx.sort_by(|&i, &j| data[i].total_cmp(&data[j]));
+        quantile_sorted(&idx.iter().map(|&i| d[i]).collect::<Vec<_>>(), p)
+    };
+    let (m, dm) = (mean(data), mean(d));
+    // d Σ (xᵢ − m)^k = k·Σ (xᵢ − m)^(k−1)·(dxᵢ − dm)
+    let dcentral = |k: i32| k as f64 * data.iter().zip(d).map(|(x, dx)| (x - m).powi(k - 1) * (dx - dm)).sum::<f64>();
+    Ok(match name {
+        "count" => 0.0,
+        "mean" => dm,
+        "median" => quantile(data, d, 0.5),
+        "quartile" => quantile(&data[1..], &d[1..], data[0] / 4.0),
+        "iqr" => quantile(data, d, 0.75) - quantile(data, d, 0.25),
+        "moda" => {
+            let (sum, count) = data.iter().zip(d).filter(|(x, _)| **x == r).fold((0.0, 0.0), |(s, c), (_, dx)| (s + dx, c + 1.0));
+            sum / count
+        }
+        "var" => dcentral(2) / (n - 1.0),
+        "varp" => dcentral(2) / n,
+        "std" => dcentral(2) / (n - 1.0) / (2.0 * r),
+        "stdp" => dcentral(2) / n / (2.0 * r),
+        "skew" => {
+            let s = variance(data, true)?.sqrt();
+            let ds = dcentral(2) / (n - 1.0) / (2.0 * s);
+            n / ((n - 1.0) * (n - 2.0)) * (dcentral(3) / s.powi(3) - 3.0 * central_sum(data, 3) * ds / s.powi(4))
+        }
+        "kurt" => {
+            let s2 = variance(data, true)?;
+            let ds2 = dcentral(2) / (n - 1.0);
+            let lead = n * (n + 1.0) / ((n - 1.0) * (n - 2.0) * (n - 3.0));
+            lead * (dcentral(4) / (s2 * s2) - 2.0 * central_sum(data, 4) * ds2 / s2.powi(3))
+        }
+        "gmean" => r * data.iter().zip(d).map(|(x, dx)| dx / x).sum::<f64>() / n,
+        "hmean" => r * r / n * data.iter().zip(d).map(|(x, dx)| dx / (x * x)).sum::<f64>(),
+        _ => return Err(format!("Función estadística '{}' desconocida", name)),
+    })
+}
+
+pub fn summary(data: &[f64]) -> Result<Summary, String> {
+    if data.is_empty() {
+        return Err("Sin datos".to_string());
+    }
+    let s = sorted(data);
+    let (q1, q3) = (quantile_sorted(&s, 0.25), quantile_sorted(&s, 0.75));
+    let var_sample = variance(data, true).ok();
+    let var_pop = variance(data, false)?;
+    Ok(Summary {
+        count: data.len(),
+        mean: mean(data),
+        median: quantile_sorted(&s, 0.5),
+        modes: modes(data),
+        min: s[0],
+        max: s[s.len() - 1],
+        var_sample,
+        var_pop,
+        std_sample: var_sample.map(f64::sqrt),
+        std_pop: var_pop.sqrt(),
+        q1,
+        q3,
+        iqr: q3 - q1,
+        skewness: skewness(data).ok(),
+        kurtosis: kurtosis(data).ok(),
+        geometric_mean: geometric_mean(data).ok(),
+        harmonic_mean: harmonic_mean(data).ok(),
+    })
+}
+
+impl Calculator {
+    /// Resumen descriptivo de la pila.
+    pub fn stack_summary(&self) -> Result<Summary, String> {
+        summary(&real_data(&self.memory_stack).map_err(|_| self.stack_error())?)
+    }
+
+    /// Un estadístico de la pila por nombre de comando (`q1`/`q3` son los cuartiles).
+    pub fn stack_stat(&self, name: &str) -> Result<f64, String> {
+        let data = real_data(&self.memory_stack).map_err(|_| self.stack_error())?;
+        match name {
+            "q1" | "q3" => {
+                let k = if name == "q1" { 1.0 } else { 3.0 };
+                stat("quartile", &[&[k][..], &data].concat())
+            }
+            _ => stat(name, &data),
+        }
+    }
+
+    fn stack_error(&self) -> String {
+        if self.memory_stack.is_empty() {
+            "Pila vacía.".to_string()
+        } else {
+            "La estadística requiere valores reales en la pila".to_string()
+        }
+    }
+}
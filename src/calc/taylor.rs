@@ -0,0 +1,580 @@
+use super::eval::{apply_func, Rpn};
+use super::{fmt_literal, random::RANDOM_FUNCS, special, AngleUnit, Calculator};
+use num_complex::{Complex64, ComplexFloat};
+
+/// Orden máximo admitido por `taylor`.
+pub const MAX_TAYLOR_ORDER: usize = 40;
+/// Puntos sobre la circunferencia para la integral de Cauchy.
+const CAUCHY_POINTS: usize = 128;
+/// Nodos de Chebyshev en el eje real para las funciones que solo admiten argumentos reales.
+const CHEB_POINTS: usize = 48;
+
+/// Serie truncada: coeficiente k-ésimo de (x-a)^k. Su longitud es el nº de coeficientes conocidos
+/// (una división que simplifica factores (x-a)^m pierde m de ellos).
+type Series = Vec<Complex64>;
+
+/// Desarrollo de Taylor. `exact` indica aritmética de series (coeficientes sin error de
+/// truncamiento); si no, `errors[k]` es el error estimado de cada coeficiente.
+#[derive(Debug, Clone)]
+pub struct TaylorSeries {
+    pub coeffs: Vec<Complex64>,
+    pub errors: Vec<f64>,
+    pub exact: bool,
+}
+
+enum JetError {
+    /// La expresión usa algo sin regla de series: se recurre a la integral de Cauchy.
+    Unsupported,
+    /// Faltan coeficientes por cancelaciones: se repite con más términos de reserva.
+    Short,
+    Fail(String),
+}
+
+impl From<String> for JetError {
+    fn from(e: String) -> Self {
+        JetError::Fail(e)
+    }
+}
+
+fn real(x: f64) -> Complex64 {
+    Complex64::new(x, 0.0)
+}
+
+fn zero() -> Complex64 {
+    real(0.0)
+}
+
+fn constant(v: Complex64, len: usize) -> Series {
+    let mut s = vec![zero(); len];
+    s[0] = v;
+    s
+}
+
+fn map(a: &[Complex64], f: impl Fn(Complex64) -> Complex64) -> Series {
+    a.iter().map(|c| f(*c)).collect()
+}
+
+fn add(a: &[Complex64], b: &[Complex64]) -> Series {
+    a.iter().zip(b).map(|(x, y)| x + y).collect()
+}
+
+fn sub(a: &[Complex64], b: &[Complex64]) -> Series {
+    a.iter().zip(b).map(|(x, y)| x - y).collect()
+}
+
+fn mul(a: &[Complex64], b: &[Complex64]) -> Series {
+    let n = a.len().min(b.len());
+    (0..n).map(|k| (0..=k).map(|j| a[j] * b[k - j]).sum()).collect()
+}
+
+/// Cociente de series. Si el divisor se anula en el punto, se simplifica el factor (x-a)^m
+/// común (p. ej. sin(x)/x en 0); si el numerador no lo comparte, hay un polo.
+fn div(a: &[Complex64], b: &[Complex64]) -> Result<Series, JetError> {
+    let Some(m) = b.iter().position(|c| *c != zero()) else {
+        return Err(JetError::Short);
+    };
+    if a.iter().take(m).any(|c| *c != zero()) {
+        return Err(JetError::Fail("División por cero".to_string()));
+    }
+    let (a, b) = (&a[m.min(a.len())..], &b[m..]);
+    let n = a.len().min(b.len());
+    let mut q: Series = Vec::with_capacity(n);
+    for k in 0..n {
+        let s: Complex64 = (1..=k).map(|j| b[j] * q[k - j]).sum();
+        q.push((a[k] - s) / b[0]);
+    }
+    Ok(q)
+}
+
+/// Derivada término a término (un coeficiente menos).
+fn deriv(a: &[Complex64]) -> Series {
+    (1..a.len()).map(|k| a[k] * k as f64).collect()
+}
+
+/// Primitiva con término independiente `c0`.
+fn integ(d: &[Complex64], c0: Complex64) -> Series {
+    std::iter::once(c0).chain(d.iter().enumerate().map(|(k, c)| c / (k + 1) as f64)).collect()
+}
+
+fn exp(a: &[Complex64]) -> Series {
+    let mut e = vec![a[0].exp()];
+    for k in 1..a.len() {
+        let s: Complex64 = (1..=k).map(|j| a[j] * e[k - j] * j as f64).sum();
+        e.push(s / k as f64);
+    }
+    e
+}
+
+fn ln(a: &[Complex64]) -> Result<Series, JetError> {
+    if a[0] == zero() {
+        return Err(JetError::Fail("ln(0) indefinido".to_string()));
+    }
+    let mut l = vec![a[0].ln()];
+    for k in 1..a.len() {
+        let s: Complex64 = (1..k).map(|j| l[j] * a[k - j] * j as f64).sum();
+        l.push((a[k] - s / k as f64) / a[0]);
+    }
+    Ok(l)
+}
+
+/// Potencia de exponente constante: k·a0·y_k = Σ (p·j − (k−j))·a_j·y_{k−j}.
+fn pow_const(a: &[Complex64], p: Complex64, name: &str) -> Result<Series, JetError> {
+    let int_p = p.im == 0.0 && p.re.fract() == 0.0 && p.re.abs() <= 64.0;
+    if a[0] == zero() {
+        if int_p && p.re >= 0.0 {
+            let mut y = constant(real(1.0), a.len());
+            for _ in 0..p.re as usize {
+                y = mul(&y, a);
+            }
+            return Ok(y);
+        }
+        return Err(JetError::Fail(format!("'{}' no es analítica en ese punto", name)));
+    }
+    let mut y = vec![if int_p { a[0].powi(p.re as i32) } else { a[0].powc(p) }];
+    for k in 1..a.len() {
+        let s: Complex64 = (1..=k).map(|j| a[j] * y[k - j] * (p * j as f64 - (k - j) as f64)).sum();
+        y.push(s / (a[0] * k as f64));
+    }
+    Ok(y)
+}
+
+/// a^b: exponente constante por recurrencia, variable como exp(b·ln a).
+fn pow(a: &[Complex64], b: &[Complex64], name: &str) -> Result<Series, JetError> {
+    if b[1..].iter().all(|c| *c == zero()) {
+        pow_const(a, b[0], name)
+    } else {
+        Ok(exp(&mul(b, &ln(a)?)))
+    }
+}
+
+/// Seno y coseno (o seno y coseno hiperbólicos si `hyperbolic`) a la vez.
+fn sin_cos(a: &[Complex64], hyperbolic: bool) -> (Series, Series) {
+    let (mut s, mut c) = if hyperbolic { (vec![a[0].sinh()], vec![a[0].cosh()]) } else { (vec![a[0].sin()], vec![a[0].cos()]) };
+    let sign = if hyperbolic { 1.0 } else { -1.0 };
+    for k in 1..a.len() {
+        let ds: Complex64 = (1..=k).map(|j| a[j] * c[k - j] * j as f64).sum();
+        let dc: Complex64 = (1..=k).map(|j| a[j] * s[k - j] * j as f64).sum();
+        s.push(ds / k as f64);
+        c.push(dc * sign / k as f64);
+    }
+    (s, c)
+}
+
+/// Serie de `func(args)`. Replica la semántica de `apply_func` (ángulos en la unidad activa,
+/// funciones que solo usan la parte real); el término independiente lo fija después el llamador.
+fn jet(func: &str, args: &[Series], unit: AngleUnit) -> Result<Series, JetError> {
+    let one = real(1.0);
+    let x = &args[args.len() - 1];
+    let len = x.len();
+    let re = |s: &Series| map(s, |c| real(c.re));
+    let scale = |s: &Series, f: f64| map(s, |c| c * f);
+    let deg_in = |s: &Series| map(s, |c| unit.to_rad(c));
+    let deg_out = |s: Series| map(&s, |c| unit.express_rad(c));
+    let ones = constant(one, len);
+    let sq = |s: &Series| mul(s, s);
+
+    let res = match func {
+        // --- Trigonométricas ---
+        "sin" => sin_cos(&deg_in(x), false).0,
+        "cos" => sin_cos(&deg_in(x), false).1,
+        "tan" => {
+            let (s, c) = sin_cos(&deg_in(x), false);
+            div(&s, &c)?
+        }
+        "asin" => deg_out(integ(&mul(&deriv(x), &pow_const(&sub(&ones, &sq(x)), real(-0.5), func)?), zero())),
+        "acos" => deg_out(integ(&scale(&mul(&deriv(x), &pow_const(&sub(&ones, &sq(x)), real(-0.5), func)?), -1.0), zero())),
+        "atan" => deg_out(integ(&div(&deriv(x), &add(&ones, &sq(x)))?, zero())),
+        "atan2" => {
+            let (y, x) = (re(&args[0]), re(&args[1]));
+            let num = sub(&mul(&x, &deriv(&y)), &mul(&y, &deriv(&x)));
+            deg_out(integ(&div(&num, &add(&sq(&x), &sq(&y)))?, zero()))
+        }
+        "hypot" => {
+            let norm2 = |s: &Series| mul(s, &map(s, |c| c.conj()));
+            pow_const(&add(&norm2(&args[0]), &norm2(&args[1])), real(0.5), func)?
+        }
+
+        // --- Hiperbólicas ---
+        "sinh" => sin_cos(x, true).0,
+        "cosh" => sin_cos(x, true).1,
+        "tanh" => {
+            let (s, c) = sin_cos(x, true);
+            div(&s, &c)?
+        }
+        "asinh" => integ(&mul(&deriv(x), &pow_const(&add(&sq(x), &ones), real(-0.5), func)?), zero()),
+        "acosh" => {
+            let a = pow_const(&sub(x, &ones), real(-0.5), func)?;
+            let b = pow_const(&add(x, &ones), real(-0.5), func)?;
+            integ(&mul(&deriv(x), &mul(&a, &b)), zero())
+        }
+        "atanh" => integ(&div(&deriv(x), &sub(&ones, &sq(x)))?, zero()),
+
+        // --- Raíces y Logaritmos ---
+        "sqrt" => pow_const(x, real(0.5), func)?,
+        // Rama real para x₀ < 0, como `apply_func`
+        "cbrt" if x[0].im == 0.0 && x[0].re < 0.0 => scale(&pow_const(&scale(x, -1.0), real(1.0 / 3.0), func)?, -1.0),
+        "cbrt" => pow_const(x, real(1.0 / 3.0), func)?,
+        "root" => pow(x, &div(&ones, &args[0])?, func)?,
+        "ln" => ln(x)?,
+        "log10" => scale(&ln(x)?, 1.0 / std::f64::consts::LN_10),
+        "log2" => scale(&ln(x)?, 1.0 / std::f64::consts::LN_2),
+        "log" => div(&ln(x)?, &ln(&args[0])?)?,
+        "exp" => exp(x),
+        "pow" | "^" => pow(&args[0], x, func)?,
+
+        // --- Funciones especiales: erf' = 2/√π·e^(−x²), erfinv' = √π/2·e^(erfinv²) ---
+        "erf" | "erfc" => {
+            let sign = if func == "erf" { 1.0 } else { -1.0 };
+            integ(&scale(&mul(&deriv(x), &exp(&scale(&sq(x), -1.0))), sign * std::f64::consts::FRAC_2_SQRT_PI), zero())
+        }
+        "erfinv" => {
+            // Punto fijo de y = y₀ + ∫ √π/2·e^(y²)·x′: cada vuelta fija un coeficiente más
+            let y0 = real(special::erfinv(x[0].re)?);
+            let mut y = constant(y0, len);
+            for _ in 1..len {
+                y = integ(&scale(&mul(&deriv(x), &exp(&sq(&y))), 1.0 / std::f64::consts::FRAC_2_SQRT_PI), y0);
+            }
+            y
+        }
+
+        // --- Constantes a trozos ---
+        "floor" | "ceil" | "round" | "trunc" | "sign" | "isprime" | "nextprime" | "mcd" | "mcm"
+        | "prevprime" | "primepi" | "nthprime" | "totient" | "sigma" | "moebius" | "isperfect"
+        | "powmod" | "invmod" | "jacobi" | "legendre" | "sqrtmod"
+        | "catalan" | "stirling1" | "stirling2" | "bell" | "partitions" | "multinomial" | "derangements" | "fib" | "lucas"
+        | "comb" | "nCr" | "perm" | "nPr" => constant(zero(), args.iter().map(|a| a.len()).min().unwrap_or(len)),
+
+        // --- Conversiones (factores de escala) ---
+        "deg2rad" => scale(x, std::f64::consts::PI / 180.0),
+        "rad2deg" => scale(x, 180.0 / std::f64::consts::PI),
+        "cm2in" => scale(x, 1.0 / 2.54),
+        "in2cm" => scale(x, 2.54),
+        "m2ft" => scale(x, 3.280_839_895),
+        "ft2m" => scale(x, 1.0 / 3.280_839_895),
+
+        // --- Complejos: la variable del desarrollo es real ---
+        "abs" => pow_const(&mul(x, &map(x, |c| c.conj())), real(0.5), func)?,
+        "arg" => map(&ln(x)?, |c| real(c.im)),
+        "conj" => map(x, |c| c.conj()),
+        "re" => re(x),
+        "im" => map(x, |c| real(c.im)),
+
+        // --- Estadística / Pct ---
+        "min" | "max" => {
+            let (a, b) = (&args[0], &args[1]);
+            let pick_a = if func == "min" { a[0].re < b[0].re } else { a[0].re > b[0].re };
+            if pick_a { a.clone() } else { b.clone() }
+        }
+        "pct" => scale(&div(&args[0], &args[1])?, 100.0),
+        "applypct" => scale(&mul(&args[0], &args[1]), 0.01),
+
+        // --- Regla de tres ---
+        "r3d" => div(&mul(&args[2], &args[1]), &args[0])?,
+        "r3i" => div(&mul(&args[0], &args[1]), &args[2])?,
+
+        // --- Bases: devuelven el propio valor ---
+        "bin" | "oct" | "hex" | "dms" => x.clone(),
+        "deg" | "rad" | "grad" => {
+            let from = AngleUnit::parse(func).ok_or(JetError::Unsupported)?;
+            map(x, |c| from.convert(c, unit))
+        }
+
+        // --- Operadores ---
+        "+" => add(&args[0], x),
+        "-" => sub(&args[0], x),
+        "*" => mul(&args[0], x),
+        "/" => div(&args[0], x)?,
+        "%" | "mod" => {
+            let (a, b) = (re(&args[0]), re(x));
+            let q = (a[0].re / b[0].re).floor();
+            sub(&a, &scale(&b, q))
+        }
+
+        _ => return Err(JetError::Unsupported),
+    };
+    Ok(res)
+}
+
+/// Coeficientes de Cauchy c_k = (1/N)·Σ f(a + r·e^{iθm})·e^{-ikθm} / r^k.
+fn cauchy_coeffs(samples: &[Complex64], n: usize, r: f64) -> Vec<Complex64> {
+    let m = samples.len();
+    (0..=n)
+        .map(|k| {
+            let s: Complex64 = samples
+                .iter()
+                .enumerate()
+                .map(|(j, f)| f * Complex64::from_polar(1.0, -2.0 * std::f64::consts::PI * (k * j) as f64 / m as f64))
+                .sum();
+            s / (m as f64 * r.powi(k as i32))
+        })
+        .collect()
+}
+
+/// Coeficientes de Taylor en t = (x − a)/r, hasta grado n, del interpolante de Chebyshev de
+/// `samples` (tomadas en a + r·cos(π(m + ½)/N)), y el último coeficiente de Chebyshev por
+/// encima del redondeo (los de ruido se descartan: al pasar a potencias se amplifican).
+fn chebyshev_taylor(samples: &[f64], n: usize) -> (Vec<f64>, usize) {
+    let m = samples.len();
+    let max = samples.iter().fold(0.0f64, |acc, f| acc.max(f.abs()));
+    let mut c: Vec<f64> = (0..m)
+        .map(|j| {
+            let s: f64 = samples.iter().enumerate().map(|(i, f)| f * (std::f64::consts::PI * j as f64 * (i as f64 + 0.5) / m as f64).cos()).sum();
+            s * if j == 0 { 1.0 } else { 2.0 } / m as f64
+        })
+        .collect();
+    let last = c.iter().rposition(|cj| cj.abs() > 64.0 * f64::EPSILON * max).unwrap_or(0);
+    c.truncate(last + 1);
+    // Σ c_j·T_j(t) en potencias de t, con T_(j+1) = 2t·T_j − T_(j−1)
+    let mut out = vec![0.0; n + 1];
+    let (mut t_prev, mut t_cur): (Vec<f64>, Vec<f64>) = (Vec::new(), vec![1.0]);
+    for (j, cj) in c.iter().enumerate() {
+        for (o, v) in out.iter_mut().zip(&t_cur) {
+            *o += cj * v;
+        }
+        let mut next = vec![0.0; t_cur.len() + 1];
+        for (k, v) in t_cur.iter().enumerate() {
+            next[k + 1] += if j == 0 { 1.0 } else { 2.0 } * v;
+        }
+        for (k, v) in t_prev.iter().enumerate() {
+            next[k] -= v;
+        }
+        t_prev = std::mem::replace(&mut t_cur, next);
+    }
+    (out, last)
+}
+
+/// Redondea los coeficientes que están a menos de su error de un entero (o de 0).
+fn clean(v: f64, e: f64) -> f64 {
+    if (v - v.round()).abs() <= 10.0 * e { v.round() + 0.0 } else { v }
+}
+
+fn fmt_coeff(c: Complex64) -> String {
+    if c.im == 0.0 {
+        fmt_literal(c.re)
+    } else {
+        let sign = if c.im < 0.0 { "-" } else { "+" };
+        format!("({}{}{}*i)", fmt_literal(c.re), sign, fmt_literal(c.im.abs()))
+    }
+}
+
+impl TaylorSeries {
+    /// El polinomio como expresión evaluable, p. ej. `1 + x + 0.5*x^2`.
+    pub fn to_expr(&self, var: &str, a: f64) -> String {
+        let base = if a == 0.0 {
+            var.to_string()
+        } else if a < 0.0 {
+            format!("({}+{})", var, fmt_literal(-a))
+        } else {
+            format!("({}-{})", var, fmt_literal(a))
+        };
+        let mut out = String::new();
+        for (k, c) in self.coeffs.iter().enumerate() {
+            if *c == zero() {
+                continue;
+            }
+            let negative = c.im == 0.0 && c.re < 0.0;
+            let mag = if negative { -c } else { *c };
+            let power = match k {
+                0 => String::new(),
+                1 => base.clone(),
+                _ => format!("{}^{}", base, k),
+            };
+            let term = if k == 0 {
+                fmt_coeff(mag)
+            } else if mag == real(1.0) {
+                power
+            } else {
+                format!("{}*{}", fmt_coeff(mag), power)
+            };
+            if out.is_empty() {
+                out = if negative { format!("-{}", term) } else { term };
+            } else {
+                out.push_str(if negative { " - " } else { " + " });
+                out.push_str(&term);
+            }
+        }
+        if out.is_empty() { "0".to_string() } else { out }
+    }
+}
+
+impl Calculator {
+    /// Desarrollo de Taylor de orden `n` de `expr` alrededor de `var = a`.
+    /// Los coeficientes salen exactos con aritmética de series truncadas sobre la expresión
+    /// compilada; si alguna función no tiene regla, se calculan con la integral de Cauchy
+    /// sobre una circunferencia en el plano complejo o, si la expresión solo admite argumentos
+    /// reales, con un interpolante de Chebyshev en el eje real.
+    pub fn taylor(&mut self, expr: &str, var: &str, a: f64, n: usize) -> Result<TaylorSeries, String> {
+        if n > MAX_TAYLOR_ORDER {
+            return Err(format!("El orden máximo es {}.", MAX_TAYLOR_ORDER));
+        }
+        let rpn = self.compile(expr)?;
+        for guard in [0, 4, 8, 16] {
+            match self.run_jet(&rpn, var, real(a), n + 1 + guard) {
+                Ok(mut s) if s.len() > n => {
+                    s.truncate(n + 1);
+                    return Ok(TaylorSeries { errors: vec![0.0; n + 1], coeffs: s, exact: true });
+                }
+                Ok(_) | Err(JetError::Short) => continue,
+                Err(JetError::Unsupported) => return self.taylor_numeric(expr, var, a, n),
+                Err(JetError::Fail(e)) => return Err(e),
+            }
+        }
+        Err("No se pudo desarrollar: demasiadas cancelaciones en el punto.".to_string())
+    }
+
+    /// Ejecuta la RPN sobre series de `len` coeficientes con `var` = a + (x-a).
+    fn run_jet(&mut self, rpn: &[Rpn], var: &str, a: Complex64, len: usize) -> Result<Series, JetError> {
+        let mut values: Vec<Series> = Vec::new();
+        for item in rpn {
+            match item {
+                Rpn::Num(v) => values.push(constant(*v, len)),
+                Rpn::Int(n) => values.push(constant(real(*n as f64), len)),
+                Rpn::Var(name) => {
+                    let v = if let Some((_, v)) = self.scopes.iter().rev().find(|(s, _)| s == name) {
+                        v.val
+                    } else if name == var {
+                        let mut s = constant(a, len);
+                        if len > 1 {
+                            s[1] = real(1.0);
+                        }
+                        values.push(s);
+                        continue;
+                    } else if name == "last" || name == "ans" {
+                        self.last_result
+                    } else {
+                        *self.vars.get(name).ok_or(format!("Variable '{}' no existe", name))?
+                    };
+                    values.push(constant(v, len));
+                }
+                Rpn::Call(func, argc) => {
+                    if values.len() < *argc {
+                        return Err(JetError::Fail("Faltan argumentos".to_string()));
+                    }
+                    let args = values.split_off(values.len() - argc);
+                    if RANDOM_FUNCS.contains(&func.as_str()) {
+                        return Err(JetError::Fail(format!("'{}' es aleatoria: no tiene serie de Taylor", func)));
+                    }
+                    if args.is_empty() || self.funcs.contains_key(func) {
+                        return Err(JetError::Unsupported);
+                    }
+                    if args.iter().any(|s| s.is_empty()) {
+                        return Err(JetError::Short);
+                    }
+                    // 0/0 se resuelve con la propia serie; el resto toma el valor de apply_func
+                    let r0 = if func == "/" && args[1][0] == zero() {
+                        None
+                    } else {
+                        let mut vals: Vec<Complex64> = args.iter().map(|s| s[0]).collect();
+                        let args0 = vals.clone();
+                        apply_func(&mut vals, func, self.angle)?;
+                        if vals.len() != 1 {
+                            return Err(JetError::Fail(format!("Número de argumentos incorrecto para '{}'", func)));
+                        }
+                        Some(self.real_only(func, &args0, vals[0])?)
+                    };
+                    let mut s = jet(func, &args, self.angle)?;
+                    if s.is_empty() {
+                        return Err(JetError::Short);
+                    }
+                    if let Some(r0) = r0 {
+                        s[0] = r0;
+                    }
+                    if s.iter().any(|c| !c.re.is_finite() || !c.im.is_finite()) {
+                        return Err(JetError::Fail(format!("'{}' no es analítica en ese punto", func)));
+                    }
+                    values.push(s);
+                }
+                Rpn::Reduce(_) => return Err(JetError::Unsupported),
+            }
+        }
+        let res = values.pop().ok_or(JetError::Fail("Error en expresión".to_string()))?;
+        if !values.is_empty() {
+            return Err(JetError::Fail("Error en expresión: sobran operandos".to_string()));
+        }
+        Ok(res)
+    }
+
+    /// Coeficientes por la fórmula integral de Cauchy. Se prueban radios decrecientes y se
+    /// elige el de menor error estimado (aliasing con la mitad de puntos + redondeo).
+    fn taylor_numeric(&mut self, expr: &str, var: &str, a: f64, n: usize) -> Result<TaylorSeries, String> {
+        let scale = a.abs().max(1.0);
+        let mut best: Option<TaylorSeries> = None;
+        let mut best_score = f64::INFINITY;
+        let mut last_err = String::new();
+        for j in 0..8 {
+            let r = scale * 0.5f64.powi(j);
+            let samples: Result<Vec<Complex64>, String> = (0..CAUCHY_POINTS)
+                .map(|m| {
+                    let z = real(a) + Complex64::from_polar(r, 2.0 * std::f64::consts::PI * m as f64 / CAUCHY_POINTS as f64);
+                    self.eval_with(expr, &[(var, z)])
+                })
+                .collect();
+            let samples = match samples {
+                Ok(s) => s,
+                Err(e) => {
+                    last_err = e;
+                    continue;
+                }
+            };
+            let max = samples.iter().map(|f| f.norm()).fold(0.0, f64::max);
+            let fine = cauchy_coeffs(&samples, n, r);
+            let coarse = cauchy_coeffs(&samples.iter().step_by(2).copied().collect::<Vec<_>>(), n, r);
+            let errors: Vec<f64> = (0..=n)
+                .map(|k| (fine[k] - coarse[k]).norm() + 4.0 * f64::EPSILON * max / r.powi(k as i32))
+                .collect();
+            let score = errors.iter().copied().fold(0.0, f64::max);
+            if score < best_score {
+                best_score = score;
+                let coeffs = fine.iter().zip(&errors).map(|(c, e)| Complex64::new(clean(c.re, *e), clean(c.im, *e))).collect();
+                best = Some(TaylorSeries { coeffs, errors, exact: false });
+            }
+        }
+        match best {
+            Some(b) => Ok(b),
+            None => self.taylor_real(expr, var, a, n).map_err(|_| format!("No se pudo evaluar alrededor del punto: {}", last_err)),
+        }
+    }
+
+    /// Coeficientes a partir de muestras en el eje real (erf, Bessel, distribuciones... no
+    /// admiten puntos complejos): interpolante de Chebyshev en [a − r, a + r] pasado a
+    /// potencias, con radios decrecientes y el error estimado con la mitad de nodos.
+    fn taylor_real(&mut self, expr: &str, var: &str, a: f64, n: usize) -> Result<TaylorSeries, String> {
+        let scale = a.abs().max(1.0);
+        let mut best: Option<TaylorSeries> = None;
+        let mut best_score = f64::INFINITY;
+        let mut last_err = String::new();
+        for j in 0..8 {
+            let r = scale * 0.5f64.powi(j);
+            let mut fit = |points: usize| -> Result<(Vec<f64>, usize, f64), String> {
+                let samples = (0..points)
+                    .map(|m| {
+                        let x = a + r * (std::f64::consts::PI * (m as f64 + 0.5) / points as f64).cos();
+                        self.eval_with(expr, &[(var, real(x))]).map(|f| f.re)
+                    })
+                    .collect::<Result<Vec<f64>, String>>()?;
+                let max = samples.iter().fold(0.0f64, |acc, f| acc.max(f.abs()));
+                let (coeffs, last) = chebyshev_taylor(&samples, n);
+                Ok((coeffs, last, max))
+            };
+            let ((fine, last, max), (coarse, _, _)) = match fit(CHEB_POINTS).and_then(|f| Ok((f, fit(CHEB_POINTS / 2)?))) {
+                Ok(v) => v,
+                Err(e) => {
+                    last_err = e;
+                    continue;
+                }
+            };
+            // Diferencia con la mitad de nodos + redondeo amplificado al pasar a potencias
+            let growth = (1.0 + std::f64::consts::SQRT_2).powi(last as i32);
+            let errors: Vec<f64> = (0..=n)
+                .map(|k| ((fine[k] - coarse[k]).abs() + 4.0 * f64::EPSILON * max * growth) / r.powi(k as i32))
+                .collect();
+            let score = errors.iter().copied().fold(0.0, f64::max);
+            if score < best_score {
+                best_score = score;
+                let coeffs = fine.iter().enumerate().zip(&errors).map(|((k, c), e)| real(clean(c / r.powi(k as i32), *e))).collect();
+                best = Some(TaylorSeries { coeffs, errors, exact: false });
+            }
+        }
+        best.ok_or_else(|| format!("No se pudo evaluar alrededor del punto: {}", last_err))
+    }
+}
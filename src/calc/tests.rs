@@ -1,7 +1,10 @@
-use super::Calculator;
+use super::{AngleUnit, Calculator};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use num_complex::Complex64;
 
-fn approx(a: f64, b: f64, eps: f64) -> bool {
-    (a - b).abs() <= eps
+fn approx(a: Complex64, b: f64, eps: f64) -> bool {
+    (a.re - b).abs() <= eps && a.im.abs() <= eps
 }
 
 #[test]
@@ -14,7 +17,8 @@ fn scientific_and_invalid_numbers() {
 
 #[test]
 fn atan2_hypot_root() {
-    let mut c = Calculator::new(); // DEG
+    let mut c = Calculator::new();
+    c.angle = AngleUnit::Deg;
     assert!(approx(c.evaluate("hypot(3,4)").unwrap(), 5.0, 1e-12));
     assert!(approx(c.evaluate("atan2(1,1)").unwrap(), 45.0, 1e-9));
     assert!(approx(c.evaluate("root(3,8)").unwrap(), 2.0, 1e-9));
@@ -37,3 +41,1045 @@ fn combinatorics() {
     assert!(approx(c.evaluate("perm(5,2)").unwrap(), 20.0, 1e-12));
 }
 
+
+#[test]
+fn higher_order_derivatives() {
+    let mut c = Calculator::new();
+    let d1 = c.derivative("sin(x)", "x", 1.0, 1, None).unwrap();
+    assert!(approx(d1.value, 1.0f64.cos(), 1e-10));
+    assert!(d1.error < 1e-8);
+    let d2 = c.derivative("x^3", "x", 2.0, 2, None).unwrap();
+    assert!(approx(d2.value, 12.0, 1e-8));
+    let d3 = c.derivative("exp(x)", "x", 0.5, 3, None).unwrap();
+    assert!(approx(d3.value, 0.5f64.exp(), 1e-6));
+    let d4 = c.derivative("x^4", "x", 1.0, 4, None).unwrap();
+    assert!(approx(d4.value, 24.0, 1e-5));
+    assert!(c.derivative("x", "x", 1.0, 5, None).is_err());
+    // Estadística y parámetros de distribuciones por diferenciación automática, no por diferencias
+    let dv = c.derivative("var(x, 1, 2)", "x", 1.0, 1, None).unwrap();
+    assert!(dv.exact && approx(dv.value, -1.0 / 3.0, 1e-15));
+    let dm = c.derivative("normcdf(1, x, 1)", "x", 0.0, 1, None).unwrap();
+    assert!(dm.exact && approx(dm.value, -0.241_970_724_519_143_35, 1e-15));
+    // x no queda definida tras derivar
+    assert!(!c.vars.contains_key("x"));
+    // Evaluar con variables temporales no altera el último resultado ni cómo se muestra
+    let x = [("x", Complex64::new(2.0, 0.0))];
+    for e in ["hex(255)", "fact(30)", "(2±0.1)*3", "dms(1.5)"] {
+        let r = c.evaluate(e).unwrap();
+        let shown = c.format_result(r);
+        c.eval_with("x^2", &x).unwrap();
+        c.eval_with("fact(25) + (1±0.5)*x", &x).unwrap();
+        assert_eq!(c.format_result(c.last_result), shown, "{}", e);
+    }
+}
+
+#[test]
+fn deriv_order_prefix() {
+    use super::calculus::split_deriv_order;
+    assert_eq!(split_deriv_order("2: x^3 2").unwrap(), (2, "x^3 2"));
+    assert_eq!(split_deriv_order("3:sin(x) 0").unwrap(), (3, "sin(x) 0"));
+    // Sin ':' un número inicial es parte de la expresión, no el orden
+    assert_eq!(split_deriv_order("2*x^2 3").unwrap(), (1, "2*x^2 3"));
+    assert_eq!(split_deriv_order("2 3").unwrap(), (1, "2 3"));
+    assert!(split_deriv_order("5: x 1").is_err());
+}
+
+#[test]
+fn gradient_and_hessian() {
+    let mut c = Calculator::new();
+    let p = [("x", 1.0), ("y", 2.0)];
+    let g = c.gradient("x^2*y+y^3", &p, None).unwrap();
+    assert!(approx(g[0].value, 4.0, 1e-8));
+    assert!(approx(g[1].value, 13.0, 1e-8));
+    let h = c.hessian("x^2*y+y^3", &p, None).unwrap();
+    assert!(approx(h[0][0].value, 4.0, 1e-6));
+    assert!(approx(h[0][1].value, 2.0, 1e-6));
+    assert!(approx(h[1][0].value, 2.0, 1e-6));
+    assert!(approx(h[1][1].value, 12.0, 1e-6));
+}
+
+#[test]
+fn dual_numbers_exact_derivatives() {
+    let mut c = Calculator::new();
+    let d = |c: &mut Calculator, expr: &str, x: f64| c.evaluate_dual(expr, &[("x", Complex64::new(x, 0.0))]).unwrap().d(0);
+    assert!(approx(d(&mut c, "sin(x)*exp(x)", 0.7), 0.7f64.exp() * (0.7f64.sin() + 0.7f64.cos()), 1e-15));
+    assert!(approx(d(&mut c, "x^x", 2.0), 4.0 * (2.0f64.ln() + 1.0), 1e-14));
+    assert!(approx(d(&mut c, "sqrt(1+x^2)", 3.0), 3.0 / 10.0f64.sqrt(), 1e-15));
+    assert!(approx(d(&mut c, "log(2, x)", 8.0), 1.0 / (8.0 * 2.0f64.ln()), 1e-15));
+    assert!(approx(d(&mut c, "atan2(x, 1)", 1.0), 0.5, 1e-15));
+    assert!(approx(d(&mut c, "floor(x)+x", 1.5), 1.0, 0.0));
+    // Complejo: d/dx (i·x)^2 = -2x
+    assert!(approx(d(&mut c, "(i*x)^2", 3.0), -6.0, 1e-12));
+    // DEG: d/dx sin(x°) = cos(x°)·π/180
+    c.angle = AngleUnit::Deg;
+    assert!(approx(d(&mut c, "sin(x)", 60.0), 0.5 * std::f64::consts::PI / 180.0, 1e-15));
+    c.angle = AngleUnit::Rad;
+    // Primera derivada exacta en `deriv`
+    let dv = c.derivative("x^3", "x", 2.0, 1, None).unwrap();
+    assert!(dv.exact && dv.value == Complex64::new(12.0, 0.0));
+    assert!(c.evaluate_dual("abs(x)", &[("x", Complex64::new(0.0, 0.0))]).is_err());
+}
+
+#[test]
+fn rpn_argument_checks() {
+    let mut c = Calculator::new();
+    assert!(c.evaluate("(1+2").is_err());
+    assert!(c.evaluate("1+2)").is_err());
+    assert!(c.evaluate("atan2(1)").is_err());
+    assert!(c.evaluate("sqrt(4, 9)").is_err());
+    assert!(approx(c.evaluate("-sin(pi/2)^2").unwrap(), -1.0, 1e-12));
+    assert!(approx(c.evaluate("2^3^2").unwrap(), 512.0, 1e-9));
+}
+
+#[test]
+fn ode_dormand_prince() {
+    let mut c = Calculator::new();
+    c.vars.insert("k".to_string(), Complex64::new(2.0, 0.0));
+    let spec = c.parse_ode("y' = -k*y, y(0)=1, t=0..1").unwrap();
+    let sol = c.solve_ode(&spec).unwrap();
+    let (t_end, y_end) = sol.table.last().unwrap();
+    assert!(approx(Complex64::new(*t_end, 0.0), 1.0, 1e-12));
+    assert!((y_end[0] - (-2.0f64).exp()).abs() < 1e-6);
+
+    // Sistema: oscilador armónico, una vuelta completa
+    let spec = c.parse_ode("x' = y, y' = -x, x(0)=1, y(0)=0, t=0..2*pi, rtol=1e-10, atol=1e-12").unwrap();
+    let sol = c.solve_ode(&spec).unwrap();
+    let (_, s) = sol.table.last().unwrap();
+    assert!((s[0] - 1.0).abs() < 1e-8 && s[1].abs() < 1e-8);
+    assert_eq!(sol.table.len(), 11);
+
+    assert!(c.parse_ode("y' = -y, t=0..1").is_err());
+    assert!(c.parse_ode("y' = -y, y(1)=1, t=0..1").is_err());
+    assert!(!c.vars.contains_key("t") && !c.vars.contains_key("y"));
+
+    // El estado final pasa a las variables, pero nunca a una constante
+    let spec = c.parse_ode("y' = -k*y, y(0)=1, t=0..1").unwrap();
+    c.ode(&spec).unwrap();
+    assert!((c.vars["y"].re - (-2.0f64).exp()).abs() < 1e-6);
+    assert!(c.parse_ode("e' = -e, e(0)=1, t=0..1").unwrap_err().contains("reservada"));
+    assert!(approx(c.evaluate("e").unwrap(), std::f64::consts::E, 0.0));
+}
+
+#[test]
+fn sum_and_prod() {
+    let mut c = Calculator::new();
+    c.vars.insert("k".to_string(), Complex64::new(7.0, 0.0));
+    assert!(approx(c.evaluate("sum(k, 1, 100, k^2)").unwrap(), 338350.0, 1e-6));
+    // El índice es local: la variable del usuario no cambia
+    assert!(approx(c.evaluate("k").unwrap(), 7.0, 0.0));
+    assert!(approx(c.evaluate("prod(k, 2, 10, 1 - 1/k^2)").unwrap(), 0.55, 1e-12));
+    assert!(approx(c.evaluate("sum(j, 1, 3, sum(k, 1, j, k))").unwrap(), 10.0, 0.0));
+    assert!(approx(c.evaluate("sum(k, 1, 0, k)").unwrap(), 0.0, 0.0));
+    assert!(approx(c.evaluate("prod(k, 1, 0, k)").unwrap(), 1.0, 0.0));
+    // Infinitas: alternada (Wynn), monótona lenta y producto (Richardson), rápida
+    let pi = std::f64::consts::PI;
+    assert!(approx(c.evaluate("4*sum(k, 0, inf, (-1)^k/(2*k+1))").unwrap(), pi, 1e-12));
+    assert!(approx(c.evaluate("sum(k, 1, inf, 1/k^2)").unwrap(), pi * pi / 6.0, 1e-12));
+    assert!(approx(c.evaluate("prod(k, 2, inf, 1 - 1/k^2)").unwrap(), 0.5, 1e-12));
+    assert!(approx(c.evaluate("sum(k, 0, inf, 1/fact(k))").unwrap(), std::f64::consts::E, 1e-14));
+    // Colas n^(−q) no enteras: ζ(1.5) y ζ(1.1)
+    assert!(approx(c.evaluate("sum(k, 1, inf, 1/k^1.5)").unwrap(), 2.612_375_348_685_488, 1e-12));
+    assert!(approx(c.evaluate("sum(k, 1, inf, 1/k^1.1)").unwrap(), 10.584_448_464_950_81, 1e-11));
+    // Sin convergencia demostrada no se afirma divergencia
+    assert!(c.evaluate("sum(k, 1, inf, 1/k)").unwrap_err().contains("no se alcanzó la convergencia"));
+    // Acelerar no convierte en suma una serie cuyos términos no tienden a 0
+    for e in ["sum(k, 1, inf, (-1)^k)", "sum(k, 1, inf, (-1)^k*(1 + 1/k))", "prod(k, 1, inf, -1)"] {
+        assert!(c.evaluate(e).unwrap_err().contains("no converge"), "{}", e);
+    }
+    // ... pero sí una alternada de términos que decrecen muy despacio
+    assert!(approx(c.evaluate("sum(k, 2, inf, (-1)^k/ln(k))").unwrap(), 0.924_299_897_222_938_9, 1e-12));
+    assert!(c.evaluate("sum(2, 1, 3, k)").is_err());
+    assert!(c.evaluate("sum(k, 1, 2.5, k)").is_err());
+    // Derivada exacta a través de la serie
+    let d = c.evaluate_dual("sum(k, 0, inf, x^k/fact(k))", &[("x", Complex64::new(1.0, 0.0))]).unwrap();
+    assert!(approx(d.d(0), std::f64::consts::E, 1e-14));
+}
+
+#[test]
+fn limits() {
+    use super::calculus::{Approach, LimitValue};
+    let mut c = Calculator::new();
+    let finite = |l: LimitValue| match l {
+        LimitValue::Finite { value, .. } => value,
+        _ => panic!("límite no finito: {}", l),
+    };
+    assert!(approx(finite(c.limit("sin(x)/x", "x", 0.0, Approach::Both).unwrap()), 1.0, 1e-12));
+    assert!(approx(finite(c.limit("(1-cos(x))/x^2", "x", 0.0, Approach::Both).unwrap()), 0.5, 1e-9));
+    assert!(approx(finite(c.limit("(1+1/x)^x", "x", f64::INFINITY, Approach::Both).unwrap()), std::f64::consts::E, 1e-10));
+    assert!(approx(finite(c.limit("sqrt(x)", "x", 0.0, Approach::Right).unwrap()), 0.0, 1e-10));
+    assert!(approx(finite(c.limit("x*ln(x)", "x", 0.0, Approach::Right).unwrap()), 0.0, 1e-9));
+    assert!(approx(finite(c.limit("atan(x)", "x", f64::NEG_INFINITY, Approach::Both).unwrap()), -std::f64::consts::FRAC_PI_2, 1e-12));
+    assert_eq!(c.limit("1/x", "x", 0.0, Approach::Right).unwrap(), LimitValue::PosInf);
+    assert_eq!(c.limit("1/x", "x", 0.0, Approach::Left).unwrap(), LimitValue::NegInf);
+    assert_eq!(c.limit("1/x^2", "x", 0.0, Approach::Both).unwrap(), LimitValue::PosInf);
+    assert_eq!(c.limit("ln(x)", "x", 0.0, Approach::Right).unwrap(), LimitValue::NegInf);
+    // Las muestras desbordan antes de tener cola: se afina hasta el desbordamiento
+    assert_eq!(c.limit("exp(x)", "x", f64::INFINITY, Approach::Both).unwrap(), LimitValue::PosInf);
+    assert_eq!(c.limit("exp(-x)", "x", f64::NEG_INFINITY, Approach::Both).unwrap(), LimitValue::PosInf);
+    assert_eq!(c.limit("-exp(x^2)", "x", f64::INFINITY, Approach::Both).unwrap(), LimitValue::NegInf);
+    assert!(c.limit("1/x", "x", 0.0, Approach::Both).is_err());
+    assert!(c.limit("sin(1/x)", "x", 0.0, Approach::Right).is_err());
+    assert!(!c.vars.contains_key("x"));
+}
+
+#[test]
+fn taylor_series() {
+    let mut c = Calculator::new();
+    let close = |got: &[Complex64], want: &[f64]| got.len() == want.len() && got.iter().zip(want).all(|(g, w)| approx(*g, *w, 1e-12));
+
+    let t = c.taylor("exp(x)", "x", 0.0, 4).unwrap();
+    assert!(t.exact && close(&t.coeffs, &[1.0, 1.0, 0.5, 1.0 / 6.0, 1.0 / 24.0]));
+    assert_eq!(t.to_expr("x", 0.0), "1 + x + 0.5*x^2 + 0.16666666666666666*x^3 + 0.041666666666666664*x^4");
+    // 0/0 en el punto: se simplifica el factor común
+    let t = c.taylor("sin(x)/x", "x", 0.0, 4).unwrap();
+    assert!(close(&t.coeffs, &[1.0, 0.0, -1.0 / 6.0, 0.0, 1.0 / 120.0]));
+    let t = c.taylor("ln(x)", "x", 1.0, 3).unwrap();
+    assert!(close(&t.coeffs, &[0.0, 1.0, -0.5, 1.0 / 3.0]));
+    assert_eq!(t.to_expr("x", 1.0), "(x-1) - 0.5*(x-1)^2 + 0.3333333333333333*(x-1)^3");
+    let t = c.taylor("x^x", "x", 1.0, 3).unwrap();
+    assert!(close(&t.coeffs, &[1.0, 1.0, 1.0, 0.5]));
+    let t = c.taylor("tan(x)", "x", 0.0, 5).unwrap();
+    assert!(close(&t.coeffs, &[0.0, 1.0, 0.0, 1.0 / 3.0, 0.0, 2.0 / 15.0]));
+    // El polinomio impreso se puede evaluar
+    let t = c.taylor("sqrt(x)", "x", 4.0, 3).unwrap();
+    let poly = t.to_expr("x", 4.0);
+    assert!(approx(c.eval_with(&poly, &[("x", Complex64::new(4.1, 0.0))]).unwrap(), 4.1f64.sqrt(), 1e-7));
+    // Sin regla de series (sum): integral de Cauchy
+    let t = c.taylor("sum(k, 1, 3, x^k)", "x", 0.0, 4).unwrap();
+    assert!(!t.exact);
+    assert!(t.coeffs.iter().zip([0.0, 1.0, 1.0, 1.0, 0.0]).all(|(g, w)| approx(*g, w, 1e-10)));
+    // Funciones de argumento real: erf con reglas de series, el resto en el eje real
+    let k = std::f64::consts::FRAC_2_SQRT_PI;
+    let t = c.taylor("erf(x)", "x", 0.0, 5).unwrap();
+    assert!(t.exact && close(&t.coeffs, &[0.0, k, 0.0, -k / 3.0, 0.0, k / 10.0]));
+    let t = c.taylor("erfinv(x)", "x", 0.0, 3).unwrap();
+    assert!(t.exact && close(&t.coeffs, &[0.0, 1.0 / k, 0.0, std::f64::consts::PI / 12.0 / k]));
+    let t = c.taylor("normcdf(x)", "x", 0.0, 3).unwrap();
+    let phi0 = 1.0 / (2.0 * std::f64::consts::PI).sqrt();
+    assert!(!t.exact);
+    for (g, w) in t.coeffs.iter().zip([0.5, phi0, 0.0, -phi0 / 6.0]) {
+        assert!(approx(*g, w, 1e-9), "{} vs {}", g, w);
+    }
+    assert!(c.taylor("sqrt(x)", "x", 0.0, 3).is_err());
+    assert!(c.taylor("1/x", "x", 0.0, 3).is_err());
+}
+
+#[test]
+fn descriptive_statistics() {
+    let mut c = Calculator::new();
+    for v in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+        c.memory_stack.push(Complex64::new(v, 0.0));
+    }
+    let st = c.stack_summary().unwrap();
+    assert_eq!(st.count, 8);
+    assert_eq!((st.mean, st.median, st.var_pop, st.std_pop), (5.0, 4.5, 4.0, 2.0));
+    assert_eq!(st.modes, vec![4.0]);
+    assert_eq!((st.q1, st.q3, st.iqr), (4.0, 5.5, 1.5));
+    assert!((st.var_sample.unwrap() - 32.0 / 7.0).abs() < 1e-14);
+    // Valores de referencia de SKEW y KURT de Excel
+    assert!((st.skewness.unwrap() - 0.818_487_553_356_8).abs() < 1e-12);
+    assert!((st.kurtosis.unwrap() - 0.940_625).abs() < 1e-12);
+    assert!((st.geometric_mean.unwrap() - 4.603_215_596_046_737).abs() < 1e-12);
+    assert!((st.harmonic_mean.unwrap() - 4.201_750_729_470_613).abs() < 1e-12);
+    assert_eq!(c.stack_stat("q1").unwrap(), 4.0);
+    assert_eq!(c.stack_stat("std").unwrap(), st.std_sample.unwrap());
+
+    // Las mismas funciones, variádicas en expresiones
+    assert!(approx(c.evaluate("median(3, 1, 2, 10)").unwrap(), 2.5, 0.0));
+    assert!(approx(c.evaluate("mean(1, 2, 6) + count(7, 7)").unwrap(), 5.0, 0.0));
+    assert!(approx(c.evaluate("quartile(1, 1, 2, 3, 4)").unwrap(), 1.75, 1e-15));
+    // Sin paréntesis son variables: no ocultan las del usuario
+    c.vars.insert("mean".to_string(), Complex64::new(3.0, 0.0));
+    c.vars.insert("var".to_string(), Complex64::new(0.5, 0.0));
+    assert!(approx(c.evaluate("mean*2 + var").unwrap(), 6.5, 0.0));
+    assert!(approx(c.evaluate("mean(mean, 5)").unwrap(), 4.0, 0.0));
+    assert!(approx(c.evaluate("moda(1, 3, 3, 1, 2)").unwrap(), 1.0, 0.0));
+    assert!(c.evaluate("moda(1, 2, 3)").is_err());
+    assert!(c.evaluate("std(5)").is_err());
+    assert!(c.evaluate("gmean(1, -1)").is_err());
+    assert!(c.evaluate("mean(1, i)").is_err());
+
+    // Sin datos, o datos complejos en la pila
+    c.memory_stack.clear();
+    assert!(c.stack_summary().is_err());
+    c.memory_stack.push(Complex64::new(1.0, 2.0));
+    assert!(c.stack_stat("mean").is_err());
+}
+
+#[test]
+fn curve_fitting() {
+    use super::fit::FitModel;
+    let mut c = Calculator::new();
+    c.pairs = vec![(1.0, 2.1), (2.0, 3.9), (3.0, 6.2), (4.0, 7.8), (5.0, 10.1)];
+    let r = c.fit(FitModel::Linear).unwrap();
+    assert!((r.params[0] - 0.05).abs() < 1e-12 && (r.params[1] - 1.99).abs() < 1e-12);
+    // Errores típicos: s² = SSR/(n-2) = 0.107/3
+    let s2: f64 = 0.107 / 3.0;
+    assert!((r.std_errors[1] - (s2 / 10.0).sqrt()).abs() < 1e-12);
+    assert!((r.std_errors[0] - (s2 * 1.1).sqrt()).abs() < 1e-12);
+    assert!((r.r2 - (1.0 - 0.107 / 39.708)).abs() < 1e-12);
+    assert!((r.residuals[1] + 0.13).abs() < 1e-12);
+
+    // El modelo queda como función llamable (y derivable)
+    c.define_func("fit", &["x"], &r.expr("x")).unwrap();
+    assert!(approx(c.evaluate("fit(6)").unwrap(), 11.99, 1e-12));
+    assert!(approx(c.derivative("fit(x)^2", "x", 1.0, 1, None).unwrap().value, 2.0 * 2.04 * 1.99, 1e-12));
+    assert!(c.evaluate("fit(1, 2)").is_err());
+    assert!(c.define_func("sin", &["x"], "x").is_err());
+    assert!(c.define_func("pi", &["x"], "x").is_err());
+    // Mismos nombres que las variables, con '_'
+    c.define_func("f_1", &["x_0"], "2*x_0").unwrap();
+    c.vars.insert("a_b".to_string(), Complex64::new(3.0, 0.0));
+    assert!(approx(c.evaluate("f_1(a_b)").unwrap(), 6.0, 0.0));
+    assert!(c.define_func("_f", &["x"], "x").is_err());
+
+    // Datos exactos: se recuperan los parámetros
+    c.pairs = (0..6).map(|k| { let x = k as f64 * 0.5; (x, 1.0 - 2.0 * x + 0.5 * x * x) }).collect();
+    let r = c.fit(FitModel::Poly(2)).unwrap();
+    assert!(r.params.iter().zip([1.0, -2.0, 0.5]).all(|(p, w)| (p - w).abs() < 1e-12));
+    assert_eq!(r.expr("x"), format!("{} - {}*x + {}*x^2", r.params[0], -r.params[1], r.params[2]));
+    c.pairs = (1..8).map(|k| { let x = k as f64; (x, 3.0 * (-0.4 * x).exp()) }).collect();
+    let r = c.fit(FitModel::Exp).unwrap();
+    assert!((r.params[0] - 3.0).abs() < 1e-9 && (r.params[1] + 0.4).abs() < 1e-9);
+    c.pairs = (1..8).map(|k| { let x = k as f64; (x, 2.5 * x.powf(1.5)) }).collect();
+    let r = c.fit(FitModel::Power).unwrap();
+    assert!((r.params[0] - 2.5).abs() < 1e-9 && (r.params[1] - 1.5).abs() < 1e-9);
+    c.pairs = (1..8).map(|k| { let x = k as f64; (x, 4.0 - 3.0 * x.ln()) }).collect();
+    let r = c.fit(FitModel::Log).unwrap();
+    assert!((r.params[0] - 4.0).abs() < 1e-12 && (r.params[1] + 3.0).abs() < 1e-12);
+
+    // Datos insuficientes o fuera de dominio
+    c.pairs = vec![(1.0, 1.0), (1.0, 2.0), (1.0, 3.0)];
+    assert!(c.fit(FitModel::Linear).is_err());
+    c.pairs = vec![(-1.0, 1.0), (2.0, 2.0)];
+    assert!(c.fit(FitModel::Log).is_err());
+    assert!(c.fit(FitModel::Poly(2)).is_err());
+}
+
+#[test]
+fn probability_distributions() {
+    use super::distributions::eval_dist;
+    let rel = |f: &str, args: &[f64], want: f64| {
+        let got = eval_dist(f, args).unwrap();
+        assert!(((got - want) / want).abs() < 1e-12, "{}{:?} = {:e}, se esperaba {:e}", f, args, got, want);
+    };
+    // Referencias con mpmath a 40 dígitos, incluidas colas profundas
+    rel("normcdf", &[-10.0], 7.619_853_024_160_526e-24);
+    rel("normcdf", &[30.0, 1.0, 2.0], 1.0);
+    rel("normpdf", &[3.0, 1.0, 2.0], 0.120_985_362_259_571_67);
+    rel("norminv", &[0.975], 1.959_963_984_540_054);
+    rel("norminv", &[1e-300], -37.047_096_299_361_2);
+    rel("tcdf", &[-3.0, 10.0], 6.671_827_511_284_789e-3);
+    rel("tcdf", &[-50.0, 3.5], 2.009_542_223_849_108e-6);
+    rel("tcdf", &[-8.0, 1e5], 6.286_959_938_128_186e-16);
+    rel("tpdf", &[0.2, 1e7], 0.391_042_683_432_945);
+    rel("tinv", &[0.975, 10.0], 2.228_138_851_986_275);
+    // Cola extrema: el cuantil se pule con Halley hasta pocos ulp
+    let tail = |args: &[f64], want: f64| {
+        let got = eval_dist("tinv", args).unwrap();
+        assert!(((got - want) / want).abs() < 1e-14, "tinv{:?} = {:e}, se esperaba {:e}", args, got, want);
+    };
+    tail(&[1e-12, 5.0], -393.956_959_577_603_77);
+    tail(&[1e-12, 100.0], -8.025_825_594_493_253);
+    tail(&[1e-12, 1000.0], -7.124_228_925_314_409);
+    tail(&[1e-12, 2.5], -55_306.174_076_515_82);
+    tail(&[1e-15, 7.0], -279.127_999_279_28);
+    rel("chi2cdf", &[1200.0, 1000.0], 0.999_987_744_057_669_3);
+    rel("chi2pdf", &[100.0, 1000.0], 2.414_568_563_592_489e-306);
+    rel("chi2inv", &[0.95, 3.0], 7.814_727_903_251_18);
+    rel("fcdf", &[2.0, 5.0, 2.0], 0.633_938_145_260_609);
+    rel("finv", &[0.95, 2.0, 10.0], 4.102_821_015_130_401);
+    rel("expcdf", &[1e-10, 2.0], 1.999_999_999_8e-10);
+    rel("binompmf", &[49800.0, 100000.0, 0.5], 1.133_720_339_298_455_6e-3);
+    rel("binomcdf", &[3.0, 1000.0, 0.01], 1.007_265_477_201_438e-2);
+    rel("poisspmf", &[999000.0, 1e6], 2.420_514_150_506_49e-4);
+    rel("poisscdf", &[1000.0, 300.0], 1.0);
+    rel("poisscdf", &[1.0, 300.0], 1.549_608_266_946_016_1e-128);
+    rel("geomcdf", &[10.0, 0.2], 0.892_625_817_6);
+    rel("hygepmf", &[60.0, 1000.0, 300.0, 200.0], 0.068_710_903_098_831_71);
+    rel("hygecdf", &[3.0, 50.0, 10.0, 5.0], 0.995_916_479_450_244_5);
+
+    // Cuantiles discretos: el menor k con P(X <= k) >= p
+    assert_eq!(eval_dist("binominv", &[0.5, 20.0, 0.3]).unwrap(), 6.0);
+    assert_eq!(eval_dist("poissinv", &[0.99, 4.5]).unwrap(), 10.0);
+    assert_eq!(eval_dist("geominv", &[0.9, 0.2]).unwrap(), 11.0);
+    assert_eq!(eval_dist("binominv", &[eval_dist("binomcdf", &[6.0, 20.0, 0.3]).unwrap(), 20.0, 0.3]).unwrap(), 6.0);
+
+    // Desde expresiones, con derivada respecto a x y validación de parámetros
+    let mut c = Calculator::new();
+    assert!(approx(c.evaluate("binompmf(3, 10, 0.5)").unwrap(), 0.117_187_5, 1e-15));
+    assert!(approx(c.evaluate("norminv(normcdf(1.3))").unwrap(), 1.3, 1e-14));
+    assert!(c.evaluate("normcdf(1, 0, -1)").is_err());
+    assert!(c.evaluate("tinv(1.5, 3)").is_err());
+    assert!(c.evaluate("binompmf(1, 10)").is_err());
+    assert!(c.evaluate("normcdf(i)").is_err());
+}
+
+#[test]
+fn seeded_random_numbers() {
+    let mut c = Calculator::new();
+    c.set_seed(42);
+    // xoshiro256** sembrado con splitmix64: valores fijos en cualquier plataforma
+    assert_eq!(c.evaluate("rand()").unwrap().re, 0.083_862_971_059_882_16);
+    assert_eq!(c.evaluate("rand()").unwrap().re, 0.378_980_250_662_668_6);
+
+    let draw = |c: &mut Calculator| -> Vec<f64> {
+        ["rand(5, 6)", "randint(1, 6)", "randn()", "choice(10, 20, 30)", "normrnd(3, 2)", "binomrnd(10, 0.3)", "poissrnd(4)", "hygernd(50, 10, 5)"]
+            .iter()
+            .map(|e| c.evaluate(e).unwrap().re)
+            .collect()
+    };
+    c.set_seed(7);
+    let a = draw(&mut c);
+    c.set_seed(7);
+    assert_eq!(draw(&mut c), a);
+    assert_eq!(c.rng.seed(), 7);
+    assert!((5.0..6.0).contains(&a[0]));
+    assert!([10.0, 20.0, 30.0].contains(&a[3]));
+    assert!(a[5..].iter().all(|k| k.fract() == 0.0 && *k >= 0.0));
+
+    // randint cubre el rango completo y solo él
+    let mut seen = [false; 6];
+    for _ in 0..200 {
+        let k = c.evaluate("randint(1, 6)").unwrap().re;
+        assert!((1.0..=6.0).contains(&k) && k.fract() == 0.0);
+        seen[k as usize - 1] = true;
+    }
+    assert!(seen.iter().all(|s| *s));
+
+    // Momentos de la normal con 20000 muestras (tolerancia de ~5 errores típicos)
+    let xs: Vec<f64> = (0..20000).map(|_| c.evaluate("randn(10, 2)").unwrap().re).collect();
+    let m = xs.iter().sum::<f64>() / xs.len() as f64;
+    let v = xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() - 1) as f64;
+    assert!((m - 10.0).abs() < 0.07 && (v - 4.0).abs() < 0.2, "media {} varianza {}", m, v);
+
+    assert!(c.evaluate("randint(3, 1)").is_err());
+    assert!(c.evaluate("randint(1.5, 3)").is_err());
+    assert!(c.evaluate("normrnd(0, -1)").is_err());
+    assert!(c.evaluate("choice()").is_err());
+    assert!(c.taylor("rand() + x", "x", 0.0, 3).is_err());
+}
+
+
+#[test]
+fn special_functions() {
+    let mut c = Calculator::new();
+    let mut rel = |expr: &str, re: f64, im: f64| {
+        let got = c.evaluate(expr).unwrap();
+        let want = Complex64::new(re, im);
+        assert!((got - want).norm() <= 1e-13 * want.norm(), "{} = {}, se esperaba {}", expr, got, want);
+    };
+    // Referencias con mpmath a 30 dígitos
+    rel("gamma(0.5)^2", std::f64::consts::PI, 0.0);
+    rel("gamma(170.5)", 5.562_092_414_56e305, 0.0);
+    rel("gamma(-2.7)", -0.931_082_784_838_963_9, 0.0);
+    rel("gamma(0.3 - 2*i)", 0.057_465_337_569_588_03, 0.074_984_912_582_646_14);
+    rel("lgamma(1000)", 5_905.220_423_209_181, 0.0);
+    rel("lgamma(-3.5 + 0.7*i)", -2.766_560_633_983_366, -11.590_672_776_543_29);
+    rel("digamma(1)", -0.577_215_664_901_532_9, 0.0);
+    rel("digamma(1 + i)", 0.094_650_320_622_476_98, 1.076_674_047_468_581);
+    rel("beta(2, 3)", 1.0 / 12.0, 0.0);
+    rel("beta(-0.5, 2)", -4.0, 0.0);
+    rel("fact(0.5)", 0.886_226_925_452_758, 0.0);
+    rel("erf(1)", 0.842_700_792_949_714_9, 0.0);
+    rel("erfc(5)", 1.537_459_794_428_034_8e-12, 0.0);
+    rel("erfinv(0.5)", 0.476_936_276_204_469_9, 0.0);
+    rel("erfinv(1 - 1e-12)", 5.042_031_898_572_696, 0.0); // argumento redondeado a f64
+    rel("zeta(2)", std::f64::consts::PI.powi(2) / 6.0, 0.0);
+    rel("zeta(-3.5)", 4.441_011_335_479_432e-3, 0.0);
+    rel("zeta(2 + i)", 1.150_355_703_254_902_7, -0.437_530_865_919_607_9);
+    rel("besselj(1, 2)", 0.576_724_807_756_873_4, 0.0);
+    rel("besselj(2.5, 30)", 0.141_202_858_799_282_12, 0.0);
+    rel("besselj(1, 1e5)", 1.846_757_562_882_567_7e-3, 0.0);
+    rel("bessely(0, 0.5)", -0.444_518_733_506_706_56, 0.0);
+    rel("bessely(-1.3, 24)", 0.024_978_217_094_557_187, 0.0);
+    rel("besseli(1, -3.7)", -7.435_745_796_535_337, 0.0);
+    rel("besselk(2.5, 3)", 0.084_060_631_974_117_38, 0.0);
+    rel("airy(10)", 1.104_753_255_289_868_6e-10, 0.0);
+    rel("airy(3, -5.3)", 0.405_556_940_883_315_83, 0.0);
+    rel("lambertw(1e300)", 684.247_208_629_760_8, 0.0);
+    rel("lambertw(-1, -0.3)", -1.781_337_023_421_627_7, 0.0);
+    rel("lambertw(2, -3 - 4*i)", -0.557_922_738_746_791_1, 8.717_362_702_415_932);
+    rel("ellipk(-100)", 0.368_219_248_609_141, 0.0);
+    rel("ellipe(0.99)", 1.015_993_545_025_224, 0.0);
+    // Valores exactos y derivadas
+    assert_eq!(c.evaluate("fact(20)").unwrap().re, 2_432_902_008_176_640_000.0);
+    assert_eq!(c.evaluate("zeta(-2)").unwrap().re, 0.0);
+    let d = c.evaluate_dual("gamma(x)", &[("x", Complex64::new(1.0, 0.0))]).unwrap().d(0);
+    assert!(approx(d, -0.577_215_664_901_532_9, 1e-15));
+    let d = c.evaluate_dual("lambertw(x)", &[("x", Complex64::new(std::f64::consts::E, 0.0))]).unwrap().d(0);
+    assert!(approx(d, 0.5 / std::f64::consts::E, 1e-15));
+    // Dominio
+    assert!(c.evaluate("gamma(-2)").is_err());
+    assert!(c.evaluate("zeta(1)").is_err());
+    assert!(c.evaluate("erf(i)").is_err());
+    assert!(c.evaluate("bessely(0, -1)").is_err());
+    assert!(c.evaluate("besselj(0.5, -1)").is_err());
+    assert!(c.evaluate("airy(4, 1)").is_err());
+    assert!(c.evaluate("ellipk(1.5)").is_err());
+    assert!(c.evaluate("fact(171.5)").is_err());
+}
+
+#[test]
+fn primality_and_factorization() {
+    use super::numtheory::{divisors, factorize};
+    use crate::math_ext::is_prime_u128;
+    let mut c = Calculator::new();
+    // Exactos aunque los operandos no quepan en f64
+    assert_eq!(c.evaluate("isprime(2^61-1)").unwrap().re, 1.0);
+    assert_eq!(c.evaluate("isprime(2^89-1)").unwrap().re, 1.0);
+    assert_eq!(c.evaluate("isprime(2^61+1)").unwrap().re, 0.0);
+    c.evaluate("nextprime(10^18)").unwrap();
+    assert_eq!(c.exact_result, Some(BigInt::from(1_000_000_000_000_000_003u128)));
+    // Literales en coma flotante que son enteros exactos más allá de 2^53, y cerca de 2^64
+    c.evaluate("nextprime(1e18)").unwrap();
+    assert_eq!(c.exact_result, Some(BigInt::from(1_000_000_000_000_000_003u128)));
+    assert_eq!(c.evaluate("isprime(1e18+9)").unwrap().re, 1.0);
+    c.evaluate("nextprime(2^64)").unwrap();
+    assert_eq!(c.exact_result, Some(BigInt::from(18_446_744_073_709_551_629u128)));
+    assert_eq!(c.evaluate("isprime(2^64-59)").unwrap().re, 1.0);
+    assert_eq!(c.evaluate("isprime(2^64+1)").unwrap().re, 0.0);
+    // 2^127 como intermedio no desborda
+    assert_eq!(c.evaluate("isprime(2^127-1)").unwrap().re, 1.0);
+    assert!(c.evaluate("isprime(2^127+1)").is_err());
+    // Pseudoprimos fuertes para varias bases y números de Carmichael
+    for n in [561u128, 3_215_031_751, 3_825_123_056_546_413_051, 318_665_857_834_031_151_167_461] {
+        assert!(!is_prime_u128(n), "{}", n);
+    }
+    assert!(is_prime_u128(170_141_183_460_469_231_731_687_303_715_884_105_727));
+    assert_eq!(factorize(360), vec![(2, 3), (3, 2), (5, 1)]);
+    assert_eq!(factorize(-18_446_744_073_709_551_617), vec![(274_177, 1), (67_280_421_310_721, 1)]);
+    assert_eq!(
+        factorize(1_000_000_000_039 * 1_000_000_000_061),
+        vec![(1_000_000_000_039, 1), (1_000_000_000_061, 1)]
+    );
+    assert_eq!(c.eval_integer("(2^31-1)^4").map(factorize).unwrap(), vec![(2_147_483_647, 4)]);
+    assert_eq!(divisors(12), vec![1, 2, 3, 4, 6, 12]);
+    assert_eq!(c.evaluate("totient(36)").unwrap().re, 12.0);
+    assert_eq!(c.evaluate("sigma(12)").unwrap().re, 28.0);
+    assert_eq!(c.evaluate("sigma(12, 0)").unwrap().re, 6.0);
+    assert_eq!(c.evaluate("sigma(12, 2)").unwrap().re, 210.0);
+    assert_eq!(c.evaluate("moebius(30)").unwrap().re, -1.0);
+    assert_eq!(c.evaluate("moebius(12)").unwrap().re, 0.0);
+    assert_eq!(c.evaluate("isperfect(8128)").unwrap().re, 1.0);
+    assert_eq!(c.evaluate("isperfect(2^60*(2^61-1))").unwrap().re, 1.0);
+    assert_eq!(c.evaluate("isperfect(12)").unwrap().re, 0.0);
+    assert!(c.evaluate("totient(0)").is_err());
+    assert!(c.evaluate("isprime(2.5)").is_err());
+    assert!(c.evaluate("sigma(10, -1)").is_err());
+}
+
+#[test]
+fn modular_arithmetic() {
+    use super::numtheory::crt;
+    use crate::math_ext::{egcd_i128, is_prime_u128, sqrt_mod_prime};
+    let mut c = Calculator::new();
+    assert_eq!(c.evaluate("powmod(2, 10^18, 10^9+7)").unwrap().re, 719_476_260.0);
+    assert_eq!(c.evaluate("powmod(3, -1, 97)").unwrap().re, 65.0);
+    c.evaluate("powmod(3, 2^100, 2^89-1)").unwrap();
+    assert_eq!(c.evaluate("invmod(-3, 97)").unwrap().re, 32.0);
+    assert!(c.evaluate("invmod(4, 8)").is_err());
+    assert_eq!(c.evaluate("jacobi(1001, 9907)").unwrap().re, -1.0);
+    assert_eq!(c.evaluate("jacobi(3, 9)").unwrap().re, 0.0);
+    assert!(c.evaluate("jacobi(3, 10)").is_err());
+    assert_eq!(c.evaluate("legendre(-1, 13)").unwrap().re, 1.0);
+    assert!(c.evaluate("legendre(2, 15)").is_err());
+    assert_eq!(c.evaluate("sqrtmod(10, 13)").unwrap().re, 6.0);
+    assert!(c.evaluate("sqrtmod(5, 13)").is_err());
+    // Tonelli–Shanks con p ≡ 1 (mod 2^k) para k grande
+    for p in [17u128, 257, 65_537, 998_244_353, (1 << 64) - (1 << 32) + 1] {
+        assert!(is_prime_u128(p));
+        for a in 1..50u128 {
+            let a2 = a * a % p;
+            let r = sqrt_mod_prime(a2, p).unwrap();
+            assert_eq!(r * r % p, a2);
+            assert!(r <= p - r);
+        }
+    }
+    assert_eq!(egcd_i128(240, 46), (2, -9, 47));
+    assert_eq!(egcd_i128(-12, 18).0, 6);
+    assert_eq!(crt(&[2, 3, 2], &[3, 5, 7]), Ok((23, 105)));
+    assert_eq!(crt(&[1, 3], &[4, 6]), Ok((9, 12)));
+    assert!(crt(&[1, 2], &[4, 6]).is_err());
+    assert_eq!(crt(&[0, 0], &[1i128 << 62, 3]), Ok((0, 3i128 << 62)));
+
+    // Modo modulo: cada operación se reduce, el exponente no
+    c.set_modulus(Some(97)).unwrap();
+    assert_eq!(c.evaluate("3^-1").unwrap().re, 65.0);
+    assert_eq!(c.evaluate("2^100").unwrap().re, 16.0);
+    assert_eq!(c.evaluate("1/3 + 5").unwrap().re, 70.0);
+    assert_eq!(c.evaluate("-5").unwrap().re, 92.0);
+    assert!(c.evaluate("10/0").is_err());
+    assert!(c.evaluate("1.5 + 2").is_err());
+    c.set_modulus(Some((1 << 89) - 1)).unwrap();
+    c.evaluate("2^89").unwrap();
+    assert_eq!(c.exact_result, None);
+    assert_eq!(c.evaluate("2^88 * 4").unwrap().re, 2.0);
+    assert!(c.set_modulus(Some(1)).is_err());
+    c.set_modulus(None).unwrap();
+    assert_eq!(c.evaluate("2^10").unwrap().re, 1024.0);
+}
+
+#[test]
+fn prime_counting() {
+    use crate::math_ext::{nth_prime, prime_pi, primes_in};
+    let small = primes_in(0, 100_000);
+    assert_eq!(small.len(), 9592);
+    assert_eq!(&small[..5], &[2, 3, 5, 7, 11]);
+    for (k, &p) in small.iter().enumerate().step_by(97) {
+        assert_eq!(nth_prime(k as u64 + 1), p);
+        assert_eq!(prime_pi(p), k as u64 + 1);
+        assert_eq!(prime_pi(p - 1), k as u64);
+    }
+    assert_eq!(primes_in(1_000_000_000_000 - 100, 1_000_000_000_000), vec![999_999_999_937, 999_999_999_959, 999_999_999_961, 999_999_999_989]);
+    let mut c = Calculator::new();
+    assert_eq!(c.evaluate("primepi(10^9)").unwrap().re, 50_847_534.0);
+    assert_eq!(c.evaluate("nthprime(10^7)").unwrap().re, 179_424_673.0);
+    assert_eq!(c.evaluate("prevprime(100)").unwrap().re, 97.0);
+    assert_eq!(c.evaluate("prevprime(2^61)").unwrap().re, 2f64.powi(61));
+    assert_eq!(c.exact_result, Some(BigInt::from((1u64 << 61) - 1)));
+    assert!(c.evaluate("prevprime(2)").is_err());
+    assert!(c.evaluate("nthprime(0)").is_err());
+    assert!(c.evaluate("primepi(10^13)").is_err());
+}
+
+#[test]
+fn exact_combinatorics() {
+    let mut c = Calculator::new();
+    let mut exact = |expr: &str| {
+        c.evaluate(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        c.exact_result.as_ref().map_or(c.last_result.re as i128, |n| n.to_i128().unwrap())
+    };
+    assert_eq!(exact("fact(25)"), 15_511_210_043_330_985_984_000_000);
+    assert_eq!(exact("comb(100, 50)"), 100_891_344_545_564_193_334_812_497_256);
+    assert_eq!(exact("comb(-3, 2)"), 6);
+    assert_eq!(exact("comb(-3, 3)"), -10);
+    assert_eq!(exact("comb(5, 7)"), 0);
+    assert_eq!(exact("perm(30, 20)"), 73_096_577_329_197_271_449_600_000);
+    assert_eq!(exact("catalan(60)"), 1_583_850_964_596_120_042_686_772_779_038_896);
+    assert_eq!(exact("stirling1(20, 5)"), -371_384_787_345_228_000);
+    assert_eq!(exact("stirling2(30, 10)"), 173_373_343_599_189_364_594_756);
+    assert_eq!(exact("bell(40)"), 157_450_588_391_204_931_289_324_344_702_531_067);
+    assert_eq!(exact("partitions(1000)"), 24_061_467_864_032_622_473_692_149_727_991);
+    assert_eq!(exact("multinomial(10, 20, 30)"), 3_553_261_127_084_984_957_001_360);
+    assert_eq!(exact("derangements(30)"), 97_581_073_836_835_777_732_377_428_235_481);
+    assert_eq!(exact("fib(180)"), 18_547_707_689_471_986_212_190_138_521_399_707_760);
+    assert_eq!(exact("fib(-10)"), -55);
+    assert_eq!(exact("lucas(-5)"), -11);
+    assert_eq!(exact("lucas(100)"), 792_070_839_848_372_253_127);
+    // Fuera de i128 siguen siendo exactos (el valor f64 es su redondeo)
+    let mut digits = |expr: &str| {
+        let v = c.evaluate(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        c.format_result(v)
+    };
+    assert_eq!(digits("catalan(100)"), "896519947090131496687170070074100632420837521538745909320");
+    assert_eq!(digits("fact(40)"), "815915283247897734345611269596115894272000000000");
+    assert_eq!(digits("fact(3000)/fact(2998)"), "8997000");
+    assert_eq!(digits("fact(100) + 1").len(), 158);
+    // Si tiene que pasar a coma flotante se muestra como aproximado, sin rellenar con ceros
+    assert_eq!(digits("fact(40)*1.5"), "≈ 1.2238729248718465e48");
+    assert!(digits("partitions(30000)").starts_with("≈ 4.29"));
+    let rel = |c: &mut Calculator, expr: &str, want: f64| {
+        let v = c.evaluate(expr).unwrap().re;
+        assert!(((v - want) / want).abs() < 1e-13, "{}: {} vs {}", expr, v, want);
+    };
+    rel(&mut c, "comb(1000, 500)", 2.702_882_409_454_366e299);
+    rel(&mut c, "catalan(100)", 8.965_199_470_901_315e56);
+    rel(&mut c, "stirling2(100, 50)", 4.309_832_370_093_663e101);
+    rel(&mut c, "partitions(5000)", 1.698_201_688_254_421e74);
+    rel(&mut c, "fib(1000)", 4.346_655_768_693_746e208);
+    rel(&mut c, "comb(2.5, 2)", 1.875);
+    rel(&mut c, "comb(0.5, 0.25)", 1.078_705_202_376_759);
+    assert!(c.evaluate("catalan(-1)").is_err());
+    assert!(c.evaluate("perm(5, 7)").is_err());
+    assert!(c.evaluate("multinomial(2, 1.5)").is_err());
+    assert!(c.evaluate("fib(10^6)").is_err());
+}
+
+#[test]
+fn time_value_of_money() {
+    let mut c = Calculator::new();
+    // Ejemplos de la documentación de Excel
+    let mut check = |expr: &str, want: f64, tol: f64| {
+        let v = c.evaluate(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e)).re;
+        assert!((v - want).abs() <= tol * want.abs().max(1.0), "{}: {} vs {}", expr, v, want);
+    };
+    check("pmt(0.05/12, 360, 200000)", -1_073.643_246_024_278, 1e-12);
+    check("pmt(0.08/12, 10, 10000, 0, 1)", -1_030.164_327_177_966, 1e-12);
+    check("fv(0.06/12, 10, -200, -500, 1)", 2_581.403_374_060_18, 1e-12);
+    check("pv(0.08/12, 240, 500)", -59_777.145_851_188, 1e-12);
+    check("nper(0.01, -100, -1000, 10000, 1)", 59.673_865_674_295, 1e-12);
+    check("rate(48, -200, 8000)", 0.007_701_472_488_2, 1e-9);
+    check("npv(0.1, -10000, 3000, 4200, 6800)", 1_188.443_412_335_2, 1e-12);
+    check("irr(-70000, 12000, 15000, 18000, 21000, 26000)", 0.086_630_948, 1e-7);
+    check("irr(-70000, 12000, 15000, 18000, 21000)", -0.021_244_848, 1e-7);
+    check("xirr(date(2008,1,1), -10000, date(2008,3,1), 2750, date(2008,10,30), 4250, date(2009,2,15), 3250, date(2009,4,1), 2750)", 0.373_362_535, 1e-8);
+    check("effect(0.0525, 4)", 0.053_542_667_370_758, 1e-12);
+    check("nominal(0.053543, 4)", 0.052_500_319_868_356, 1e-12);
+    check("date(2024, 2, 29) - date(2023, 12, 31)", 60.0, 0.0);
+    check("pmt(0, 10, 1000)", -100.0, 1e-15);
+    check("fv(0, 10, -100)", 1000.0, 1e-15);
+    // rate recupera la tasa de pmt
+    check("rate(360, pmt(0.004, 360, 150000, -20000, 1), 150000, -20000, 1)", 0.004, 1e-10);
+    let d = c.evaluate_dual("pv(x, 10, -100)", &[("x", Complex64::new(0.05, 0.0))]).unwrap().d(0);
+    assert!((d.re + 3_749.884_076_640_87).abs() < 1e-5);
+    assert!(c.evaluate("irr(100, 200)").is_err());
+    assert!(c.evaluate("pmt(0.01, 10, 1000, 0, 2)").is_err());
+    assert!(c.evaluate("pv(0.01)").is_err());
+    // Sobre la pila y la pila de pares
+    c.memory_stack = [-100.0, 30.0, 40.0, 50.0].iter().map(|&v| Complex64::new(v, 0.0)).collect();
+    assert!((c.stack_irr().unwrap() - 0.088_963_394_693).abs() < 1e-9);
+    assert!((c.stack_npv(0.1).unwrap() + 1.912_437_675_022).abs() < 1e-9);
+    c.pairs = vec![(0.0, -1000.0), (365.0, 1100.0)];
+    assert!((c.pairs_xirr().unwrap() - 0.1).abs() < 1e-12);
+}
+
+#[test]
+fn amortization_schedule() {
+    use crate::calc::finance::amortization;
+    let rows = amortization(200_000.0, 0.05, 30.0, 0.0).unwrap();
+    assert_eq!(rows.len(), 360);
+    assert!(rows.iter().all(|r| (r.payment - 1_073.643_246_024_28).abs() < 1e-8));
+    assert!((rows[0].interest - 833.333_333_333_333).abs() < 1e-9);
+    assert_eq!(rows[359].balance, 0.0);
+    let principal: f64 = rows.iter().map(|r| r.principal).sum();
+    let interest: f64 = rows.iter().map(|r| r.interest).sum();
+    assert!((principal - 200_000.0).abs() < 1e-6);
+    assert!((interest - 186_511.568_568_739).abs() < 1e-5);
+    // Pago extra: acorta el plazo y la última cuota liquida el saldo
+    let rows = amortization(200_000.0, 0.05, 30.0, 100.0).unwrap();
+    assert_eq!(rows.len(), 298);
+    assert!((rows.iter().map(|r| r.interest).sum::<f64>() - 149_442.538_587_851).abs() < 1e-5);
+    assert!(rows[297].payment < 1_173.64 && rows[297].balance == 0.0);
+    let zero = amortization(1200.0, 0.0, 1.0, 0.0).unwrap();
+    assert!(zero.iter().all(|r| r.payment == 100.0 && r.interest == 0.0));
+    assert!(amortization(1000.0, 0.1, 0.3, 0.0).is_err());
+    assert!(amortization(-1000.0, 0.1, 1.0, 0.0).is_err());
+    assert!(amortization(1000.0, 0.1, 1.0, -5.0).is_err());
+}
+
+#[test]
+fn polar_complex_io() {
+    use crate::calc::ComplexFormat;
+    let mut c = Calculator::new();
+    let z = c.evaluate("5∠atan2(4, 3)").unwrap();
+    assert!((z - Complex64::new(3.0, 4.0)).norm() < 1e-12);
+    let z = c.evaluate("2*cis(pi/2) + 1∠pi").unwrap();
+    assert!((z - Complex64::new(-1.0, 2.0)).norm() < 1e-12);
+    // ∠ liga más que el producto y el ángulo sigue a la unidad activa
+    c.angle = AngleUnit::Deg;
+    let z = c.evaluate("2*5∠-90").unwrap();
+    assert!((z - Complex64::new(0.0, -10.0)).norm() < 1e-12);
+    assert!((c.evaluate("10∠30 + 10∠-30").unwrap() - Complex64::new(300f64.sqrt(), 0.0)).norm() < 1e-12);
+    assert!(c.evaluate("(1+i)∠30").is_err());
+
+    let z = Complex64::new(3.0, 4.0);
+    assert_eq!(c.format(z), "3 + 4i");
+    c.complex_format = ComplexFormat::Polar;
+    assert_eq!(c.format(z), format!("5∠{}", 0.927_295_218_001_612_2f64.to_degrees()));
+    assert_eq!(c.format(Complex64::new(-2.0, 0.0)), "-2");
+    c.angle = AngleUnit::Rad;
+    assert_eq!(c.format(z), "5∠0.9272952180016122");
+    c.complex_format = ComplexFormat::Exponential;
+    assert_eq!(c.format(z), "5·e^(i0.9272952180016122)");
+    // Derivada respecto al módulo y al ángulo
+    let d = c.evaluate_dual("x∠1", &[("x", Complex64::new(2.0, 0.0))]).unwrap().d(0);
+    assert!((d - Complex64::from_polar(1.0, 1.0)).norm() < 1e-12);
+    let d = c.evaluate_dual("2∠x", &[("x", Complex64::new(1.0, 0.0))]).unwrap().d(0);
+    assert!((d - Complex64::i() * Complex64::from_polar(2.0, 1.0)).norm() < 1e-12);
+}
+
+#[test]
+fn output_formats() {
+    use crate::calc::OutputFormat;
+    let mut c = Calculator::new();
+    let mut show = |fmt: OutputFormat, group: bool, v: f64| {
+        c.output_format = fmt;
+        c.digit_grouping = group;
+        c.format_real(v)
+    };
+    assert_eq!(show(OutputFormat::Fixed(2), false, 1234.567), "1234.57");
+    assert_eq!(show(OutputFormat::Fixed(0), true, -1_234_567.4), "-1 234 567");
+    assert_eq!(show(OutputFormat::Significant(4), false, 1.0 / 3.0), "0.3333");
+    assert_eq!(show(OutputFormat::Significant(4), false, 2.0), "2.000");
+    assert_eq!(show(OutputFormat::Significant(3), false, 123_456.0), "1.23e5");
+    assert_eq!(show(OutputFormat::Significant(3), false, 9.996), "10.0");
+    assert_eq!(show(OutputFormat::Engineering, false, 0.00047), "470e-6");
+    assert_eq!(show(OutputFormat::Engineering, false, -123_456_789.0), "-123.457e6");
+    assert_eq!(show(OutputFormat::Engineering, false, 999_999.9), "1e6");
+    assert_eq!(show(OutputFormat::Si, false, 4700.0), "4.7k");
+    assert_eq!(show(OutputFormat::Si, false, 22e-6), "22µ");
+    assert_eq!(show(OutputFormat::Si, false, 0.0), "0");
+    assert_eq!(show(OutputFormat::Si, false, 1e30), "1e30");
+    assert_eq!(show(OutputFormat::Decimal, true, 1_234_567.891), "1 234 567.891");
+    assert_eq!(show(OutputFormat::Decimal, true, 999.5), "999.5");
+    assert_eq!(show(OutputFormat::Scientific, true, 1234.5), "1.234500e3");
+    assert_eq!(show(OutputFormat::Si, true, f64::INFINITY), "inf");
+    // Los complejos usan el mismo formato en cada parte, y lo agrupado se puede releer
+    c.output_format = OutputFormat::Fixed(1);
+    assert_eq!(c.format(Complex64::new(1.25, -2000.0)), "1.2 - 2 000.0i");
+    let text = c.format(Complex64::new(1_234_567.0, 0.0));
+    assert_eq!(c.evaluate(&text).unwrap().re, 1_234_567.0);
+}
+
+#[test]
+fn radix_display() {
+    let mut c = Calculator::new();
+    let show = |c: &mut Calculator, expr: &str| {
+        let v = c.evaluate(expr).unwrap();
+        c.format_result(v)
+    };
+    assert_eq!(show(&mut c, "hex(255)"), "0xff");
+    assert_eq!(show(&mut c, "bin(10)"), "0b1010");
+    assert_eq!(show(&mut c, "oct(-8)"), "0o1777777777777777777770");
+    assert_eq!(show(&mut c, "hex(-1)"), "0xffffffffffffffff");
+    assert_eq!(show(&mut c, "hex(2.75)"), "0x2.c");
+    assert_eq!(show(&mut c, "bin(-0.625)"), "-0b0.101");
+    // Solo la llamada exterior fija la base; dentro de una expresión es la identidad
+    assert_eq!(show(&mut c, "hex(16) + 1"), "17");
+    assert_eq!(show(&mut c, "hex(fact(30))"), "0xd13f6370f96865df5dd54000000");
+    c.radix = 16;
+    assert_eq!(show(&mut c, "18446744073709551616"), "0x10000000000000000");
+    assert_eq!(show(&mut c, "fact(30)"), "0xd13f6370f96865df5dd54000000");
+    assert_eq!(c.format(Complex64::new(10.0, -1.5)), "0xa - 0x1.8i");
+    c.radix = 3;
+    assert_eq!(show(&mut c, "0.5"), format!("3#0.{}…", "1".repeat(34)));
+    c.radix = 2;
+    c.digit_grouping = true;
+    assert_eq!(show(&mut c, "255"), "0b1111 1111");
+}
+
+#[test]
+fn programmer_mode() {
+    use crate::calc::programmer::WordMode;
+    let mut c = Calculator::new();
+    let run = |c: &mut Calculator, spec: &str, expr: &str| {
+        c.set_word(Some(WordMode::parse(spec).unwrap()));
+        let v = c.evaluate(expr).unwrap_or_else(|e| panic!("{} {}: {}", spec, expr, e));
+        c.format_result(v)
+    };
+    assert_eq!(run(&mut c, "u8", "200 + 100"), "44");
+    assert_eq!(run(&mut c, "u8", "0 - 1"), "255");
+    assert_eq!(run(&mut c, "u8 sat", "200 + 100"), "255");
+    assert_eq!(run(&mut c, "u8 sat", "3 - 5"), "0");
+    assert_eq!(run(&mut c, "i8", "127 + 1"), "-128");
+    assert_eq!(run(&mut c, "i8 sat", "-100 - 100"), "-128");
+    assert_eq!(run(&mut c, "i8 sat", "-128 / -1"), "127");
+    assert_eq!(run(&mut c, "i32", "-7 / 2"), "-3");
+    assert_eq!(run(&mut c, "i32", "-7 % 2"), "-1");
+    assert_eq!(run(&mut c, "i32", "2^31"), "-2147483648");
+    assert_eq!(run(&mut c, "u32", "0xffffffff * 0xffffffff"), "1");
+    assert_eq!(run(&mut c, "i64", "0xdeadbeefdeadbeef"), "-2401053088876216593");
+    assert_eq!(run(&mut c, "i64", "ans + 1"), "-2401053088876216592");
+    assert_eq!(run(&mut c, "u128", "0 - 1"), u128::MAX.to_string());
+    assert_eq!(run(&mut c, "i128 sat", "2^127"), i128::MAX.to_string());
+    assert_eq!(run(&mut c, "i16", "hex(-2)"), "0xfffe");
+    c.radix = 2;
+    assert_eq!(run(&mut c, "i8", "-1"), "0b11111111");
+    c.radix = 10;
+    let w = WordMode::parse("i16").unwrap();
+    assert_eq!(w.views(w.fit(-2)).map(|v| v.1), ["0xfffe", "-2", "0o177776", "0b1111 1111 1111 1110"]);
+    assert!(c.evaluate("1.5").is_err());
+    assert!(c.evaluate("7 / 0").is_err());
+    assert!(c.evaluate("sin(1)").is_err());
+    assert!(WordMode::parse("i24").is_err() && WordMode::parse("u8 clamp").is_err());
+    // Es excluyente con el modo modulo y se desactiva con None
+    c.set_modulus(Some(7)).unwrap();
+    assert!(c.word.is_none());
+    c.set_modulus(None).unwrap();
+    assert_eq!(c.evaluate("0x10 + 0b11 - 0o7").unwrap().re, 12.0);
+    assert_eq!(c.evaluate("totient(1000000000000000003)").unwrap().re, 1_000_000_000_000_000_002.0);
+    assert_eq!(c.exact_result, Some(BigInt::from(1_000_000_000_000_000_002u128)));
+}
+
+#[test]
+fn closed_forms() {
+    use crate::calc::identify::{identify, DEFAULT_TOL};
+    let best = |x: f64| identify(x, DEFAULT_TOL).first().map(|f| f.expr.clone()).unwrap_or_default();
+    assert_eq!(best(std::f64::consts::FRAC_PI_4), "π/4");
+    assert_eq!(best(std::f64::consts::SQRT_2), "√2");
+    assert_eq!(best(-0.75), "-3/4");
+    assert_eq!(best(3.0 * 5f64.sqrt() / 7.0), "3√5/7");
+    assert_eq!(best(1.5f64.ln()), "ln(3/2)");
+    assert_eq!(best(-std::f64::consts::PI.powi(2) / 6.0), "-π²/6");
+    assert_eq!(best(1.618033988749895), "(1 + √5)/2");
+    assert_eq!(best((3.0 * std::f64::consts::E - 1.0) / 4.0), "(3e - 1)/4");
+    assert!(identify(0.1234567891234, DEFAULT_TOL).is_empty());
+    // La tolerancia decide: 1/3 + 1e-9 solo se reconoce con tol >= 1e-9
+    assert!(identify(1.0 / 3.0 + 1e-9, DEFAULT_TOL).is_empty());
+    let f = &identify(1.0 / 3.0 + 1e-9, 1e-6)[0];
+    assert_eq!(f.expr, "1/3");
+    assert!((f.error - 1e-9).abs() < 1e-15);
+    // Tolerancia relativa: un valor diminuto no es 0; y 0 no se repite como ln(1)
+    assert!(identify(1e-20, DEFAULT_TOL).is_empty());
+    let zero = identify(0.0, DEFAULT_TOL);
+    assert_eq!(zero.len(), 1);
+    assert_eq!(zero[0].expr, "0");
+}
+
+#[test]
+fn uncertainty_propagation() {
+    use crate::calc::format::format_measurement;
+    let mut c = Calculator::new();
+    let v = c.evaluate("9.81 ± 0.02").unwrap();
+    c.assign("g", v);
+    let v = c.evaluate("1.25 +/- 0.005").unwrap();
+    c.assign("L", v);
+    // T = 2π√(L/g): σ_T/T = ½·√((σ_L/L)² + (σ_g/g)²)
+    let t = c.evaluate("2*pi*sqrt(L/g)").unwrap();
+    let u = c.uncertainty("ans").unwrap();
+    let rel = 0.5 * ((0.005f64 / 1.25).powi(2) + (0.02f64 / 9.81).powi(2)).sqrt();
+    assert!((u.sigma() - t.re * rel).abs() < 1e-12);
+    assert!((u.worst_case() - t.re * 0.5 * (0.005 / 1.25 + 0.02 / 9.81)).abs() < 1e-12);
+    assert_eq!(c.format_result(t), "2.243 ± 0.005");
+    // Correlación: la misma medida usada dos veces
+    let z = c.evaluate("g - g").unwrap();
+    assert!(c.uncertainty("ans").is_none());
+    assert_eq!(c.format_result(z), "0");
+    c.evaluate("g*g").unwrap();
+    assert!((c.uncertainty("ans").unwrap().sigma() - 2.0 * 9.81 * 0.02).abs() < 1e-12);
+    // Fuentes independientes se suman en cuadratura; ± liga menos que *
+    c.evaluate("(1 ± 0.3) + 2*2 ± 0.4").unwrap();
+    assert!((c.uncertainty("ans").unwrap().sigma() - 0.5).abs() < 1e-12);
+    c.evaluate("ans * 2").unwrap();
+    assert!((c.uncertainty("ans").unwrap().sigma() - 1.0).abs() < 1e-12);
+    // Una variable sobrescrita por otra vía pierde su incertidumbre
+    c.vars.insert("g".to_string(), Complex64::new(10.0, 0.0));
+    assert!(c.uncertainty("g").is_none());
+    assert!(c.evaluate("1 ± -1").is_err());
+    assert_eq!(format_measurement(9.8123, 0.0234), "9.81 ± 0.02");
+    assert_eq!(format_measurement(9.8123, 0.0164), "9.812 ± 0.016");
+    assert_eq!(format_measurement(1234.5, 23.0), "1230 ± 20");
+    assert_eq!(format_measurement(6.02214076e23, 3e18), "(6.02214 ± 0.00003)e23");
+    assert_eq!(format_measurement(1.234e-7, 2.3e-9), "(1.23 ± 0.02)e-7");
+}
+
+#[test]
+fn uncertainty_through_table_functions() {
+    let mut c = Calculator::new();
+    let mut sigma = |expr: &str| {
+        c.evaluate(expr).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        c.uncertainty("ans").map_or(0.0, |u| u.sigma())
+    };
+    // Estadística: ∂std/∂x₁ = (x₁ − m)/((n − 1)·s) = −0.5 ; la mediana es el dato central
+    assert!((sigma("std(1±0.1, 2, 3)") - 0.05).abs() < 1e-15);
+    assert!((sigma("median(1, 2±0.1, 3)") - 0.1).abs() < 1e-15);
+    assert_eq!(sigma("median(1±0.1, 2, 3)"), 0.0);
+    // Parámetros de distribuciones (∂Φ/∂μ = −φ) y orden de Bessel
+    assert!((sigma("normcdf(1, 0±0.1, 1)") - 0.024_197_072_451_914_335).abs() < 1e-15);
+    assert!((sigma("besselj(1±0.1, 2)") - 0.005_618_076_074_181_31).abs() < 1e-10);
+    // Cada función estadística contra diferencias centradas
+    let mut d = |expr: &str, x: f64| {
+        let at = |c: &mut Calculator, x: f64| c.evaluate_dual(expr, &[("x", Complex64::new(x, 0.0))]).unwrap_or_else(|e| panic!("{}: {}", expr, e));
+        let h = 1e-5;
+        let fd = (at(&mut c, x + h).val.re - at(&mut c, x - h).val.re) / (2.0 * h);
+        let ad = at(&mut c, x).d(0).re;
+        assert!((ad - fd).abs() < 1e-8 * fd.abs().max(1.0), "{}: {} vs {}", expr, ad, fd);
+    };
+    for f in ["mean", "median", "var", "varp", "std", "stdp", "iqr", "skew", "kurt", "gmean", "hmean"] {
+        d(&format!("{}(x, 2, 4, 7, 11)", f), 1.3);
+    }
+    d("quartile(1, x, 2, 4, 7, 11)", 3.1);
+    d("moda(x, x, 3)", 1.3);
+    d("tcdf(1.5, x)", 4.0);
+    d("chi2pdf(2, x)", 3.5);
+    d("normpdf(0.3, 1, x)", 2.0);
+    d("norminv(0.8, x, 2)", 1.0);
+    d("expcdf(0.7, x)", 1.5);
+    d("binompmf(3, 10, x)", 0.4);
+    d("besseli(x, 1.5)", 0.7);
+    assert!(c.evaluate("poisspmf(2±0.1, 3)").is_err());
+}
+
+#[test]
+fn angle_units_and_dms() {
+    use crate::calc::format::format_dms;
+    let mut c = Calculator::new();
+    // Los sufijos mandan sobre el modo solo para su literal
+    assert!(approx(c.evaluate("sin(30°)").unwrap(), 0.5, 1e-15));
+    assert!(approx(c.evaluate("90° - pi/2").unwrap(), 0.0, 1e-15));
+    c.angle = AngleUnit::Deg;
+    assert!(approx(c.evaluate("cos(1rad)").unwrap(), 1f64.cos(), 1e-15));
+    assert!(approx(c.evaluate("100grad").unwrap(), 90.0, 1e-12));
+    assert!(approx(c.evaluate("12°30'15\"").unwrap(), 12.504_166_666_666_667, 1e-12));
+    assert!(approx(c.evaluate("-12°30′").unwrap(), -12.5, 1e-12));
+    assert!(c.evaluate("12°60'").is_err());
+    // Las inversas convierten el complejo entero: sin(asin(2)) = 2 en cualquier unidad
+    let z = c.evaluate("asin(2)").unwrap();
+    assert!((z - c.angle.express_rad(Complex64::new(2.0, 0.0).asin())).norm() < 1e-12);
+    assert!(approx(c.evaluate("sin(asin(2))").unwrap(), 2.0, 1e-12));
+    c.angle = AngleUnit::Grad;
+    assert!(approx(c.evaluate("sin(100)").unwrap(), 1.0, 1e-15));
+    assert_eq!(c.evaluate("atan2(1, 1)").unwrap().re, 50.0);
+    assert_eq!(c.evaluate("90°").unwrap().re, 100.0);
+    let d = c.evaluate_dual("sin(x)", &[("x", Complex64::new(0.0, 0.0))]).unwrap().d(0);
+    assert!(approx(d, std::f64::consts::PI / 200.0, 1e-15));
+    // dms: se muestra en grados, minutos y segundos (desde la unidad activa)
+    let v = c.evaluate("dms(50)").unwrap();
+    assert_eq!(c.format_result(v), "45°0'0\"");
+    c.angle = AngleUnit::Rad;
+    let v = c.evaluate("dms(1)").unwrap();
+    assert_eq!(c.format_result(v), "57°17'44.81\"");
+    assert_eq!(format_dms(12.504_166_666_666_667), "12°30'15\"");
+    assert_eq!(format_dms(29.999_999_9), "30°0'0\"");
+    assert_eq!(format_dms(-0.5), "-0°30'0\"");
+}
+
+#[test]
+fn complex_semantics() {
+    let mut c = Calculator::new();
+    let close = |z: Complex64, re: f64, im: f64| (z.re - re).abs() < 1e-12 && (z.im - im).abs() < 1e-12;
+    // Logaritmos: rama principal
+    let ln10 = std::f64::consts::LN_10;
+    assert!(close(c.evaluate("log10(-1)").unwrap(), 0.0, std::f64::consts::PI / ln10));
+    assert!(close(c.evaluate("log2(2*i)").unwrap(), 1.0, std::f64::consts::FRAC_PI_2 / std::f64::consts::LN_2));
+    assert!(c.evaluate("log10(0)").is_err());
+    // Redondeo componente a componente y signo z/|z|
+    assert!(close(c.evaluate("floor(1.5 - 2.5*i)").unwrap(), 1.0, -3.0));
+    assert!(close(c.evaluate("ceil(1.5 - 2.5*i)").unwrap(), 2.0, -2.0));
+    assert!(close(c.evaluate("round(1.4 + 2.6*i)").unwrap(), 1.0, 3.0));
+    assert!(close(c.evaluate("trunc(-1.7 + 1.7*i)").unwrap(), -1.0, 1.0));
+    assert!(close(c.evaluate("sign(3 + 4*i)").unwrap(), 0.6, 0.8));
+    assert!(close(c.evaluate("sign(0)").unwrap(), 0.0, 0.0));
+    // Norma, porcentajes, raíces y conversiones con complejos
+    assert!(close(c.evaluate("hypot(3*i, 4)").unwrap(), 5.0, 0.0));
+    assert!(close(c.evaluate("pct(1 + i, 4)").unwrap(), 25.0, 25.0));
+    assert!(close(c.evaluate("applypct(50, 2*i)").unwrap(), 0.0, 1.0));
+    assert!(close(c.evaluate("root(2, -4)").unwrap(), 0.0, 2.0));
+    assert!(c.evaluate("root(0, 2)").is_err());
+    assert!(close(c.evaluate("in2cm(1 + i)").unwrap(), 2.54, 2.54));
+    assert!(close(c.evaluate("rad2deg(pi*i)").unwrap(), 0.0, 180.0));
+    // Sin orden ni división entera en ℂ: error explícito
+    for e in ["atan2(i, 1)", "min(i, 2)", "max(1, i)", "mod(5*i, 3)", "5*i % 3", "mcd(4*i, 2)", "mcm(4, 2*i)"] {
+        assert!(c.evaluate(e).unwrap_err().contains("requiere argumentos reales"), "{}", e);
+    }
+    // Las derivadas siguen las mismas reglas
+    let d = c.derivative("log10(x)", "x", 1.0, 1, None).unwrap();
+    assert!(approx(d.value, 1.0 / ln10, 1e-12));
+    let d = c.derivative("root(3, x)", "x", 8.0, 2, None).unwrap();
+    assert!(approx(d.value, -2.0 / 9.0 * 8f64.powf(-5.0 / 3.0), 1e-10));
+}
+
+#[test]
+fn real_mode() {
+    let mut c = Calculator::new();
+    // La raíz cúbica de un real es real en cualquier modo
+    assert!(approx(c.evaluate("cbrt(-8)").unwrap(), -2.0, 1e-15));
+    assert_eq!(c.evaluate("(-2)^3").unwrap(), Complex64::new(-8.0, 0.0));
+    assert_eq!(c.evaluate("sqrt(-4)").unwrap(), Complex64::new(0.0, 2.0));
+    c.real = true;
+    for (e, call) in [("sqrt(-4)", "sqrt(-4)"), ("asin(2)", "asin(2)"), ("ln(-1)", "ln(-1)"), ("(-8)^0.5", "-8 ^ 0.5"), ("root(2, -8)", "root(2, -8)")] {
+        let err = c.evaluate(e).unwrap_err();
+        assert!(err.contains("dominio") && err.contains(call), "{}: {}", e, err);
+    }
+    assert!(approx(c.evaluate("root(3, -8)").unwrap(), -2.0, 1e-15));
+    assert!(approx(c.evaluate("root(5, -32)").unwrap(), -2.0, 1e-15));
+    assert!(approx(c.evaluate("acos(0.5)").unwrap(), std::f64::consts::FRAC_PI_3, 1e-15));
+    assert!(approx(c.evaluate("(-2)^-1").unwrap(), -0.5, 1e-15));
+    // Un complejo explícito no es un error: solo lo son los que surgen de reales
+    assert_eq!(c.evaluate("2*i").unwrap(), Complex64::new(0.0, 2.0));
+    let d = c.derivative("cbrt(x)", "x", -8.0, 1, None).unwrap();
+    assert!(approx(d.value, 1.0 / 12.0, 1e-12));
+    c.reset();
+    assert!(!c.real);
+}
@@ -3,6 +3,8 @@ use num_complex::Complex64;
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(Complex64),
+    /// Literal entero que f64 no representa exactamente (|n| > 2^53).
+    Int(i128),
     Op(char),
     LParen,
     RParen,
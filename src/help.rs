@@ -5,45 +5,81 @@ pub fn show_help() {
     
     println!("{}", "\nOperaciones y Constantes:".green().bold());
     println!("  Operadores : +, -, *, /, ^, %");
-    println!("  Constantes : pi, e, phi/golden, tau, c");
+    println!("  Constantes : pi, e, phi/golden, tau, c, inf");
     
     println!("{}", "\nComandos Básicos:".green().bold());
-    println!("  {:<35} : {}", "Comando <expr>".cyan(), "Evalúa la expresión");
-    println!("  {:<35} : {}", "var = <expr>".cyan(), "Guarda una variable");
-    println!("  {:<35} : {}", "mode".cyan(), "Alterna entre RAD y DEG");
-    println!("  {:<35} : {}", "fmt".cyan(), "Alterna formato (Decimal / Científico)");
-    println!("  {:<35} : {}", "new".cyan(), "Reinicia la calculadora (borra vars)");
-    println!("  {:<35} : {}", "exit / quit".cyan(), "Salir del programa");
+    println!("  {:<35} : Evalúa la expresión", "Comando <expr>".cyan());
+    println!("  {:<35} : Guarda una variable", "var = <expr>".cyan());
+    println!("  {:<35} : Alterna entre RAD y DEG o fija la unidad angular", "mode [rad|deg|grad]".cyan());
+    println!("  {:<35} : Alterna formato (Decimal / Científico)", "fmt".cyan());
+    println!("  {:<35} : Decimales fijos, cifras significativas, ingeniería, SI", "fmt fix N|sig N|eng|si|dec|sci".cyan());
+    println!("  {:<35} : Separa los miles con espacios", "fmt group [on|off]".cyan());
+    println!("  {:<35} : Muestra los resultados en base N (2..36)", "base N".cyan());
+    println!("  {:<35} : Modo programador: enteros de C (i32, u8 sat, ...)", "prog <tipo> [wrap|sat] / prog off".cyan());
+    println!("  {:<35} : Incertidumbre de una medida a ± σ (por defecto, ans)", "unc [expr]".cyan());
+    println!("  {:<35} : Modo real estricto: error en vez de resultados complejos", "real [on|off]".cyan());
+    println!("  {:<35} : Sugiere formas cerradas (π/4, √2, ln 2, ...)", "identify [expr] [tol=t] / on|off".cyan());
+    println!("  {:<35} : Muestra los complejos como a + bi, r∠θ o r·e^(iθ)", "complex [rect|polar|exp]".cyan());
+    println!("  {:<35} : Reinicia la calculadora (borra vars)", "new".cyan());
+    println!("  {:<35} : Salir del programa", "exit / quit".cyan());
 
     println!("{}", "\nFunciones Matemáticas:".green().bold());
-    println!("  {:<15} : {}", "1 Argumento".blue(), "sin, cos, tan, asin, acos, atan, sinh, cosh, tanh");
-    println!("  {:<15}   {}", "".blue(), "exp, ln, log10, log2, sqrt, cbrt, abs, sign");
-    println!("  {:<15}   {}", "".blue(), "floor, ceil, round, trunc, fact, isprime, nextprime");
-    println!("  {:<15}   {}", "".blue(), "deg2rad, rad2deg, cm2in, in2cm, m2ft, ft2m");
-    println!("  {:<15} : {}", "2 Argumentos".blue(), "root(n,x), log(b,n), pow(b,e), hypot(x,y), atan2(y,x)");
-    println!("  {:<15}   {}", "".blue(), "min, max, mod, mcd, mcm, comb, perm, rand, pct, applypct");
-    println!("  {:<15} : {}", "3 Argumentos".blue(), "r3d(a,b,c), r3i(a,b,c) (Reglas de tres)");
+    println!("  {:<15} : sin, cos, tan, asin, acos, atan, sinh, cosh, tanh", "1 Argumento".blue());
+    println!("  {:<15}   exp, ln, log10, log2, sqrt, cbrt, abs, sign", "".blue());
+    println!("  {:<15}   floor, ceil, round, trunc, fact, isprime, nextprime", "".blue());
+    println!("  {:<15}   prevprime, primepi, nthprime, totient, moebius, isperfect, sigma(n[,k])", "".blue());
+    println!("  {:<15}   deg2rad, rad2deg, cm2in, in2cm, m2ft, ft2m", "".blue());
+    println!("  {:<15} : root(n,x), log(b,n), pow(b,e), hypot(x,y), atan2(y,x)", "2 Argumentos".blue());
+    println!("  {:<15}   min, max, mod, mcd, mcm, comb, perm, rand, pct, applypct", "".blue());
+    println!("  {:<15}   invmod(a,m), jacobi(a,n), legendre(a,p), sqrtmod(a,p)", "".blue());
+    println!("  {:<15} : r3d(a,b,c), r3i(a,b,c) (Reglas de tres), powmod(a,b,m)", "3 Argumentos".blue());
+    println!("  {:<15} : sum(k,a,b,expr), prod(k,a,b,expr) (b puede ser inf)", "Series".blue());
+    println!("  {:<15} : mean, median, moda, var, std, iqr, skew, kurt, gmean... (n args)", "Estadística".blue());
+    println!("  {:<15} : <dist>pdf/pmf, <dist>cdf, <dist>inv para norm, t, chi2, f, exp, unif,", "Distribuciones".blue());
+    println!("  {:<15}   binom, poiss, geom, hyge. Ej: normcdf(x, mu, sigma), tinv(p, df)", "".blue());
+    println!("  {:<15} : rand(), randint(a,b), randn(), choice(...), <dist>rnd(...)", "Aleatorias".blue());
+    println!("  {:<15} : pv, fv, pmt, nper, rate (tasa, ..., [tipo]), npv(tasa, v1, ...), irr(v0, v1, ...)", "Finanzas".blue());
+    println!("  {:<15}   xirr(fecha1, v1, ...), date(a, m, d), effect(tasa, m), nominal(tasa, m)", "".blue());
+    println!("  {:<15} : catalan, bell, partitions, derangements, fib, lucas, multinomial(...)", "Combinatoria".blue());
+    println!("  {:<15}   stirling1(n,k), stirling2(n,k) (resultados enteros exactos)", "".blue());
+    println!("  {:<15} : gamma, lgamma, digamma, beta(a,b), zeta, erf, erfc, erfinv, lambertw([k,] x)", "Especiales".blue());
+    println!("  {:<15}   besselj/bessely/besseli/besselk(nu,x), airy([k,] x), ellipk(m), ellipe(m)", "".blue());
 
     println!("{}", "\nNúmeros Complejos y Bases:".green().bold());
-    println!("  {:<15} : {}", "Complejos".magenta(), "abs, arg, conj, re, im");
-    println!("  {:<15} : {}", "Bases".magenta(), "bin(n), oct(n), hex(n)");
+    println!("  {:<15} : abs, arg, conj, re, im, cis(θ) ; forma polar r∠θ (θ en la unidad activa)", "Complejos".magenta());
+    println!("  {:<15} : bin(n), oct(n), hex(n)", "Bases".magenta());
 
     println!("{}", "\nGestión de Pila (Stack):".green().bold());
-    println!("  {:<35} : {}", "push <expr> ...".cyan(), "Añade valores a la pila");
-    println!("  {:<35} : {}", "pop / dup / swap".cyan(), "Manipula el último valor");
-    println!("  {:<35} : {}", "clearstack".cyan(), "Vacía la pila");
-    println!("  {:<35} : {}", "mem".cyan(), "Muestra el contenido de la pila");
-    println!("  {:<35} : {}", "sum / avg / min / max / std".cyan(), "Estadística sobre la pila");
+    println!("  {:<35} : Añade valores a la pila", "push <expr> ...".cyan());
+    println!("  {:<35} : Manipula el último valor", "pop / dup / swap".cyan());
+    println!("  {:<35} : Vacía la pila", "clearstack".cyan());
+    println!("  {:<35} : Muestra el contenido de la pila", "mem".cyan());
+    println!("  {:<35} : Estadística sobre la pila", "sum / avg / min / max / std".cyan());
+    println!("  {:<35} : Resumen descriptivo completo de la pila", "stats".cyan());
+    println!("  {:<35} : Pila de pares (x, y) para ajustes", "pushxy x y ... / popxy / memxy / clearxy".cyan());
+    println!("  {:<35} : Ajuste por mínimos cuadrados a los pares", "fit linear|poly n|exp|power|log".cyan());
+    println!("  {:<35} : Un estadístico de la pila (o función)", "mean / median / moda / var / q1 ...".cyan());
+    println!("  {:<35} : Apila n muestras de una expresión aleatoria", "sample <n> <expr>".cyan());
+    println!("  {:<35} : VAN / TIR de la pila; TIR con fechas de los pares", "npv <tasa> / irr / xirr".cyan());
+    println!("  {:<35} : Cuadro de amortización mensual [csv <fichero>]", "amort <capital> <tipo> <años> [extra]".cyan());
+    println!("  {:<35} : Semilla del generador aleatorio", "seed [n]".cyan());
 
     println!("{}", "\nHistorial y Herramientas:".green().bold());
-    println!("  {:<35} : {}", "hist / clear".cyan(), "Ver / Borrar historial");
-    println!("  {:<35} : {}", "!! / !N".cyan(), "Repetir última exp / línea N");
-    println!("  {:<35} : {}", "last / ans".cyan(), "Usar el último resultado");
-    println!("  {:<35} : {}", "plot <exprs> ...".cyan(), "Graficar funciones, Ejem: plot sin(x)");
-    println!("  {:<35} : {}", "integ <expr> ...".cyan(), "Integración numérica");
-    println!("  {:<35} : {}", "deriv <expr> ...".cyan(), "Derivada numérica");
-    println!("  {:<35} : {}", "solve <expr> <guess>".cyan(), "Resolver ecuación (Newton)");
-    println!("  {:<35} : {}", "ayuda <cmd>".cyan(), "Ayuda específica (ej: ayuda sin)");
+    println!("  {:<35} : Ver / Borrar historial", "hist / clear".cyan());
+    println!("  {:<35} : Repetir última exp / línea N", "!! / !N".cyan());
+    println!("  {:<35} : Usar el último resultado", "last / ans".cyan());
+    println!("  {:<35} : Graficar funciones, Ejem: plot sin(x)", "plot <exprs> ...".cyan());
+    println!("  {:<35} : Integración numérica", "integ <expr> ...".cyan());
+    println!("  {:<35} : Derivada numérica (orden 1-4, gradiente, Hessiana)", "deriv [n:] <expr> ...".cyan());
+    println!("  {:<35} : Límite (lateral con a+ / a-, o en ±inf)", "limit <expr> x->a".cyan());
+    println!("  {:<35} : Desarrollo de Taylor de orden n", "taylor <expr> x=a [n]".cyan());
+    println!("  {:<35} : Resolver ecuación (Newton)", "solve <expr> <guess>".cyan());
+    println!("  {:<35} : Factorización en primos / divisores", "factor <n> / divisors <n>".cyan());
+    println!("  {:<35} : Apila los primos del intervalo [a, b]", "primes <a> <b>".cyan());
+    println!("  {:<35} : Bézout / teorema chino del resto", "egcd a b / crt([r..], [m..])".cyan());
+    println!("  {:<35} : Aritmética modular (+ - * / ^ mod n)", "modulo <n> / modulo off".cyan());
+    println!("  {:<35} : EDO con RK45, Ejem: ode y' = -y, y(0)=1, t=0..5", "ode y' = <expr>, y(t0)=.., t=a..b".cyan());
+    println!("  {:<35} : Ayuda específica (ej: ayuda sin)", "ayuda <cmd>".cyan());
     println!();
 }
 
@@ -52,17 +88,17 @@ pub fn show_specific_help(cmd: &str) {
         // --- Funciones (1 arg) ---
         "exp" => ("exp(x)", "Calcula e elevado a la potencia x. Ej: exp(1) -> 2.718"),
         "sqrt" => ("sqrt(x)", "Raíz cuadrada. Ej: sqrt(16) -> 4"),
-        "cbrt" => ("cbrt(x)", "Raíz cúbica. Ej: cbrt(27) -> 3"),
+        "cbrt" => ("cbrt(x)", "Raíz cúbica (la real para reales). Ej: cbrt(27) -> 3 ; cbrt(-8) -> -2"),
         "ln" => ("ln(x)", "Logaritmo natural (base e). Ej: ln(e) -> 1"),
         "abs" => ("abs(x)", "Valor absoluto o módulo de un complejo. Ej: abs(-5) -> 5"),
-        "floor" => ("floor(x)", "Redondea hacia abajo al entero más cercano. Ej: floor(3.9) -> 3"),
+        "floor" => ("floor(x)", "Redondea hacia abajo al entero más cercano (con complejos, cada parte). Ej: floor(3.9) -> 3"),
         "ceil" => ("ceil(x)", "Redondea hacia arriba al entero más cercano. Ej: ceil(3.1) -> 4"),
         "round" => ("round(x)", "Redondea al entero más próximo. Ej: round(3.5) -> 4"),
         "trunc" => ("trunc(x)", "Elimina la parte decimal. Ej: trunc(-3.7) -> -3"),
-        "sign" => ("sign(x)", "Retorna el signo del número (1, -1 o 0). Ej: sign(-10) -> -1"),
-        "sin" => ("sin(x)", "Seno (según modo RAD/DEG). Ej: sin(pi/2) -> 1"),
-        "cos" => ("cos(x)", "Coseno (según modo RAD/DEG). Ej: cos(0) -> 1"),
-        "tan" => ("tan(x)", "Tangente (según modo RAD/DEG). Ej: tan(pi/4) -> 1"),
+        "sign" => ("sign(x)", "Retorna el signo del número (1, -1 o 0); con complejos, z/|z|. Ej: sign(-10) -> -1"),
+        "sin" => ("sin(x)", "Seno (según el modo RAD/DEG/GRAD). Ej: sin(pi/2) -> 1 ; sin(30°) -> 0.5 en cualquier modo"),
+        "cos" => ("cos(x)", "Coseno (según el modo RAD/DEG/GRAD). Ej: cos(0) -> 1"),
+        "tan" => ("tan(x)", "Tangente (según el modo RAD/DEG/GRAD). Ej: tan(pi/4) -> 1"),
         "asin" => ("asin(x)", "Arcoseno. Ej: asin(1) -> 1.57 (rad)"),
         "acos" => ("acos(x)", "Arcocoseno. Ej: acos(0) -> 1.57 (rad)"),
         "atan" => ("atan(x)", "Arcotangente. Ej: atan(1) -> 0.78 (rad)"),
@@ -72,32 +108,80 @@ pub fn show_specific_help(cmd: &str) {
         "asinh" => ("asinh(x)", "Arcoseno hiperbólico."),
         "acosh" => ("acosh(x)", "Arcocoseno hiperbólico."),
         "atanh" => ("atanh(x)", "Arcotangente hiperbólica."),
+        "mode" => ("mode [rad | deg | grad]", "Unidad angular de sin/cos/tan/cis/∠ y de los resultados de asin/acos/atan/atan2.\nSin argumento alterna entre RAD y DEG. GRAD: gradianes (400 por vuelta)."),
+        "°" | "deg" | "rad" | "grad" => ("30° ; 12°30'15\" ; 1rad ; 50grad ; 30deg", "Sufijos de unidad angular para un literal: mandan sobre el modo activo solo para ese número.\nCon ° se admiten minutos (') y segundos (\"). deg(x), rad(x) y grad(x) hacen lo mismo con\ncualquier expresión. Ej (RAD): sin(30°) -> 0.5 ; (DEG): cos(1rad) -> 0.5403"),
+        "dms" => ("dms(x)", "Devuelve x; si es la llamada exterior, el ángulo (en la unidad activa) se muestra en\ngrados, minutos y segundos. Ej (DEG): dms(12.5041667) -> 12°30'15\" ; (RAD): dms(1) -> 57°17'44.81\""),
         "deg2rad" => ("deg2rad(x)", "Convierte grados a radianes. Ej: deg2rad(180) -> 3.1415"),
         "rad2deg" => ("rad2deg(x)", "Convierte radianes a grados. Ej: rad2deg(pi) -> 180"),
         "cm2in" => ("cm2in(x)", "Centímetros a pulgadas. Ej: cm2in(2.54) -> 1"),
         "in2cm" => ("in2cm(x)", "Pulgadas a centímetros. Ej: in2cm(1) -> 2.54"),
         "m2ft" => ("m2ft(x)", "Metros a pies. Ej: m2ft(1) -> 3.28"),
         "ft2m" => ("ft2m(x)", "Pies a metros. Ej: ft2m(3.28) -> 1"),
-        "fact" => ("fact(n)", "Factorial de n. Ej: fact(5) -> 120"),
-        "log10" => ("log10(x)", "Logaritmo base 10. Ej: log10(100) -> 2"),
-        "log2" => ("log2(x)", "Logaritmo base 2. Ej: log2(8) -> 3"),
-        "isprime" => ("isprime(n)", "1 si es primo, 0 si no. Ej: isprime(7) -> 1"),
+        "fact" => ("fact(n)", "Factorial de n: entero exacto con todas sus cifras (hasta unas 19700) y Γ(n+1)\npara no enteros y complejos. Ej: fact(25) -> 15511210043330985984000000 ; fact(0.5) -> 0.8862"),
+        "log10" => ("log10(x)", "Logaritmo base 10 (rama principal con complejos). Ej: log10(100) -> 2"),
+        "log2" => ("log2(x)", "Logaritmo base 2 (rama principal con complejos). Ej: log2(8) -> 3"),
+        "isprime" => ("isprime(n)", "1 si es primo, 0 si no (Miller-Rabin determinista; BPSW por encima de 3.3e24).\nCon enteros la expresión se evalúa exacta, sin pasar por f64. Ej: isprime(2^61-1) -> 1"),
         "nextprime" => ("nextprime(n)", "Encuentra el siguiente número primo. Ej: nextprime(8) -> 11"),
+        "prevprime" => ("prevprime(n)", "Mayor primo menor que n. Ej: prevprime(100) -> 97"),
+        "primepi" => ("primepi(n)", "π(n): cuántos primos hay <= n (algoritmo de Lucy Hedgehog, n <= 10^12).\nEj: primepi(10^10) -> 455052511"),
+        "nthprime" => ("nthprime(k)", "k-ésimo primo (hasta 10^12). Ej: nthprime(1000) -> 7919"),
+        "primes" => ("primes <a> <b>", "Lista los primos de [a, b] (criba segmentada, b - a <= 10^8) y los apila.\nEj: primes 1 30 -> 2, 3, 5, ..., 29"),
+        "totient" => ("totient(n)", "Función φ de Euler: enteros en [1, n] coprimos con n. Ej: totient(36) -> 12"),
+        "sigma" => ("sigma(n) | sigma(n, k)", "Suma de las potencias k-ésimas de los divisores (k = 1 por defecto; k = 0 los cuenta).\nEj: sigma(12) -> 28 ; sigma(12, 0) -> 6"),
+        "moebius" => ("moebius(n)", "Función de Möbius: 0 si n tiene un factor cuadrado, (-1)^k con k factores primos si no.\nEj: moebius(30) -> -1"),
+        "isperfect" => ("isperfect(n)", "1 si n es perfecto (igual a la suma de sus divisores propios). Ej: isperfect(28) -> 1"),
+        "factor" => ("factor <n>", "Factorización en primos con división de prueba y rho de Pollard (Brent), exacta para\nenteros de hasta 128 bits escritos como expresión. Ej: factor 360 -> 360 = 2^3 * 3^2 * 5"),
+        "powmod" => ("powmod(a, b, m)", "a^b mod m exacto (con b < 0 usa el inverso de a). Ej: powmod(2, 10^18, 10^9+7) -> 719476260"),
+        "invmod" => ("invmod(a, m)", "Inverso de a módulo m (requiere mcd(a, m) = 1). Ej: invmod(3, 97) -> 65"),
+        "jacobi" => ("jacobi(a, n)", "Símbolo de Jacobi (a/n) para n impar positivo: -1, 0 o 1. Ej: jacobi(2, 15) -> 1"),
+        "legendre" => ("legendre(a, p)", "Símbolo de Legendre (a/p) para p primo impar: 1 si a es residuo cuadrático,\n-1 si no, 0 si p divide a. Ej: legendre(2, 7) -> 1"),
+        "sqrtmod" => ("sqrtmod(a, p)", "Menor x con x^2 ≡ a (mod p), p primo (Tonelli-Shanks); la otra raíz es p - x.\nEj: sqrtmod(10, 13) -> 6"),
+        "egcd" => ("egcd <a> <b>", "Euclides extendido: mcd(a, b) = a*x + b*y. Apila g, x, y.\nEj: egcd 240 46 -> mcd(240, 46) = 2 = 240*(-9) + 46*(47)"),
+        "crt" => ("crt([r1, r2, ...], [m1, m2, ...])", "Teorema chino del resto: x ≡ ri (mod mi); admite módulos no coprimos si el\nsistema es compatible. Ej: crt([2,3,2], [3,5,7]) -> x ≡ 23 (mod 105)"),
+        "modulo" => ("modulo <n> | modulo off", "Modo de aritmética modular: cada + - * / ^ se reduce mod n (la división usa el\ninverso; a^-1 también). Solo admite enteros. Ej: modulo 97 ; 3^-1 -> 65"),
+        "divisors" => ("divisors <n>", "Lista los divisores positivos de n y los apila. Ej: divisors 12 -> 1, 2, 3, 4, 6, 12"),
 
         // --- Funciones (2 args) ---
-        "atan2" => ("atan2(y, x)", "Arcotangente de dos variables."),
-        "hypot" => ("hypot(x, y)", "Hipotenusa (sqrt(x²+y²)). Ej: hypot(3, 4) -> 5"),
-        "root" => ("root(n, x)", "Raíz n-ésima de x. Ej: root(3, 8) -> 2"),
+        "atan2" => ("atan2(y, x)", "Arcotangente de dos variables (solo reales)."),
+        "hypot" => ("hypot(x, y)", "Hipotenusa (sqrt(|x|²+|y|²)). Ej: hypot(3, 4) -> 5"),
+        "root" => ("root(n, x)", "Raíz n-ésima de x (principal: x^(1/n)). Ej: root(3, 8) -> 2"),
         "log" => ("log(base, n)", "Logaritmo en base específica. Ej: log(3, 9) -> 2"),
         "mcd" => ("mcd(a, b)", "Máximo Común Divisor. Ej: mcd(12, 18) -> 6"),
         "mcm" => ("mcm(a, b)", "Mínimo Común Múltiplo. Ej: mcm(4, 6) -> 12"),
-        "comb" | "nCr" => ("comb(n, k)", "Combinaciones de n en k. Ej: comb(5, 2) -> 10"),
-        "perm" | "nPr" => ("perm(n, k)", "Permutaciones de n en k. Ej: perm(5, 2) -> 20"),
+        "comb" | "nCr" => ("comb(n, k)", "Combinaciones de n en k, exactas con enteros. n puede ser negativo o real\n(vía Γ). Ej: comb(5, 2) -> 10 ; comb(-3, 2) -> 6 ; comb(2.5, 2) -> 1.875"),
+        "perm" | "nPr" => ("perm(n, k)", "Permutaciones de n en k (exactas). Ej: perm(5, 2) -> 20"),
+        "pv" => ("pv(tasa, nper, pago, [vf], [tipo])", "Valor actual (VA de Excel). Lo que se paga es negativo; tipo 0 = pagos al final\ndel periodo (defecto), 1 = al principio. Ej: pv(0.08/12, 240, 500) -> -59777.15"),
+        "fv" => ("fv(tasa, nper, pago, [va], [tipo])", "Valor futuro (VF de Excel). Ej: fv(0.06/12, 10, -200, -500, 1) -> 2581.40"),
+        "pmt" => ("pmt(tasa, nper, va, [vf], [tipo])", "Pago periódico de un préstamo (PAGO de Excel).\nEj: pmt(0.05/12, 360, 200000) -> -1073.64"),
+        "nper" => ("nper(tasa, pago, va, [vf], [tipo])", "Número de periodos (NPER de Excel). Ej: nper(0.01, -100, -1000, 10000, 1) -> 59.67"),
+        "rate" => ("rate(nper, pago, va, [vf], [tipo], [estimación])", "Tasa por periodo, resuelta por Newton (TASA de Excel).\nEj: rate(48, -200, 8000) -> 0.0077"),
+        "npv" => ("npv(tasa, v1, v2, ...) | npv <tasa>", "Valor actual neto: el primer flujo se descuenta un periodo, como VNA de Excel.\nCon 'npv <tasa>' usa los flujos de la pila. Ej: npv(0.1, -10000, 3000, 4200, 6800) -> 1188.44"),
+        "irr" => ("irr(v0, v1, ...) | irr", "Tasa interna de retorno (TIR de Excel); sin argumentos, de los flujos de la pila.\nEj: irr(-70000, 12000, 15000, 18000, 21000, 26000) -> 0.0866"),
+        "xirr" => ("xirr(fecha1, v1, fecha2, v2, ...) | xirr", "TIR con fechas (TIR.NO.PER de Excel, años de 365 días). Sin argumentos usa la\npila de pares: pushxy date(2024,1,15) -1000 ..."),
+        "amort" => ("amort <capital> <tipo_anual> <años> [extra] [csv <fichero>]", "Cuadro de amortización con cuota mensual fija: periodo, cuota, intereses,\namortización y saldo, con totales. 'extra' se amortiza cada mes y acorta el plazo.\nLos importes se muestran con 2 decimales (o con el formato elegido con fmt); se pagina\nen el terminal y con 'csv' se exporta con precisión completa.\nEj: amort 200000 0.05 30 100 csv prestamo.csv"),
+        "date" => ("date(año, mes, día)", "Número de serie de Excel de una fecha (días desde 30/12/1899). Ej: date(2008, 1, 1) -> 39448"),
+        "effect" => ("effect(tasa_nominal, periodos)", "Tasa efectiva anual (INT.EFECTIVO de Excel). Ej: effect(0.0525, 4) -> 0.053543"),
+        "nominal" => ("nominal(tasa_efectiva, periodos)", "Tasa nominal anual (TASA.NOMINAL de Excel). Ej: nominal(0.053543, 4) -> 0.0525"),
+        "catalan" => ("catalan(n)", "Número de Catalan C(2n, n)/(n+1). Ej: catalan(10) -> 16796"),
+        "stirling1" => ("stirling1(n, k)", "Número de Stirling de primera especie (con signo). Ej: stirling1(5, 2) -> -50"),
+        "stirling2" => ("stirling2(n, k)", "Número de Stirling de segunda especie: particiones de n elementos en k\nbloques. Ej: stirling2(5, 2) -> 15"),
+        "bell" => ("bell(n)", "Número de Bell: particiones de un conjunto de n elementos. Ej: bell(5) -> 52"),
+        "partitions" => ("partitions(n)", "p(n): particiones del entero n (exactas hasta n = 20000; después, Rademacher).\nEj: partitions(100) -> 190569292"),
+        "multinomial" => ("multinomial(k1, k2, ...)", "Coeficiente multinomial (k1+k2+...)!/(k1!·k2!·...). Ej: multinomial(2, 3, 4) -> 1260"),
+        "derangements" => ("derangements(n)", "Desarreglos: permutaciones sin puntos fijos. Ej: derangements(5) -> 44"),
+        "fib" => ("fib(n)", "Número de Fibonacci (admite n negativo). Ej: fib(90) -> 2880067194370816120"),
+        "lucas" => ("lucas(n)", "Número de Lucas L(n) = F(n-1) + F(n+1). Ej: lucas(10) -> 123"),
         "pow" => ("pow(base, exp)", "Potencia. Ej: pow(2, 10) -> 1024"),
-        "min" => ("min(a, b)", "El menor de dos valores. Ej: min(5, 3) -> 3"),
-        "max" => ("max(a, b)", "El mayor de dos valores. Ej: max(5, 3) -> 5"),
-        "mod" => ("mod(a, b)", "Residuo de la división (módulo). Ej: mod(10, 3) -> 1"),
-        "rand" => ("rand(min, max)", "Número aleatorio entre min y max."),
+        "min" => ("min(a, b)", "El menor de dos valores reales. Ej: min(5, 3) -> 3"),
+        "max" => ("max(a, b)", "El mayor de dos valores reales. Ej: max(5, 3) -> 5"),
+        "mod" => ("mod(a, b)", "Residuo de la división (módulo) de reales. Ej: mod(10, 3) -> 1"),
+        "rand" => ("rand() | rand(min, max)", "Uniforme en [0, 1) o en [min, max). La secuencia se fija con 'seed <n>'."),
+        "randint" => ("randint(a, b)", "Entero uniforme en [a, b] (ambos incluidos)."),
+        "randn" => ("randn() | randn(mu, sigma)", "Normal (por defecto la estándar)."),
+        "choice" => ("choice(x1, x2, ...)", "Uno de los argumentos al azar, con igual probabilidad."),
+        "seed" => ("seed [n]", "Fija la semilla del generador aleatorio (xoshiro256**): la misma semilla\nreproduce la misma secuencia. Sin argumento muestra la semilla actual."),
+        "sample" => ("sample <n> <expr>", "Evalúa n veces una expresión aleatoria y apila los resultados (para stats, mean...).\nEj: seed 1 ; sample 1000 normrnd(10, 2) ; stats"),
+        "normrnd" | "trnd" | "chi2rnd" | "frnd" | "exprnd" | "unifrnd" | "binomrnd" | "poissrnd" | "geomrnd" | "hygernd" => ("<dist>rnd(parámetros)", "Muestra aleatoria de una distribución, con los mismos parámetros que\n<dist>pdf/<dist>cdf. Ej: normrnd(0, 1), binomrnd(10, 0.3), poissrnd(4)"),
         "pct" => ("pct(parte, total)", "Porcentaje que representa la parte. Ej: pct(10, 50) -> 20"),
         "applypct" => ("applypct(%, val)", "Aplica un porcentaje a un valor. Ej: applypct(20, 100) -> 20"),
 
@@ -105,20 +189,76 @@ pub fn show_specific_help(cmd: &str) {
         "r3d" => ("r3d(a, b, c)", "Regla de tres directa (c*b/a)."),
         "r3i" => ("r3i(a, b, c)", "Regla de tres inversa (a*b/c)."),
 
+        // --- Estadística (n argumentos; como comando usan la pila) ---
+        "stats" => ("stats", "Resumen de la pila: n, media, mediana, moda, mínimo/máximo, cuartiles e IQR,\nvarianza y desviación típica (muestral y poblacional), asimetría, curtosis en exceso,\nmedias geométrica y armónica. Cada estadístico es también un comando suelto\n(mean, median, moda, var, varp, std, stdp, q1, q3, iqr, skew, kurt, gmean, hmean, count)."),
+        "mean" | "count" => ("mean(x1, x2, ...) | count(...)", "Media aritmética / número de datos. Ej: mean(1, 2, 6) -> 3"),
+        "median" => ("median(x1, x2, ...)", "Mediana. Ej: median(3, 1, 2, 10) -> 2.5"),
+        "moda" => ("moda(x1, x2, ...)", "Valor más frecuente (el menor si hay empate). Como comando lista todas las modas."),
+        "var" | "varp" => ("var(...) | varp(...)", "Varianza muestral (n-1) y poblacional (n)."),
+        "std" | "stdp" => ("std(...) | stdp(...)", "Desviación típica muestral (n-1) y poblacional (n). Ej: stdp(2,4,4,4,5,5,7,9) -> 2"),
+        "quartile" | "iqr" | "q1" | "q3" => ("quartile(k, x1, x2, ...) | iqr(...)", "Cuartil k (0..4) con interpolación lineal (QUARTILE.INC de Excel) y rango\nintercuartílico. Los comandos q1 / q3 / iqr usan la pila.\nEj: quartile(1, 1, 2, 3, 4) -> 1.75"),
+        "skew" | "kurt" => ("skew(...) | kurt(...)", "Asimetría y curtosis en exceso muestrales (SKEW y KURT de Excel)."),
+        "gmean" | "hmean" => ("gmean(...) | hmean(...)", "Medias geométrica y armónica (datos positivos)."),
+
+        // --- Distribuciones (x o k primero, después los parámetros) ---
+        "normpdf" | "normcdf" | "norminv" => ("normpdf(x, [mu, sigma]) | normcdf(x, ...) | norminv(p, ...)", "Normal (por defecto la estándar, mu = 0 y sigma = 1).\nEj: normcdf(1.96) -> 0.975 ; norminv(0.975, 100, 15) -> 129.4"),
+        "tpdf" | "tcdf" | "tinv" => ("tpdf(x, df) | tcdf(x, df) | tinv(p, df)", "t de Student con df grados de libertad (df real > 0). Ej: tinv(0.975, 10) -> 2.228"),
+        "chi2pdf" | "chi2cdf" | "chi2inv" => ("chi2pdf(x, k) | chi2cdf(x, k) | chi2inv(p, k)", "Chi-cuadrado con k grados de libertad. Ej: chi2inv(0.95, 3) -> 7.815"),
+        "fpdf" | "fcdf" | "finv" => ("fpdf(x, d1, d2) | fcdf(x, d1, d2) | finv(p, d1, d2)", "F de Snedecor. Ej: finv(0.95, 2, 10) -> 4.103"),
+        "exppdf" | "expcdf" | "expinv" => ("exppdf(x, lambda) | expcdf(x, lambda) | expinv(p, lambda)", "Exponencial de tasa lambda (media 1/lambda)."),
+        "unifpdf" | "unifcdf" | "unifinv" => ("unifpdf(x, a, b) | unifcdf(x, a, b) | unifinv(p, a, b)", "Uniforme continua en [a, b]."),
+        "binompmf" | "binomcdf" | "binominv" => ("binompmf(k, n, p) | binomcdf(k, n, p) | binominv(q, n, p)", "Binomial: k éxitos en n ensayos. binominv da el menor k con P(X <= k) >= q.\nEj: binompmf(3, 10, 0.5) -> 0.1171875"),
+        "poisspmf" | "poisscdf" | "poissinv" => ("poisspmf(k, lambda) | poisscdf(k, lambda) | poissinv(q, lambda)", "Poisson de media lambda."),
+        "geompmf" | "geomcdf" | "geominv" => ("geompmf(k, p) | geomcdf(k, p) | geominv(q, p)", "Geométrica: k = número de ensayos hasta el primer éxito (k >= 1)."),
+        "hygepmf" | "hygecdf" | "hygeinv" => ("hygepmf(k, N, K, n) | hygecdf(...) | hygeinv(q, N, K, n)", "Hipergeométrica: k éxitos al extraer n sin reemplazo de N elementos con K éxitos."),
+
+        // --- Funciones especiales ---
+        "gamma" => ("gamma(z)", "Función Gamma (admite complejos). Γ(n) = (n-1)! en los enteros positivos.\nEj: gamma(0.5) -> 1.7725 (√π) ; gamma(1+i) -> 0.498 - 0.155i"),
+        "lgamma" => ("lgamma(z)", "ln|Γ(x)| para x real (útil cuando Γ desborda); rama principal de ln Γ(z) para complejos.\nEj: lgamma(1000) -> 5905.22"),
+        "digamma" => ("digamma(z)", "Función digamma ψ(z) = Γ'(z)/Γ(z). Ej: digamma(1) -> -0.5772 (−γ)"),
+        "beta" => ("beta(a, b)", "Función Beta B(a, b) = Γ(a)Γ(b)/Γ(a+b). Ej: beta(2, 3) -> 0.08333"),
+        "zeta" => ("zeta(s)", "Zeta de Riemann (admite complejos; polo en s = 1). Ej: zeta(2) -> 1.6449 (π²/6)"),
+        "erf" | "erfc" => ("erf(x) | erfc(x)", "Función de error y su complementaria 1 - erf(x), con precisión relativa en la cola.\nEj: erf(1) -> 0.8427 ; erfc(10) -> 2.088e-45"),
+        "erfinv" => ("erfinv(y)", "Inversa de erf en [-1, 1]. Ej: erfinv(0.5) -> 0.4769"),
+        "besselj" | "bessely" | "besseli" | "besselk" => ("besselj(nu, x) | bessely(nu, x) | besseli(nu, x) | besselk(nu, x)", "Funciones de Bessel J, Y y modificadas I, K de orden real nu. Y y K requieren x > 0;\nJ e I admiten x < 0 con orden entero.\nEj: besselj(1, 2) -> 0.5767 ; besselk(0.5, 1) -> 0.4611"),
+        "airy" => ("airy(x) | airy(k, x)", "Funciones de Airy: k = 0 Ai (por defecto), 1 Ai', 2 Bi, 3 Bi'.\nEj: airy(0) -> 0.3550 ; airy(2, 1) -> 1.2074"),
+        "lambertw" => ("lambertw(x) | lambertw(k, x)", "W de Lambert (w·e^w = x), rama k (0 por defecto). Para -1/e <= x < 0 las ramas\n0 y -1 son reales. Ej: lambertw(1) -> 0.5671 ; lambertw(-1, -0.1) -> -3.5772"),
+        "ellipk" | "ellipe" => ("ellipk(m) | ellipe(m)", "Integrales elípticas completas de primera y segunda especie, parámetro m = k² <= 1.\nEj: ellipk(0.5) -> 1.8541 ; ellipe(0.5) -> 1.3506"),
+
+        // --- Ajustes ---
+        "fit" => ("fit linear | poly <n> | exp | power | log  [nombre] [plot]", "Ajusta un modelo a los pares de pushxy: coeficientes con su error típico, R² y tabla de\nresiduos. El modelo queda como función (por defecto fit(x)) usable en expresiones, plot,\nderiv, etc. exp y power se refinan con Levenberg-Marquardt sobre los y originales.\nCon 'plot' dibuja los datos y la curva.\nEj: pushxy 1 2.1 2 3.9 3 6.2 ; fit linear ; fit(4)"),
+        "pushxy" | "popxy" | "memxy" | "clearxy" => ("pushxy x1 y1 [x2 y2 ...]", "Pila de pares (x, y) para fit. popxy quita el último, memxy los lista y clearxy la vacía."),
+
+        // --- Series ---
+        "sum" => ("sum(k, a, b, expr)", "Sumatorio de expr para k = a..b. El índice k es local (no modifica una variable k).\nCon b = inf suma hasta converger (las series alternadas se aceleran).\nEj: sum(k, 1, 100, k^2) -> 338350 ; 4*sum(k, 0, inf, (-1)^k/(2*k+1)) -> pi"),
+        "prod" => ("prod(k, a, b, expr)", "Productorio de expr para k = a..b (b puede ser inf).\nEj: prod(k, 2, 10, 1 - 1/k^2) -> 0.55"),
+
         // --- Comandos REPL ---
         "integ" => ("integ <expr> <min> <max> [steps]", "Calcula la integral definida numérica (Regla del Trapecio).\nEj: integ x^2 0 1 1000 -> 0.333..."),
-        "deriv" => ("deriv <expr> <x> [h]", "Calcula la derivada numérica (diferencia centrada) de una función en un punto.\nEj: deriv x^2 3 -> 6"),
+        "ode" => ("ode y' = <expr>, ..., y(t0)=<v>, ..., t=<t0>..<t1> [, rtol=1e-6, atol=1e-9, n=11]", "Integra un problema de valor inicial (o un sistema de primer orden) con\nRunge-Kutta adaptativo Dormand-Prince 5(4). Muestra una tabla de n filas, guarda el\nestado final en las variables y dibuja la trayectoria.\nEj: ode x' = y, y' = -x, x(0)=1, y(0)=0, t=0..2*pi"),
+        "limit" => ("limit <expr> x->a | x->a+ | x->a- | x->inf | x->-inf", "Límite numérico con extrapolación de Richardson y error estimado. Sin sufijo calcula\nambos lados y comprueba que coinciden; detecta la divergencia a ±∞.\nEj: limit sin(x)/x x->0 -> 1 ; limit 1/x x->0+ -> +∞ ; limit (1+1/x)^x x->inf -> e"),
+        "taylor" => ("taylor <expr> x=a [n]", "Coeficientes de Taylor hasta orden n (5 por defecto, máximo 40) y el polinomio como\nexpresión evaluable. Son exactos (aritmética de series) salvo que la expresión use algo\nsin regla de series; entonces se calculan con la integral de Cauchy (con interpolación de\nChebyshev en el eje real si solo admite argumentos reales) y su error.\nEj: taylor exp(x) x=0 4 -> 1 + x + 0.5*x^2 + ..."),
+        "deriv" => ("deriv [n:] <expr> <x> [h] | deriv [n:] <expr> x=1 y=2", "Derivada de orden n (1-4). La primera es exacta (números duales); las superiores usan\nextrapolación de Richardson con error estimado. Con h explícito se usan diferencias finitas.\nCon varias variables: n=1 da el gradiente y n=2 la Hessiana.\nEl orden va seguido de ':' (sin él, un número inicial es parte de la expresión).\nEj: deriv x^2 3 -> 6 ; deriv 2: x^3 2 -> 12 ; deriv 2*x^2 3 -> 12 ; deriv x^2*y x=1 y=2"),
 
         // --- Complejos ---
         "arg" => ("arg(z)", "Argumento (ángulo) de un número complejo."),
         "conj" => ("conj(z)", "Conjugado de un complejo (a+bi -> a-bi)."),
         "re" => ("re(z)", "Parte real de un complejo."),
         "im" => ("im(z)", "Parte imaginaria de un complejo."),
+        "cis" => ("cis(θ)", "cos θ + i·sin θ, con θ en la unidad angular activa. Ej (DEG): 10*cis(30) -> 8.66 + 5i"),
+        "∠" | "polar" => ("r∠θ", "Literal polar (fasor) r·(cos θ + i·sin θ), con θ en la unidad angular activa.\nLiga más que * y /: 2*5∠53.13 = 2*(5∠53.13). Ej (DEG): 5∠53.13 -> 3 + 4i"),
+        "fmt" => ("fmt [dec | sci | fix N | sig N | eng | si | group [on|off] | show]", "Formato de todos los resultados (REPL, pila, variables e historial):\n  dec: decimal exacto (defecto)   sci: científico con 6 decimales   fix N: N decimales\n  sig N: N cifras significativas   eng: exponente múltiplo de 3 (470e-6)   si: prefijos (4.7k, 22µ)\n  group: separa los miles con espacios (1 234 567.89); los espacios se ignoran al leer.\nSin argumentos alterna decimal / científico."),
+        "complex" => ("complex [rect | polar | exp]", "Forma de mostrar los complejos: rectangular a + bi (defecto), polar r∠θ\n(θ en la unidad de 'mode') o exponencial r·e^(iθ) (θ en radianes). Sin argumento, muestra la actual."),
 
         // --- Bases ---
-        "bin" => ("bin(n)", "Muestra n en binario (0b...). Ej: bin(10) -> 0b1010"),
-        "oct" => ("oct(n)", "Muestra n en octal (0o...). Ej: oct(10) -> 0o12"),
-        "hex" => ("hex(n)", "Muestra n en hexadecimal (0x...). Ej: hex(255) -> 0xff"),
+        "bin" => ("bin(n)", "Devuelve n; si es la llamada exterior, el resultado se muestra en binario.\nEj: bin(10) -> 0b1010 ; bin(-1) -> 64 unos (complemento a dos)"),
+        "oct" => ("oct(n)", "Devuelve n; si es la llamada exterior, el resultado se muestra en octal. Ej: oct(10) -> 0o12"),
+        "hex" => ("hex(n)", "Devuelve n; si es la llamada exterior, el resultado se muestra en hexadecimal.\nEj: hex(255) -> 0xff ; hex(2.75) -> 0x2.c"),
+        "prog" => ("prog <i|u><8|16|32|64|128> [wrap|sat] | prog off", "Modo programador: + - * / % ^ con enteros de C del tamaño indicado. El desbordamiento\nes circular (wrap, defecto) o saturado (sat); / trunca hacia cero y % lleva el signo del\ndividendo. Cada resultado se muestra en HEX, DEC, OCT y BIN. Admite literales 0x.., 0o.., 0b...\nEj: prog u8 ; 200 + 100 -> 44 ; prog i8 sat ; 100 + 100 -> 127"),
+        "±" | "+/-" | "unc" => ("a ± σ  (o a +/- σ) ; unc [expr]", "Medida con incertidumbre típica σ. Cada ± es una fuente de error independiente y se propaga\nlinealmente (primer orden) por todas las funciones: σ_f = √Σ(∂f/∂xₖ·σₖ)². Una misma medida\nusada dos veces está correlacionada consigo misma (g - g = 0). Liga más que + y menos que *.\nLa σ se muestra con 1 cifra significativa (2 si empieza por 1) y el valor redondeado igual.\n'unc' detalla σ, el error relativo y la cota en el peor caso Σ|∂f/∂xₖ|·σₖ.\nEj: g = 9.81 ± 0.02 ; L = 1.25 ± 0.005 ; 2*pi*sqrt(L/g) -> 2.243 ± 0.005"),
+        "identify" => ("identify [expr] [tol=t] | identify on|off", "Busca formas cerradas del valor (por defecto, ans): racionales pequeños (fracción continua),\nmúltiplos racionales de π, π², e y ln 2, raíces cuadradas, ln(p/q) y combinaciones (a + b·c)/d\ncon c en √2, √3, √5, π, e, ln 2 (relaciones enteras por PSLQ). Cada sugerencia muestra su error;\nse aceptan las que distan <= tol·|x| (tol relativa, 1e-12 por defecto).\n'identify on' sugiere la más simple tras cada resultado real no entero.\nEj: identify 0.7853981633974483 -> π/4 ; identify 1.618033988749895 -> (1 + √5)/2"),
+        "real" => ("real [on | off]", "Modo real estricto (sin argumento, alterna). Si una operación con argumentos reales da un\nresultado no real, es un error de dominio que nombra la función y el argumento.\nLas raíces de índice impar de negativos dan la raíz real. El prompt muestra ℝ.\nEj: real on ; sqrt(-4) -> Error de dominio: sqrt(-4) no es real ; root(3, -8) -> -2"),
+        "base" => ("base N", "Base de salida de los resultados (2..36; 10 = decimal, con el formato de 'fmt').\nLos negativos enteros se muestran en complemento a dos de 64 bits y los no enteros\ncon cifras fraccionarias en esa base ('…' si no terminan). Bases distintas de 2, 8, 16: N#cifras."),
 
         _ => ("", "Ayuda no disponible para este término. Usa 'help' para la lista general."),
     };
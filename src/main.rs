@@ -18,11 +18,7 @@ fn main() {
 
         match calc.evaluate(&input) {
             Ok(res) => {
-                if res.im == 0.0 {
-                    println!("{}", res.re);
-                } else {
-                    println!("{} {} {}i", res.re, if res.im >= 0.0 { "+" } else { "-" }, res.im.abs());
-                }
+                println!("{}", calc.format_result(res));
             }
             Err(e) => {
                 eprintln!("Error: {}", e);
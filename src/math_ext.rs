@@ -1,21 +1,237 @@
-pub fn is_prime_u128(n: u128) -> bool {
-    if n <= 1 {
+/// Primos pequeños: divisiones de prueba y bases de Miller–Rabin.
+const SMALL_PRIMES: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Por debajo de este valor, Miller–Rabin con las bases 2..41 es determinista
+/// (Sorenson y Webster, 2015).
+const MR_DETERMINISTIC_LIMIT: u128 = 3_317_044_064_679_887_385_961_981;
+
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (s, overflow) = a.overflowing_add(b);
+    if overflow || s >= m { s.wrapping_sub(m) } else { s }
+}
+
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b { a - b } else { a + (m - b) }
+}
+
+/// a·b mod m sin desbordar para cualquier m < 2^128.
+pub fn mul_mod_u128(a: u128, b: u128, m: u128) -> u128 {
+    if m <= u64::MAX as u128 {
+        return (a % m) * (b % m) % m;
+    }
+    let (mut a, mut b, mut r) = (a % m, b % m, 0u128);
+    while b > 0 {
+        if b & 1 == 1 {
+            r = add_mod(r, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    r
+}
+
+/// Producto completo de 256 bits como (alto, bajo).
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const LO: u128 = u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, a & LO, b >> 64, b & LO);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (p00 >> 64) + (p01 & LO) + (p10 & LO);
+    let lo = (p00 & LO) | (mid << 64);
+    let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+/// Aritmética de Montgomery (R = 2^128) para módulos impares de más de 64 bits,
+/// donde `mul_mod_u128` tendría que multiplicar bit a bit.
+struct Montgomery {
+    n: u128,
+    /// −n⁻¹ mod 2^128
+    neg_inv: u128,
+    /// R² mod n
+    r2: u128,
+}
+
+impl Montgomery {
+    fn new(n: u128) -> Self {
+        let mut inv = n;
+        for _ in 0..7 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(n.wrapping_mul(inv)));
+        }
+        let r1 = n.wrapping_neg() % n;
+        Montgomery { n, neg_inv: inv.wrapping_neg(), r2: mul_mod_u128(r1, r1, n) }
+    }
+
+    /// (hi·2^128 + lo)·R⁻¹ mod n, para hi < n.
+    fn redc(&self, hi: u128, lo: u128) -> u128 {
+        let m = lo.wrapping_mul(self.neg_inv);
+        let (th, tl) = mul_wide(m, self.n);
+        let carry = lo.overflowing_add(tl).1 as u128;
+        let (s, overflow) = hi.overflowing_add(th);
+        let (s, overflow2) = s.overflowing_add(carry);
+        if overflow || overflow2 || s >= self.n { s.wrapping_sub(self.n) } else { s }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> u128 {
+        let (hi, lo) = mul_wide(a, b);
+        self.redc(hi, lo)
+    }
+
+    fn enter(&self, a: u128) -> u128 {
+        self.mul(a % self.n, self.r2)
+    }
+
+    fn leave(&self, a: u128) -> u128 {
+        self.redc(0, a)
+    }
+}
+
+/// b^e mod m por cuadrados sucesivos.
+pub fn pow_mod_u128(b: u128, mut e: u128, m: u128) -> u128 {
+    if m == 1 {
+        return 0;
+    }
+    if m > u64::MAX as u128 && m % 2 == 1 {
+        let mont = Montgomery::new(m);
+        let (mut b, mut r) = (mont.enter(b), mont.enter(1));
+        while e > 0 {
+            if e & 1 == 1 {
+                r = mont.mul(r, b);
+            }
+            b = mont.mul(b, b);
+            e >>= 1;
+        }
+        return mont.leave(r);
+    }
+    let (mut b, mut r) = (b % m, 1u128);
+    while e > 0 {
+        if e & 1 == 1 {
+            r = mul_mod_u128(r, b, m);
+        }
+        b = mul_mod_u128(b, b, m);
+        e >>= 1;
+    }
+    r
+}
+
+/// ⌊√n⌋ exacta.
+pub fn isqrt_u128(n: u128) -> u128 {
+    let mut x = (n as f64).sqrt() as u128;
+    while x.checked_mul(x).is_none_or(|sq| sq > n) {
+        x -= 1;
+    }
+    while (x + 1).checked_mul(x + 1).is_some_and(|sq| sq <= n) {
+        x += 1;
+    }
+    x
+}
+
+/// Símbolo de Jacobi (a/n) para n impar positivo.
+pub fn jacobi_u128(a: u128, n: u128) -> i32 {
+    let (mut a, mut n, mut t) = (a % n, n, 1);
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if matches!(n % 8, 3 | 5) {
+                t = -t;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            t = -t;
+        }
+        a %= n;
+    }
+    if n == 1 { t } else { 0 }
+}
+
+/// Test fuerte de probable primo en base a (n impar > a).
+fn strong_probable_prime(n: u128, a: u128) -> bool {
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mut x = pow_mod_u128(a, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = pow_mod_u128(x, 2, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Test fuerte de Lucas con los parámetros de Selfridge (P = 1, Q = (1 − D)/4).
+fn strong_lucas_probable_prime(n: u128) -> bool {
+    let root = isqrt_u128(n);
+    if root * root == n {
         return false;
     }
-    if n <= 3 {
+    // D = 5, −7, 9, −11, … hasta que (D/n) = −1
+    let mut d: i128 = 5;
+    loop {
+        let dm = if d > 0 { d as u128 % n } else { n - (d.unsigned_abs() % n) };
+        match jacobi_u128(dm, n) {
+            -1 => break,
+            0 if d.unsigned_abs() != n => return false,
+            _ => {}
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+    let dm = if d > 0 { d as u128 % n } else { n - (d.unsigned_abs() % n) };
+    let q = (1 - d) / 4;
+    let qm = if q >= 0 { q as u128 % n } else { n - (q.unsigned_abs() % n) };
+    // x/2 mod n (n impar)
+    let half = |x: u128| if x.is_multiple_of(2) { x / 2 } else { x / 2 + n / 2 + 1 };
+
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+    let (mut u, mut v, mut qk) = (1u128, 1u128, qm);
+    for bit in (0..127 - k.leading_zeros()).rev() {
+        u = mul_mod_u128(u, v, n);
+        v = sub_mod(mul_mod_u128(v, v, n), add_mod(qk, qk, n), n);
+        qk = mul_mod_u128(qk, qk, n);
+        if (k >> bit) & 1 == 1 {
+            let (u2, v2) = (u, v);
+            u = half(add_mod(u2, v2, n));
+            v = half(add_mod(mul_mod_u128(dm, u2, n), v2, n));
+            qk = mul_mod_u128(qk, qm, n);
+        }
+    }
+    if u == 0 || v == 0 {
         return true;
     }
-    if n % 2 == 0 || n % 3 == 0 {
+    for _ in 1..s {
+        v = sub_mod(mul_mod_u128(v, v, n), add_mod(qk, qk, n), n);
+        if v == 0 {
+            return true;
+        }
+        qk = mul_mod_u128(qk, qk, n);
+    }
+    false
+}
+
+/// Primalidad: Miller–Rabin determinista con las bases 2..41 hasta ~3.3·10^24 y,
+/// por encima, Baillie–PSW (sin contraejemplos conocidos).
+pub fn is_prime_u128(n: u128) -> bool {
+    if n < 2 {
         return false;
     }
-    let mut i = 5u128;
-    while i * i <= n {
-        if n % i == 0 || n % (i + 2) == 0 {
+    for p in SMALL_PRIMES {
+        if n == p {
+            return true;
+        }
+        if n.is_multiple_of(p) {
             return false;
         }
-        i += 6;
     }
-    true
+    if n < 43 * 43 {
+        return true;
+    }
+    if n >= MR_DETERMINISTIC_LIMIT {
+        return strong_probable_prime(n, 2) && strong_lucas_probable_prime(n);
+    }
+    SMALL_PRIMES.iter().all(|&a| strong_probable_prime(n, a))
 }
 
 pub fn next_prime_u128(n: u128) -> u128 {
@@ -29,60 +245,260 @@ pub fn next_prime_u128(n: u128) -> u128 {
     p
 }
 
-fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
-    while b != 0 {
-        let t = a % b;
-        a = b;
-        b = t;
+/// Mayor primo < n (n > 2).
+pub fn prev_prime_u128(n: u128) -> Option<u128> {
+    (2..n).rev().find(|&p| is_prime_u128(p))
+}
+
+/// Primos hasta n (criba de Eratóstenes).
+fn sieve(n: u64) -> Vec<u64> {
+    let mut composite = vec![false; n as usize + 1];
+    let mut primes = Vec::new();
+    for i in 2..=n {
+        if !composite[i as usize] {
+            primes.push(i);
+            for j in (i * i..=n).step_by(i as usize) {
+                composite[j as usize] = true;
+            }
+        }
     }
-    a
+    primes
+}
+
+/// Primos en [lo, hi] con una criba segmentada (memoria proporcional a hi - lo).
+pub fn primes_in(lo: u64, hi: u64) -> Vec<u64> {
+    sieve_segment(lo, hi, &sieve(isqrt_u128(hi as u128) as u64))
 }
 
-pub fn comb_u128(n: u64, k: u64) -> Result<u128, String> {
-    if k > n {
-        return Err("comb(n,k) requiere k <= n".to_string());
+/// Primos en [lo, hi] tachando los múltiplos de `base` (todos los primos <= √hi).
+fn sieve_segment(lo: u64, hi: u64, base: &[u64]) -> Vec<u64> {
+    let lo = lo.max(2);
+    if lo > hi {
+        return Vec::new();
     }
-    let k = std::cmp::min(k, n - k);
-    if k == 0 {
-        return Ok(1);
+    let mut composite = vec![false; (hi - lo + 1) as usize];
+    for &p in base.iter().take_while(|&&p| p * p <= hi) {
+        let start = (p * p).max(lo.div_ceil(p) * p);
+        for j in (start..=hi).step_by(p as usize) {
+            composite[(j - lo) as usize] = true;
+        }
     }
+    (lo..=hi).filter(|&k| !composite[(k - lo) as usize]).collect()
+}
 
-    let mut nums: Vec<u128> = ((n - k + 1)..=n).map(|v| v as u128).collect();
+/// π(n): número de primos <= n, con el algoritmo de Lucy Hedgehog (variante de
+/// Legendre–Meissel en O(n^(3/4)) operaciones sobre los valores ⌊n/k⌋).
+pub fn prime_pi(n: u64) -> u64 {
+    if n < 2 {
+        return 0;
+    }
+    let r = isqrt_u128(n as u128) as u64;
+    // small[v] = S(v) para v <= r; large[k] = S(⌊n/k⌋) para k <= r
+    let mut small: Vec<u64> = (0..=r).map(|v| v.saturating_sub(1)).collect();
+    let mut large: Vec<u64> = (0..=r).map(|k| n.checked_div(k).map_or(0, |q| q - 1)).collect();
+    for p in 2..=r {
+        if small[p as usize] == small[p as usize - 1] {
+            continue;
+        }
+        let sp = small[p as usize - 1];
+        let p2 = p * p;
+        for k in 1..=r.min(n / p2) {
+            let kp = k * p;
+            let s = if kp <= r { large[kp as usize] } else { small[(n / kp) as usize] };
+            large[k as usize] -= s - sp;
+        }
+        for v in (p2..=r).rev() {
+            small[v as usize] -= small[(v / p) as usize] - sp;
+        }
+    }
+    large[1]
+}
+
+/// k-ésimo primo (k >= 1): π(x) en una estimación de p_k y criba del tramo restante.
+pub fn nth_prime(k: u64) -> u64 {
+    const BLOCK: u64 = 1 << 18;
+    if k < 6 {
+        return [2, 3, 5, 7, 11][k as usize - 1];
+    }
+    let (kf, lk) = (k as f64, (k as f64).ln());
+    let llk = lk.ln();
+    // Desarrollo asintótico de Cipolla: error relativo ~1e-5 hacia 10^12
+    let x = (kf * (lk + llk - 1.0 + (llk - 2.0) / lk - (llk * llk - 6.0 * llk + 11.0) / (2.0 * lk * lk))) as u64;
+    let count = prime_pi(x);
+    // Con k pequeño la estimación puede ser ~0: el primer bloque cubre igualmente p_k
+    let base = sieve(isqrt_u128(2 * (x + BLOCK) as u128) as u64);
+    if count >= k {
+        // Hacia abajo desde x: el (count - k)-ésimo primo <= x, contando desde arriba
+        let (mut hi, mut skip) = (x, count - k);
+        loop {
+            let lo = hi.saturating_sub(BLOCK - 1);
+            let block = sieve_segment(lo, hi, &base);
+            if let Some(&p) = block.iter().rev().nth(skip as usize) {
+                return p;
+            }
+            skip -= block.len() as u64;
+            hi = lo - 1;
+        }
+    }
+    let (mut lo, mut need) = (x + 1, k - count);
+    loop {
+        let block = sieve_segment(lo, lo + BLOCK - 1, &base);
+        if let Some(&p) = block.get(need as usize - 1) {
+            return p;
+        }
+        need -= block.len() as u64;
+        lo += BLOCK;
+    }
+}
 
-    for d in 2..=k {
-        let mut dd = d as u128;
-        for num in nums.iter_mut() {
-            if dd == 1 {
-                break;
+/// Un divisor no trivial de n (compuesto impar) por el método rho de Pollard con la
+/// variante de Brent (productos de diferencias acumulados entre cada mcd).
+fn pollard_brent(n: u128) -> u128 {
+    // Con n > 2^64 se itera en forma de Montgomery: x ↦ x² + c sigue siendo una
+    // función pseudoaleatoria y mcd(R·(x − y), n) = mcd(x − y, n).
+    let mont = (n > u64::MAX as u128).then(|| Montgomery::new(n));
+    let mul = |a: u128, b: u128| match &mont {
+        Some(m) => m.mul(a, b),
+        None => mul_mod_u128(a, b, n),
+    };
+    for c in 1u128.. {
+        let f = |x: u128| add_mod(mul(x, x), c, n);
+        let (mut y, mut r, mut q, mut g) = (2u128, 1u64, 1u128, 1u128);
+        let (mut x, mut ys) = (y, y);
+        while g == 1 {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                for _ in 0..128.min(r - k) {
+                    y = f(y);
+                    q = mul(q, x.abs_diff(y));
+                }
+                g = gcd_u128(q, n);
+                k += 128;
             }
-            let g = gcd_u128(*num, dd);
-            if g > 1 {
-                *num /= g;
-                dd /= g;
+            r *= 2;
+        }
+        if g == n {
+            // El producto se anuló: repetir paso a paso desde el último bloque
+            loop {
+                ys = f(ys);
+                g = gcd_u128(x.abs_diff(ys), n);
+                if g > 1 {
+                    break;
+                }
             }
         }
-        if dd != 1 {
-            return Err("comb overflow/reducción fallida".to_string());
+        if g != n {
+            return g;
+        }
+    }
+    unreachable!()
+}
+
+/// Factorización en primos (p, exponente) ordenada por p. factor(1) = [].
+pub fn factor_u128(mut n: u128) -> Vec<(u128, u32)> {
+    let mut primes: Vec<u128> = Vec::new();
+    let mut d = 2u128;
+    while d < 1000 && d * d <= n {
+        while n.is_multiple_of(d) {
+            primes.push(d);
+            n /= d;
+        }
+        d += if d == 2 { 1 } else { 2 };
+    }
+    let mut pending = if n > 1 { vec![n] } else { Vec::new() };
+    while let Some(m) = pending.pop() {
+        if is_prime_u128(m) {
+            primes.push(m);
+        } else {
+            let root = isqrt_u128(m);
+            let g = if root * root == m { root } else { pollard_brent(m) };
+            pending.push(g);
+            pending.push(m / g);
+        }
+    }
+    primes.sort_unstable();
+    let mut out: Vec<(u128, u32)> = Vec::new();
+    for p in primes {
+        match out.last_mut() {
+            Some((q, e)) if *q == p => *e += 1,
+            _ => out.push((p, 1)),
         }
     }
+    out
+}
 
-    let mut res: u128 = 1;
-    for num in nums {
-        res = res.checked_mul(num).ok_or("comb overflow".to_string())?;
+pub fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
     }
-    Ok(res)
+    a
 }
 
-pub fn perm_u128(n: u64, k: u64) -> Result<u128, String> {
-    if k > n {
-        return Err("perm(n,k) requiere k <= n".to_string());
+/// Algoritmo de Euclides extendido: (g, x, y) con a·x + b·y = g = mcd(a, b) ≥ 0.
+pub fn egcd_i128(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1i128, 0i128);
+    let (mut y0, mut y1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
     }
-    let mut res: u128 = 1;
-    for i in 0..k {
-        res = res
-            .checked_mul((n - i) as u128)
-            .ok_or("perm overflow".to_string())?;
+    if r0 < 0 { (-r0, -x0, -y0) } else { (r0, x0, y0) }
+}
+
+/// a⁻¹ mod m (m ≥ 1), si existe.
+pub fn inv_mod_u128(a: u128, m: u128) -> Option<u128> {
+    // Euclides extendido sobre los restos, con los coeficientes llevados ya módulo m
+    let (mut r0, mut r1) = (m, a % m);
+    let (mut t0, mut t1) = (0u128, 1u128 % m);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, sub_mod(t0, mul_mod_u128(q, t1, m), m));
+    }
+    (r0 == 1 || m == 1).then_some(t0)
+}
+
+/// Raíz cuadrada de a módulo el primo p (Tonelli–Shanks); la menor de las dos.
+pub fn sqrt_mod_prime(a: u128, p: u128) -> Option<u128> {
+    let a = a % p;
+    if a == 0 || p == 2 {
+        return Some(a);
+    }
+    if jacobi_u128(a, p) != 1 {
+        return None;
+    }
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    // z: cualquier no residuo cuadrático
+    let mut z = 2;
+    while jacobi_u128(z, p) != -1 {
+        z += 1;
+    }
+    let (mut m, mut c) = (s, pow_mod_u128(z, q, p));
+    let (mut t, mut r) = (pow_mod_u128(a, q, p), pow_mod_u128(a, q.div_ceil(2), p));
+    while t != 1 {
+        // menor i con t^(2^i) = 1
+        let (mut i, mut t2) = (0, t);
+        while t2 != 1 {
+            t2 = mul_mod_u128(t2, t2, p);
+            i += 1;
+        }
+        let b = pow_mod_u128(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul_mod_u128(b, b, p);
+        t = mul_mod_u128(t, c, p);
+        r = mul_mod_u128(r, b, p);
     }
-    Ok(res)
+    Some(r.min(p - r))
 }
 
@@ -7,7 +7,15 @@ use rustyline::validate::Validator;
 use rustyline::{Context, Editor, Helper};
 use colored::Colorize;
 
-use crate::calc::Calculator;
+use crate::calc::calculus::{split_deriv_order, Approach, LimitValue};
+use crate::calc::fit::FitModel;
+use crate::calc::format::{format_measurement, format_real};
+use crate::calc::identify::{self, ClosedForm};
+use crate::calc::numtheory;
+use crate::calc::plot::BrailleCanvas;
+use crate::calc::programmer::{Overflow, WordMode};
+use crate::calc::stats::Summary;
+use crate::calc::{AngleUnit, Calculator};
 use crate::help::show_help;
 use crate::history::{load_history_expr, HistoryPick};
 
@@ -17,12 +25,23 @@ const CMD_HISTORY_FILE: &str = "historial_cmds.txt";
 const COMMANDS: &[&str] = &[
     "help", "exit", "new", "mode", "vars", "mem", "hist", "clear", "plot",
     "push", "pop", "dup", "swap", "clearstack", "mem", "sum", "avg", "min", "max", "std", "ayuda", "fmt",
-    "integ", "deriv", "solve",
+    "pushxy", "popxy", "memxy", "clearxy", "fit", "seed", "sample", "factor", "divisors",
+    "primes", "egcd", "crt", "modulo", "npv", "irr", "xirr", "amort", "complex", "base", "prog",
+    "identify", "unc", "real",
+    "stats", "count", "mean", "median", "moda", "var", "varp", "stdp", "q1", "q3", "iqr", "skew", "kurt", "gmean", "hmean",
+    "integ", "deriv", "solve", "ode", "limit", "taylor",
 ];
 
 // Lista de funciones soportadas
 const FUNCS: &[&str] = &[
-    "exp", "sqrt", "cbrt", "ln", "abs", "floor", "ceil", "round", "trunc", "sign", "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "acosh", "atanh", "deg2rad", "rad2deg", "cm2in", "in2cm", "m2ft", "ft2m", "fact", "log10", "log2", "isprime", "nextprime", "atan2", "hypot", "root", "log", "mcd", "mcm", "comb", "nCr", "perm", "nPr", "pow", "min", "max", "mod", "rand", "pct", "applypct", "r3d", "r3i", "abs", "arg", "conj", "re", "im", "bin", "oct", "hex", 
+    "exp", "sqrt", "cbrt", "cis", "ln", "abs", "floor", "ceil", "round", "trunc", "sign", "sin", "cos", "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "acosh", "atanh", "deg2rad", "rad2deg", "deg", "rad", "grad", "dms", "cm2in", "in2cm", "m2ft", "ft2m", "fact", "log10", "log2", "isprime", "nextprime", "prevprime", "primepi", "nthprime", "totient", "sigma", "moebius", "isperfect", "powmod", "invmod", "jacobi", "legendre", "sqrtmod", "atan2", "hypot", "root", "log", "mcd", "mcm", "pv", "fv", "pmt", "nper", "rate", "npv", "irr", "xirr", "effect", "nominal", "date", "comb", "nCr", "perm", "nPr", "catalan", "stirling1", "stirling2", "bell", "partitions", "multinomial", "derangements", "fib", "lucas", "pow", "min", "max", "mod", "rand", "pct", "applypct", "r3d", "r3i", "abs", "arg", "conj", "re", "im", "bin", "oct", "hex", "sum", "prod",
+    "gamma", "lgamma", "digamma", "beta", "zeta", "erf", "erfc", "erfinv", "besselj", "bessely", "besseli", "besselk", "airy", "lambertw", "ellipk", "ellipe",
+    "count", "mean", "median", "moda", "var", "varp", "std", "stdp", "quartile", "iqr", "skew", "kurt", "gmean", "hmean",
+    "normpdf", "normcdf", "norminv", "tpdf", "tcdf", "tinv", "chi2pdf", "chi2cdf", "chi2inv", "fpdf", "fcdf", "finv",
+    "exppdf", "expcdf", "expinv", "unifpdf", "unifcdf", "unifinv", "binompmf", "binomcdf", "binominv",
+    "poisspmf", "poisscdf", "poissinv", "geompmf", "geomcdf", "geominv", "hygepmf", "hygecdf", "hygeinv",
+    "randint", "randn", "choice", "normrnd", "trnd", "chi2rnd", "frnd", "exprnd", "unifrnd",
+    "binomrnd", "poissrnd", "geomrnd", "hygernd",
 ];
 
 #[derive(Clone)]
@@ -70,7 +89,7 @@ impl Highlighter for CalcHelper {
                 }
                 colored_line.push_str(&num.bright_blue().to_string());
 
-            } else if "+-*/^%=!&|".contains(c) {
+            } else if "+-*/^%=!&|∠±".contains(c) {
                 // Operadores -> Rojo
                 colored_line.push_str(&c.to_string().red().to_string());
                 chars.next();
@@ -148,25 +167,764 @@ impl Completer for CalcHelper {
     }
 }
 
-// FIX: Corregida la ruta de OutputFormat (quitamos 'mod')
-fn format_complex(val: num_complex::Complex64, fmt: crate::calc::OutputFormat) -> String {
+/// Comandos que calculan un único estadístico de la pila (`moda` y `stats` aparte).
+const STACK_STATS: &[&str] = &[
+    "count", "mean", "median", "var", "varp", "std", "stdp", "q1", "q3", "iqr", "skew", "kurt", "gmean", "hmean",
+];
+
+/// Valores de `push`/`pushxy`: separados por espacios o comas fuera de paréntesis,
+/// de modo que `pushxy date(2024, 1, 15) -1000` son dos valores.
+fn split_values(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, None);
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ' ' | ',' | '\t' if depth <= 0 => {
+                if let Some(s) = start.take() {
+                    parts.push(&input[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        parts.push(&input[s..]);
+    }
+    parts
+}
+
+fn output_format_name(fmt: crate::calc::OutputFormat) -> String {
+    use crate::calc::OutputFormat;
+    match fmt {
+        OutputFormat::Decimal => "Decimal (auto)".to_string(),
+        OutputFormat::Scientific => "Científico (e)".to_string(),
+        OutputFormat::Fixed(d) => format!("Fijo ({} decimales)", d),
+        OutputFormat::Significant(d) => format!("{} cifras significativas", d),
+        OutputFormat::Engineering => "Ingeniería (exponente múltiplo de 3)".to_string(),
+        OutputFormat::Si => "Prefijos SI (k, M, m, µ, ...)".to_string(),
+    }
+}
+
+/// fmt [toggle | dec | sci | fix N | sig N | eng | si | group [on|off] | show]
+fn cmd_fmt(calc: &mut Calculator, input: &str) {
     use crate::calc::OutputFormat;
+    const MAX_DIGITS: usize = 17;
+    let args: Vec<&str> = input.split_whitespace().collect();
+    let digits = |min: usize| match args.get(1).map(|a| a.parse::<usize>()) {
+        Some(Ok(d)) if (min..=MAX_DIGITS).contains(&d) && args.len() == 2 => Some(d),
+        _ => None,
+    };
+    let new_format = match args.as_slice() {
+        ["toggle"] => Some(if calc.output_format == OutputFormat::Decimal { OutputFormat::Scientific } else { OutputFormat::Decimal }),
+        ["dec"] => Some(OutputFormat::Decimal),
+        ["sci"] => Some(OutputFormat::Scientific),
+        ["eng"] => Some(OutputFormat::Engineering),
+        ["si"] => Some(OutputFormat::Si),
+        ["fix", ..] => match digits(0) {
+            Some(d) => Some(OutputFormat::Fixed(d)),
+            None => { println!("Uso: fmt fix N  (0 <= N <= {})", MAX_DIGITS); return; }
+        },
+        ["sig", ..] => match digits(1) {
+            Some(d) => Some(OutputFormat::Significant(d)),
+            None => { println!("Uso: fmt sig N  (1 <= N <= {})", MAX_DIGITS); return; }
+        },
+        ["group"] => { calc.digit_grouping = !calc.digit_grouping; None }
+        ["group", "on"] => { calc.digit_grouping = true; None }
+        ["group", "off"] => { calc.digit_grouping = false; None }
+        ["show"] => None,
+        _ => {
+            println!("Uso: fmt [dec | sci | fix N | sig N | eng | si | group [on|off] | show]");
+            return;
+        }
+    };
+    if let Some(f) = new_format {
+        calc.output_format = f;
+    }
+    let grouping = if calc.digit_grouping { ", miles agrupados" } else { "" };
+    println!("Formato numérico: {}{}", output_format_name(calc.output_format).cyan(), grouping);
+}
+
+fn complex_format_name(style: crate::calc::ComplexFormat) -> &'static str {
+    use crate::calc::ComplexFormat;
+    match style {
+        ComplexFormat::Rectangular => "rectangular (a + bi)",
+        ComplexFormat::Polar => "polar (r∠θ, θ en la unidad angular activa)",
+        ComplexFormat::Exponential => "exponencial (r·e^(iθ), θ en radianes)",
+    }
+}
+
+/// Resultado de un comando sobre la pila: se muestra y pasa a ser `ans`.
+fn print_stack_result(calc: &mut Calculator, res: Result<f64, String>) {
+    match res {
+        Ok(v) => {
+            calc.last_result = num_complex::Complex64::new(v, 0.0);
+            println!("= {}", calc.format_real(v).bold());
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
+/// Tabla del comando `stats`; los estadísticos no definidos para los datos se muestran como "—".
+fn print_summary(st: &Summary, calc: &Calculator) {
+    let opt = |v: Option<f64>| v.map_or("—".to_string(), |x| calc.format_real(x));
+    let modes = if st.modes.is_empty() {
+        "—".to_string()
+    } else {
+        st.modes.iter().map(|m| calc.format_real(*m)).collect::<Vec<_>>().join(", ")
+    };
+    let rows = [
+        ("Datos (n)", st.count.to_string()),
+        ("Media", calc.format_real(st.mean)),
+        ("Mediana", calc.format_real(st.median)),
+        ("Moda", modes),
+        ("Mínimo", calc.format_real(st.min)),
+        ("Máximo", calc.format_real(st.max)),
+        ("Q1 / Q3", format!("{} / {}", calc.format_real(st.q1), calc.format_real(st.q3))),
+        ("Rango intercuartílico", calc.format_real(st.iqr)),
+        ("Varianza muestral", opt(st.var_sample)),
+        ("Varianza poblacional", calc.format_real(st.var_pop)),
+        ("Desv. típica muestral", opt(st.std_sample)),
+        ("Desv. típica poblacional", calc.format_real(st.std_pop)),
+        ("Asimetría", opt(st.skewness)),
+        ("Curtosis (exceso)", opt(st.kurtosis)),
+        ("Media geométrica", opt(st.geometric_mean)),
+        ("Media armónica", opt(st.harmonic_mean)),
+    ];
+    for (name, value) in rows {
+        println!("  {:<26} {}", name, value);
+    }
+}
+
+/// deriv [n] <expr> <x0> [h]          -> derivada de orden n (1..=4) respecto a x
+/// deriv [n] <expr> x=1 y=2 ... [h]    -> gradiente (n=1) o Hessiana (n=2)
+fn cmd_deriv(calc: &mut Calculator, input: &str) {
+    let (order, input) = match split_deriv_order(input) {
+        Ok(v) => v,
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let args: Vec<&str> = input.split_whitespace().collect();
+    if args.len() < 2 {
+        println!("Uso: deriv [n:] <expr> <punto_x> [h]  |  deriv [n:] <expr> x=1 y=2 [h]");
+        println!("Ejemplo: deriv 2: x^3 2   (segunda derivada de x^3 en x=2)");
+        return;
+    }
+
+    let expr = args[0];
+    let rest = &args[1..];
+    let (point_args, extra): (Vec<&str>, Vec<&str>) = rest.iter().partition(|a| a.contains('='));
+    // h opcional: tras el punto suelto, o el único argumento sin '=' en modo multivariable
+    let h_arg = if point_args.is_empty() { extra.get(1) } else { extra.first() };
+    let h = match h_arg {
+        Some(h_str) => match h_str.parse::<f64>() {
+            Ok(v) if v > 0.0 => Some(v),
+            _ => { println!("Error: El paso h debe ser un número positivo."); return; }
+        },
+        None => None,
+    };
+
+    // Punto de evaluación: `x0` suelto o pares `var=valor`
+    let mut point: Vec<(String, f64)> = Vec::new();
+    if point_args.is_empty() {
+        match calc.evaluate(rest[0]) {
+            Ok(v) => point.push(("x".to_string(), v.re)), // Derivamos respecto a X real
+            Err(_) => { println!("Error: El punto de evaluación debe ser un número válido."); return; }
+        }
+    } else {
+        for p in &point_args {
+            let (name, val) = p.split_once('=').unwrap();
+            match calc.evaluate(val) {
+                Ok(v) if !name.is_empty() => point.push((name.trim().to_string(), v.re)),
+                _ => { println!("Error: Punto inválido '{}'.", p); return; }
+            }
+        }
+    }
+    let point_ref: Vec<(&str, f64)> = point.iter().map(|(n, v)| (n.as_str(), *v)).collect();
+    let at = point.iter().map(|(n, v)| format!("{}={}", n, v)).collect::<Vec<_>>().join(", ");
+
+    if point.len() == 1 {
+        match calc.derivative(expr, point_ref[0].0, point_ref[0].1, order, h) {
+            Ok(d) => {
+                calc.last_result = d.value;
+                let res_str = calc.format(d.value);
+                if d.exact {
+                    println!("Derivada de orden {} de '{}' en {} (exacta, diferenciación automática)", order, expr, at);
+                } else {
+                    println!("Derivada de orden {} de '{}' en {} (h={:.3e}, error ≈ {:.1e})", order, expr, at, d.step, d.error);
+                }
+                println!("= {}", res_str.bold());
+            }
+            Err(e) => println!("Error evaluando la función: {}", e),
+        }
+        return;
+    }
+
+    match order {
+        1 => match calc.gradient(expr, &point_ref, h) {
+            Ok(g) => {
+                println!("Gradiente de '{}' en {}:", expr, at);
+                for ((name, _), d) in point.iter().zip(&g) {
+                    let note = if d.exact { "exacta".to_string() } else { format!("error ≈ {:.1e}", d.error) };
+                    println!("  ∂/∂{:<6} = {}  ({})", name, calc.format(d.value).bold(), note);
+                }
+                calc.last_result = g[0].value;
+            }
+            Err(e) => println!("Error evaluando la función: {}", e),
+        },
+        2 => match calc.hessian(expr, &point_ref, h) {
+            Ok(hm) => {
+                println!("Hessiana de '{}' en {} (orden: {}):", expr, at,
+                    point.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(", "));
+                for row in &hm {
+                    let cells: Vec<String> = row.iter().map(|d| format!("{:>14}", calc.format(d.value))).collect();
+                    println!("  [{} ]", cells.join(" "));
+                }
+                let max_err = hm.iter().flatten().map(|d| d.error).fold(0.0, f64::max);
+                println!("  (error máximo ≈ {:.1e})", max_err);
+                calc.last_result = hm[0][0].value;
+            }
+            Err(e) => println!("Error evaluando la función: {}", e),
+        },
+        _ => println!("Error: Con varias variables solo se admiten gradiente (n=1) y Hessiana (n=2)."),
+    }
+}
+
+/// sample <n> <expr>  -> evalúa n veces una expresión aleatoria y apila los resultados
+fn cmd_sample(calc: &mut Calculator, input: &str) {
+    let input = input.trim();
+    let (n, expr) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let n = match n.parse::<usize>() {
+        Ok(n) if n > 0 && !expr.trim().is_empty() => n,
+        _ => { println!("Uso: sample <n> <expr>   Ej: sample 100 normrnd(0, 1)"); return; }
+    };
+    for _ in 0..n {
+        match calc.evaluate(expr) {
+            Ok(v) => calc.memory_stack.push(v),
+            Err(e) => { println!("Error: {}", e); return; }
+        }
+    }
+    println!("SAMPLE -> {} valores (size={})", n, calc.memory_stack.len());
+}
+
+/// factor <n> -> factorización en primos, p. ej. 360 = 2^3 * 3^2 * 5
+fn cmd_factor(calc: &mut Calculator, input: &str) {
+    let n = match calc.eval_integer(input) {
+        Ok(n) => n,
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    if n == 0 {
+        println!("Error: 0 no tiene factorización.");
+        return;
+    }
+    let mut parts: Vec<String> = if n < 0 { vec!["-1".to_string()] } else { Vec::new() };
+    parts.extend(numtheory::factorize(n).iter().map(|&(p, e)| if e == 1 { p.to_string() } else { format!("{}^{}", p, e) }));
+    if parts.is_empty() {
+        parts.push("1".to_string());
+    }
+    println!("{} = {}", n, parts.join(" * "));
+}
+
+/// divisors <n> -> lista los divisores positivos y los apila
+fn cmd_divisors(calc: &mut Calculator, input: &str) {
+    const MAX_SHOWN: usize = 200;
+    let n = match calc.eval_integer(input) {
+        Ok(n) if n >= 1 => n,
+        Ok(_) => { println!("Error: divisors requiere n >= 1"); return; }
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let divs = numtheory::divisors(n as u128);
+    let shown: Vec<String> = divs.iter().take(MAX_SHOWN).map(|d| d.to_string()).collect();
+    let more = if divs.len() > MAX_SHOWN { ", ..." } else { "" };
+    println!("Divisores de {} ({}): {}{}", n, divs.len(), shown.join(", "), more);
+    calc.memory_stack.extend(divs.iter().map(|&d| num_complex::Complex64::new(d as f64, 0.0)));
+    println!("DIVISORS -> {} valores (size={})", divs.len(), calc.memory_stack.len());
+}
+
+/// Argumentos enteros separados por comas (o espacios si no hay comas), con paréntesis opcionales.
+fn int_args(calc: &mut Calculator, input: &str) -> Result<Vec<i128>, String> {
+    let s = input.trim();
+    let s = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')).unwrap_or(s);
+    let parts: Vec<&str> = if s.contains(',') { s.split(',').collect() } else { s.split_whitespace().collect() };
+    parts.iter().map(|p| calc.eval_integer(p)).collect()
+}
+
+/// egcd <a> <b> -> mcd con coeficientes de Bézout (se apilan g, x, y)
+fn cmd_egcd(calc: &mut Calculator, input: &str) {
+    let (a, b) = match int_args(calc, input).as_deref() {
+        Ok(&[a, b]) => (a, b),
+        Ok(_) => { println!("Uso: egcd <a> <b>"); return; }
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let (g, x, y) = crate::math_ext::egcd_i128(a, b);
+    println!("mcd({}, {}) = {} = {}*({}) + {}*({})", a, b, g, a, x, b, y);
+    calc.memory_stack.extend([g, x, y].map(|v| num_complex::Complex64::new(v as f64, 0.0)));
+    println!("EGCD -> g, x, y (size={})", calc.memory_stack.len());
+}
 
-    // Función interna para formatear un f64 individual
-    let fmt_f64 = |n: f64| -> String {
-        match fmt {
-            OutputFormat::Decimal => format!("{}", n),      // Comportamiento estándar
-            OutputFormat::Scientific => format!("{:.6e}", n), // Científico con 6 decimales
+/// crt([r1, r2, ...], [m1, m2, ...]) -> x tal que x ≡ rᵢ (mod mᵢ)
+fn cmd_crt(calc: &mut Calculator, input: &str) {
+    let mut lists = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find('[') {
+        let Some(len) = rest[start..].find(']') else { break };
+        lists.push(&rest[start + 1..start + len]);
+        rest = &rest[start + len + 1..];
+    }
+    if lists.len() != 2 {
+        println!("Uso: crt([r1, r2, ...], [m1, m2, ...])");
+        return;
+    }
+    let parsed = int_args(calc, lists[0]).and_then(|r| Ok((r, int_args(calc, lists[1])?)));
+    match parsed.and_then(|(r, m)| numtheory::crt(&r, &m)) {
+        Ok((x, m)) => {
+            println!("x ≡ {} (mod {})", x, m);
+            calc.last_result = num_complex::Complex64::new(x as f64, 0.0);
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
+/// modulo [n | off] -> activa, desactiva o muestra el modo de aritmética modular
+fn cmd_modulo(calc: &mut Calculator, input: &str) {
+    let arg = input.trim();
+    let m = match arg {
+        "" => {
+            match calc.modulus {
+                Some(m) => println!("Modo modulo {}", m),
+                None => println!("Modo modulo desactivado."),
+            }
+            return;
+        }
+        "off" | "0" => None,
+        _ => {
+            // El propio módulo se evalúa sin reducir por el anterior
+            let previous = calc.modulus.take();
+            let m = calc.eval_integer(arg);
+            calc.modulus = previous;
+            match m {
+                Ok(m) => Some(m),
+                Err(e) => { println!("Error: {}", e); return; }
+            }
         }
     };
+    match calc.set_modulus(m) {
+        Ok(()) => match m {
+            Some(m) => println!("Modo modulo {}: + - * / ^ se reducen mod {}.", m, m),
+            None => println!("Modo modulo desactivado."),
+        },
+        Err(e) => println!("Error: {}", e),
+    }
+}
 
-    if val.im.abs() < 1e-10 {
-        // Solo Real
-        fmt_f64(val.re)
+/// prog [i|u<bits> [wrap|sat] | off]  -> modo programador con enteros de C
+fn cmd_prog(calc: &mut Calculator, input: &str) {
+    let describe = |calc: &Calculator| match calc.word {
+        Some(w) => {
+            let overflow = match w.overflow {
+                Overflow::Wrap => "circular (wrap)",
+                Overflow::Saturate => "saturado",
+            };
+            println!("Modo programador {}: enteros de {} bits {}, desbordamiento {}, división truncada.",
+                w.label().cyan(), w.bits, if w.signed { "con signo" } else { "sin signo" }, overflow);
+        }
+        None => println!("Modo programador desactivado."),
+    };
+    match input.trim() {
+        "" => describe(calc),
+        "off" => {
+            calc.set_word(None);
+            describe(calc);
+        }
+        spec => match WordMode::parse(spec) {
+            Ok(w) => {
+                calc.set_word(Some(w));
+                describe(calc);
+            }
+            Err(e) => println!("{}", e),
+        },
+    }
+}
+
+/// Línea de una sugerencia de `identify`: la forma, su error y la tolerancia aplicada.
+fn closed_form_line(f: &ClosedForm, tol: f64) -> String {
+    format!("≈ {}   (error {:.1e} <= tol {:.0e})", f.expr.cyan(), f.error, tol)
+}
+
+/// identify [expr] [tol=t] | on | off -> formas cerradas del valor (por defecto, `ans`)
+fn cmd_identify(calc: &mut Calculator, input: &str) {
+    let mut expr = input.trim();
+    match expr {
+        "on" | "off" => {
+            calc.identify = expr == "on";
+            println!("Sugerencias de forma cerrada: {}", if calc.identify { "ON" } else { "OFF" });
+            return;
+        }
+        "" => expr = "ans",
+        _ => {}
+    }
+    let mut tol = identify::DEFAULT_TOL;
+    if let Some((head, t)) = expr.rsplit_once("tol=") {
+        match calc.evaluate(t) {
+            Ok(v) if v.im == 0.0 && v.re > 0.0 => tol = v.re,
+            _ => { println!("Error: la tolerancia debe ser un real > 0"); return; }
+        }
+        expr = if head.trim().is_empty() { "ans" } else { head.trim() };
+    }
+    let x = match calc.evaluate(expr) {
+        Ok(v) if v.im.abs() < 1e-12 => v.re,
+        Ok(_) => { println!("Error: identify solo reconoce números reales"); return; }
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let found = identify::identify(x, tol);
+    if found.is_empty() {
+        println!("No se reconoce ninguna forma cerrada para {} (tol {:.0e})", calc.format_real(x), tol);
+    }
+    for f in &found {
+        println!("  {}", closed_form_line(f, tol * x.abs()));
+    }
+}
+
+/// unc [expr] -> detalle de la incertidumbre de una medida (por defecto, `ans`)
+fn cmd_unc(calc: &mut Calculator, input: &str) {
+    let expr = if input.trim().is_empty() { "ans" } else { input.trim() };
+    let res = match calc.evaluate(expr) {
+        Ok(v) => v,
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let Some(u) = calc.uncertainty("ans").cloned() else {
+        println!("= {}  (sin incertidumbre: usa a ± σ o a +/- σ)", calc.format_result(res));
+        return;
+    };
+    println!("= {}", calc.format_uncertain(&u).bold());
+    use crate::calc::OutputFormat;
+    let sig2 = |x: f64| format_real(x, OutputFormat::Significant(2), false);
+    let sigma = u.sigma();
+    if u.value.norm() != 0.0 {
+        println!("  σ = {}  ({} %)", sig2(sigma), sig2(100.0 * sigma / u.value.norm()));
     } else {
-        // Complejo (Re +/- Im i)
-        let sign = if val.im >= 0.0 { "+" } else { "-" };
-        format!("{} {} {}i", fmt_f64(val.re), sign, fmt_f64(val.im.abs()))
+        println!("  σ = {}", sig2(sigma));
+    }
+    let w = u.worst_case();
+    if u.value.im == 0.0 {
+        println!("  Peor caso (lineal): {}", format_measurement(u.value.re, w));
+    } else {
+        println!("  Peor caso (lineal): ±{}", sig2(w));
+    }
+    println!("  Fuentes de error independientes: {}", u.sources());
+}
+
+/// primes <a> <b> -> lista y apila los primos de [a, b]
+fn cmd_primes(calc: &mut Calculator, input: &str) {
+    const MAX_SHOWN: usize = 200;
+    const MAX_RANGE: i128 = 100_000_000;
+    let (a, b) = match int_args(calc, input).as_deref() {
+        Ok(&[a, b]) => (a.max(0), b),
+        Ok(_) => { println!("Uso: primes <a> <b>"); return; }
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    if b > numtheory::PRIMEPI_LIMIT || b - a > MAX_RANGE {
+        println!("Error: primes admite b <= {} y b - a <= {}", numtheory::PRIMEPI_LIMIT, MAX_RANGE);
+        return;
+    }
+    let primes = if a > b { Vec::new() } else { crate::math_ext::primes_in(a as u64, b as u64) };
+    let shown: Vec<String> = primes.iter().take(MAX_SHOWN).map(|p| p.to_string()).collect();
+    let more = if primes.len() > MAX_SHOWN { ", ..." } else { "" };
+    println!("Primos en [{}, {}] ({}): {}{}", a, b, primes.len(), shown.join(", "), more);
+    calc.memory_stack.extend(primes.iter().map(|&p| num_complex::Complex64::new(p as f64, 0.0)));
+    println!("PRIMES -> {} valores (size={})", primes.len(), calc.memory_stack.len());
+}
+
+/// Pausa cada `AMORT_PAGE` filas cuando la entrada y la salida son un terminal.
+const AMORT_PAGE: usize = 24;
+
+/// Espera Enter para seguir; devuelve false si el usuario pide salir ('q').
+fn wait_page() -> bool {
+    use std::io::{BufRead, Write};
+    print!("{}", "-- Enter para continuar, q para terminar --".dimmed());
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    let ok = std::io::stdin().lock().read_line(&mut answer).is_ok_and(|n| n > 0);
+    ok && !answer.trim().eq_ignore_ascii_case("q")
+}
+
+/// amort <capital> <tipo_anual> <años> [extra] [csv <fichero>]
+/// -> cuadro de amortización mensual, con totales; opcionalmente exportado a CSV
+fn cmd_amort(calc: &mut Calculator, input: &str) {
+    use std::io::IsTerminal;
+    use crate::calc::finance::{self, AmortRow};
+    use crate::calc::OutputFormat;
+
+    let mut args: Vec<&str> = input.split_whitespace().collect();
+    let csv_path = match args.iter().position(|a| *a == "csv") {
+        Some(k) if k + 2 == args.len() => {
+            let path = args[k + 1];
+            args.truncate(k);
+            Some(path)
+        }
+        Some(_) => { println!("Uso: amort <capital> <tipo_anual> <años> [extra] [csv <fichero>]"); return; }
+        None => None,
+    };
+    if !(3..=4).contains(&args.len()) {
+        println!("Uso: amort <capital> <tipo_anual> <años> [extra] [csv <fichero>]");
+        println!("Ejemplo: amort 200000 0.05 30 100   (5 % anual a 30 años, 100 extra al mes)");
+        return;
+    }
+    let mut vals = Vec::with_capacity(4);
+    for a in &args {
+        match calc.evaluate(a) {
+            Ok(v) if v.im == 0.0 => vals.push(v.re),
+            Ok(_) => { println!("Error: '{}' debe ser real.", a); return; }
+            Err(e) => { println!("Error en '{}': {}", a, e); return; }
+        }
+    }
+    let extra = vals.get(3).copied().unwrap_or(0.0);
+    let rows = match finance::amortization(vals[0], vals[1], vals[2], extra) {
+        Ok(r) => r,
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+
+    // Importes con céntimos salvo que el usuario haya elegido otro formato con `fmt`
+    let money = |v: f64| match calc.output_format {
+        OutputFormat::Decimal if calc.radix == 10 => {
+            format_real(if v.abs() < 0.005 { 0.0 } else { v }, OutputFormat::Fixed(2), calc.digit_grouping)
+        }
+        _ => calc.format_real(v),
+    };
+    let cells = |r: &AmortRow| [r.period.to_string(), money(r.payment), money(r.interest), money(r.principal), money(r.balance)];
+    let table: Vec<[String; 5]> = rows.iter().map(cells).collect();
+    let total_paid: f64 = rows.iter().map(|r| r.payment).sum();
+    let total_interest: f64 = rows.iter().map(|r| r.interest).sum();
+    let totals = ["Total".to_string(), money(total_paid), money(total_interest), money(vals[0]), String::new()];
+    let header = ["Periodo", "Cuota", "Intereses", "Amortización", "Saldo"];
+
+    let mut width = header.map(|h| h.chars().count());
+    for row in table.iter().chain(std::iter::once(&totals)) {
+        for (w, c) in width.iter_mut().zip(row) {
+            *w = (*w).max(c.chars().count());
+        }
+    }
+    let line = |row: &[String]| {
+        row.iter().zip(width).map(|(c, w)| format!("{:>w$}", c, w = w)).collect::<Vec<_>>().join("  ")
+    };
+
+    let paged = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    println!("{}", line(&header.map(String::from)).bold());
+    for (k, row) in table.iter().enumerate() {
+        if paged && k > 0 && k % AMORT_PAGE == 0 && !wait_page() {
+            println!("... ({} periodos más)", table.len() - k);
+            break;
+        }
+        println!("{}", line(row));
+    }
+    println!("{}", line(&totals).bold());
+    if extra > 0.0 {
+        let base = finance::amortization(vals[0], vals[1], vals[2], 0.0).unwrap_or_default();
+        let base_interest: f64 = base.iter().map(|r| r.interest).sum();
+        println!(
+            "Con {} extra al mes: {} periodos en lugar de {}, ahorro en intereses {}",
+            money(extra), rows.len(), base.len(), money(base_interest - total_interest).green()
+        );
+    }
+
+    if let Some(path) = csv_path {
+        // El CSV conserva la precisión completa para poder seguir calculando con él
+        let mut csv = String::from("periodo,cuota,intereses,amortizacion,saldo\n");
+        for r in &rows {
+            csv.push_str(&format!("{},{},{},{},{}\n", r.period, r.payment, r.interest, r.principal, r.balance));
+        }
+        csv.push_str(&format!("Total,{},{},{},\n", total_paid, total_interest, vals[0]));
+        match std::fs::write(path, csv) {
+            Ok(_) => println!("Cuadro exportado a {} ({} filas)", path, rows.len()),
+            Err(e) => eprintln!("No se pudo escribir {}: {}", path, e),
+        }
+    }
+}
+
+/// fit <modelo> [grado] [nombre] [plot]  -> ajuste sobre la pila de pares (pushxy)
+fn cmd_fit(calc: &mut Calculator, input: &str) {
+    let mut args: Vec<&str> = input.split_whitespace().collect();
+    let show_plot = args.last() == Some(&"plot");
+    if show_plot {
+        args.pop();
+    }
+    let (model, used) = match FitModel::parse(&args) {
+        Ok(m) => m,
+        Err(e) => {
+            println!("Error: {}", e);
+            println!("Uso: fit linear | poly <n> | exp | power | log  [nombre] [plot]");
+            return;
+        }
+    };
+    let name = args.get(used).copied().unwrap_or("fit");
+
+    let res = match calc.fit(model) {
+        Ok(r) => r,
+        Err(e) => { println!("Error: {}", e); return; }
+    };
+    let dof = calc.pairs.len() - res.params.len();
+    println!("Ajuste {}  (n={}, grados de libertad={})", model.formula(), calc.pairs.len(), dof);
+    for ((pname, v), se) in model.param_names().iter().zip(&res.params).zip(&res.std_errors) {
+        let se_str = if se.is_finite() { calc.format_real(*se) } else { "—".to_string() };
+        println!("  {:<4} = {:>22}  ± {}", pname, calc.format_real(*v), se_str);
+    }
+    println!("  R²   = {}", calc.format_real(res.r2).bold());
+    println!("  {:>20} {:>20} {:>20} {:>22}", "x", "y", "ŷ", "residuo");
+    for ((d, f), r) in calc.pairs.iter().zip(&res.fitted).zip(&res.residuals) {
+        println!("  {:>20} {:>20} {:>20} {:>22}", calc.format_real(d.0), calc.format_real(d.1), calc.format_real(*f), calc.format_real(*r));
+    }
+
+    let expr = res.expr("x");
+    match calc.define_func(name, &["x"], &expr) {
+        Ok(()) => println!("{}(x) = {}", name, expr.bold()),
+        Err(e) => println!("Error al guardar el modelo: {}", e),
+    }
+    if show_plot {
+        res.plot(&calc.pairs);
+    }
+}
+
+/// ode y' = ..., y(t0)=..., t=t0..t1 [, rtol=.., atol=.., n=..]
+/// -> tabla de la solución, estado final en las variables y trayectoria en Braille
+fn cmd_ode(calc: &mut Calculator, input: &str) {
+    let spec = match calc.parse_ode(input) {
+        Ok(s) => s,
+        Err(e) => {
+            println!("Error: {}", e);
+            println!("Uso: ode y' = <expr>, y(t0)=<v>, t=<t0>..<t1> [, rtol=1e-6, atol=1e-9, n=11]");
+            println!("Ejemplo: ode x' = y, y' = -x, x(0)=1, y(0)=0, t=0..2*pi");
+            return;
+        }
+    };
+    let sol = match calc.ode(&spec) {
+        Ok(s) => s,
+        Err(e) => return println!("Error: {}", e),
+    };
+
+    let cell = |v: f64| {
+        if v == 0.0 || (1e-4..1e6).contains(&v.abs()) { format!("{:>14.8}", v) } else { format!("{:>14.6e}", v) }
+    };
+    println!("Dormand–Prince RK45 (rtol={:e}, atol={:e}): {} pasos aceptados, {} rechazados",
+        spec.rtol, spec.atol, sol.accepted, sol.rejected);
+    let header: Vec<String> = std::iter::once(&spec.indep).chain(&spec.names).map(|n| format!("{:>14}", n)).collect();
+    println!("{}", header.join(" "));
+    for (t, y) in &sol.table {
+        let row: Vec<String> = std::iter::once(*t).chain(y.iter().copied()).map(cell).collect();
+        println!("{}", row.join(" "));
+    }
+    println!("Estado final guardado en: {}", spec.names.join(", "));
+
+    // Trayectoria con el renderizador Braille de `plot`
+    let all_y = sol.trajectory.iter().flat_map(|(_, y)| y.iter().copied());
+    let y_min = all_y.clone().fold(f64::INFINITY, f64::min);
+    let y_max = all_y.fold(f64::NEG_INFINITY, f64::max);
+    let (y_min, y_max) = if (y_max - y_min).abs() < 1e-9 { (y_min - 1.0, y_max + 1.0) } else { (y_min, y_max) };
+    let (x_min, x_max) = (spec.t0.min(spec.t1), spec.t0.max(spec.t1));
+    let mut canvas = BrailleCanvas::new(80, 20, (x_min, x_max), (y_min, y_max));
+    for k in 0..spec.names.len() {
+        for w in sol.trajectory.windows(2) {
+            canvas.draw_line((w[0].0, w[0].1[k]), (w[1].0, w[1].1[k]));
+        }
+    }
+    canvas.print(&format!("ODE (Braille): {:?} frente a {}", spec.names, spec.indep));
+}
+
+/// limit <expr> x->a   (a+ / a- para límites laterales, inf / -inf en el infinito)
+fn cmd_limit(calc: &mut Calculator, input: &str) {
+    let args: Vec<&str> = input.split_whitespace().collect();
+    let Some((var, target)) = args.last().and_then(|a| a.split_once("->")) else {
+        println!("Uso: limit <expr> x->a  |  x->a+  |  x->a-  |  x->inf");
+        println!("Ejemplo: limit sin(x)/x x->0");
+        return;
+    };
+    if args.len() < 2 || var.is_empty() || target.is_empty() {
+        println!("Uso: limit <expr> x->a  |  x->a+  |  x->a-  |  x->inf");
+        return;
+    }
+    let expr = args[..args.len() - 1].join(" ");
+
+    let target = target.replace('∞', "inf");
+    let target = target.strip_prefix('+').unwrap_or(&target);
+    let (target, approach) = match target.strip_suffix('+') {
+        Some(t) => (t, Approach::Right),
+        None => match target.strip_suffix('-') {
+            Some(t) => (t, Approach::Left),
+            None => (target, Approach::Both),
+        },
+    };
+    // `-inf` no pasa por el evaluador (rechaza resultados no finitos)
+    let (negate, value_str) = match target.strip_prefix('-') {
+        Some(t) => (true, t),
+        None => (false, target),
+    };
+    let point = match calc.evaluate(value_str) {
+        Ok(v) if v.im == 0.0 && !v.re.is_nan() => if negate { -v.re } else { v.re },
+        _ => { println!("Error: El punto del límite debe ser un número real o ±inf."); return; }
+    };
+
+    let side = match approach {
+        Approach::Left if point.is_finite() => " por la izquierda",
+        Approach::Right if point.is_finite() => " por la derecha",
+        _ => "",
+    };
+    match calc.limit(&expr, var, point, approach) {
+        Ok(l) => {
+            println!("Límite de '{}' cuando {} → {}{}:", expr, var, target, side);
+            match l {
+                LimitValue::Finite { value, error } => {
+                    calc.last_result = value;
+                    println!("= {}  (error ≈ {:.1e})", calc.format(value).bold(), error);
+                }
+                _ => println!("= {}", l.to_string().bold()),
+            }
+        }
+        Err(e) => println!("Error: {}", e),
+    }
+}
+
+/// taylor <expr> x=a [n]   -> desarrollo de Taylor de orden n (5 por defecto)
+fn cmd_taylor(calc: &mut Calculator, input: &str) {
+    let mut args: Vec<&str> = input.split_whitespace().collect();
+    let mut order = 5usize;
+    if args.len() >= 3 && !args[args.len() - 1].contains('=') {
+        match args[args.len() - 1].parse::<usize>() {
+            Ok(n) => order = n,
+            Err(_) => { println!("Error: El orden debe ser un entero no negativo."); return; }
+        }
+        args.pop();
+    }
+    let Some((var, at)) = args.last().and_then(|a| a.split_once('=')) else {
+        println!("Uso: taylor <expr> x=a [n]");
+        println!("Ejemplo: taylor exp(x) x=0 6");
+        return;
+    };
+    if args.len() < 2 || var.is_empty() {
+        println!("Uso: taylor <expr> x=a [n]");
+        return;
+    }
+    let expr = args[..args.len() - 1].join(" ");
+    let a = match calc.evaluate(at) {
+        Ok(v) if v.im == 0.0 && v.re.is_finite() => v.re,
+        _ => { println!("Error: El punto de desarrollo debe ser un número real finito."); return; }
+    };
+
+    match calc.taylor(&expr, var, a, order) {
+        Ok(t) => {
+            let how = if t.exact { "exacto, aritmética de series" } else { "numérico, con error estimado" };
+            println!("Taylor de '{}' en {}={} hasta orden {} ({}):", expr, var, a, order, how);
+            for (k, c) in t.coeffs.iter().enumerate() {
+                if t.exact {
+                    println!("  c{:<3} = {}", k, calc.format(*c));
+                } else {
+                    println!("  c{:<3} = {}  (error ≈ {:.1e})", k, calc.format(*c), t.errors[k]);
+                }
+            }
+            println!("≈ {}", t.to_expr(var, a).bold());
+            calc.last_result = t.coeffs[0];
+        }
+        Err(e) => println!("Error: {}", e),
     }
 }
 
@@ -188,8 +946,11 @@ pub fn run() {
             h.update_vars(calc.vars.keys().cloned());
         }
 
-        let mode_str = if calc.is_radians { "RAD" } else { "DEG" };
-        let prompt = format!("[{}] >> ", mode_str).bold().to_string();
+        let mode_str = calc.angle.label();
+        let mod_str = calc.modulus.map(|m| format!(" mod {}", m)).unwrap_or_default();
+        let word_str = calc.word.map(|w| format!(" {}", w.label())).unwrap_or_default();
+        let real_str = if calc.real { " ℝ" } else { "" };
+        let prompt = format!("[{}{}{}{}] >> ", mode_str, real_str, mod_str, word_str).bold().to_string();
 
         let line = match rl.readline(&prompt) {
             Ok(s) => s,
@@ -226,46 +987,150 @@ pub fn run() {
                 println!("Sistema reseteado.");
             }
             "mode" => {
-                calc.is_radians = !calc.is_radians;
-                println!("Modo: {}", if calc.is_radians { "RAD" } else { "DEG" });
-            }
-            "fmt" | "fmt toggle" => {
-                use crate::calc::OutputFormat;
-                calc.output_format = match calc.output_format {
-                    OutputFormat::Decimal => OutputFormat::Scientific,
-                    OutputFormat::Scientific => OutputFormat::Decimal,
-                };
-                let estado = match calc.output_format {
-                    OutputFormat::Decimal => "Decimal (auto)",
-                    OutputFormat::Scientific => "Científico (e)",
+                // Sin argumento alterna RAD/DEG (desde GRAD vuelve a RAD)
+                calc.angle = if calc.angle == AngleUnit::Rad { AngleUnit::Deg } else { AngleUnit::Rad };
+                println!("Modo: {}", calc.angle.label());
+            }
+            s if s.starts_with("mode ") => match AngleUnit::parse(s[5..].trim()) {
+                Some(unit) => {
+                    calc.angle = unit;
+                    println!("Modo: {}", calc.angle.label());
+                }
+                None => println!("Uso: mode [rad | deg | grad]"),
+            },
+            "fmt" | "fmt toggle" => cmd_fmt(&mut calc, "toggle"),
+            s if s.starts_with("fmt ") => cmd_fmt(&mut calc, &s[4..]),
+            "base" => println!("Base de salida: {}", calc.radix.to_string().cyan()),
+            s if s.starts_with("base ") && !s.contains('=') => match s[5..].trim().parse::<u32>() {
+                Ok(r) if (2..=36).contains(&r) => {
+                    calc.radix = r;
+                    println!("Base de salida: {}", r.to_string().cyan());
+                }
+                _ => println!("Uso: base N  (2 <= N <= 36; base 10 vuelve a decimal)"),
+            },
+            "complex" | "complex show" => println!("Complejos: {}", complex_format_name(calc.complex_format).cyan()),
+            s if s.starts_with("complex ") => {
+                use crate::calc::ComplexFormat;
+                calc.complex_format = match s[8..].trim() {
+                    "rect" => ComplexFormat::Rectangular,
+                    "polar" => ComplexFormat::Polar,
+                    "exp" => ComplexFormat::Exponential,
+                    _ => { println!("Uso: complex [rect | polar | exp]"); continue; }
                 };
-                println!("Formato numérico: {}", estado.cyan());
+                println!("Complejos: {}", complex_format_name(calc.complex_format).cyan());
             }
-            "fmt sci" => {
-                calc.output_format = crate::calc::OutputFormat::Scientific;
-                println!("Formato numérico: Científico");
+            "vars" => {
+                println!("Vars:");
+                let mut names: Vec<&String> = calc.vars.keys().collect();
+                names.sort();
+                for name in names {
+                    let text = match calc.uncertainty(name) {
+                        Some(u) => calc.format_uncertain(u),
+                        None => calc.format(calc.vars[name]),
+                    };
+                    println!("  {} = {}", name, text);
+                }
+                for (name, f) in &calc.funcs {
+                    println!("  {}({}) = {}", name, f.params.join(", "), f.body);
+                }
             }
-            "fmt dec" => {
-                calc.output_format = crate::calc::OutputFormat::Decimal;
-                println!("Formato numérico: Decimal");
+            "mem" => {
+                let items: Vec<String> = calc.memory_stack.iter().map(|v| calc.format(*v)).collect();
+                println!("Pila: [{}]", items.join(", "));
             }
-            "vars" => println!("Vars: {:?}", calc.vars),
-            "mem" => println!("Pila: {:?}", calc.memory_stack),
             "clearstack" => {
                 calc.memory_stack.clear();
                 println!("Pila vaciada.");
             }
+            "memxy" => {
+                if calc.pairs.is_empty() { println!("Pila de pares vacía."); }
+                for (k, (x, y)) in calc.pairs.iter().enumerate() {
+                    println!("  {:>3}: ({}, {})", k + 1, calc.format_real(*x), calc.format_real(*y));
+                }
+            }
+            "clearxy" => {
+                calc.pairs.clear();
+                println!("Pila de pares vaciada.");
+            }
+            "popxy" => match calc.pairs.pop() {
+                Some((x, y)) => println!("POPXY -> ({}, {}) (size={})", calc.format_real(x), calc.format_real(y), calc.pairs.len()),
+                None => println!("Pila de pares vacía."),
+            },
+            s if s.starts_with("pushxy ") => {
+                let parts = split_values(&s[7..]);
+                if !parts.len().is_multiple_of(2) {
+                    println!("Uso: pushxy x1 y1 [x2 y2 ...]");
+                } else {
+                    for xy in parts.chunks(2) {
+                        match (calc.evaluate(xy[0]), calc.evaluate(xy[1])) {
+                            (Ok(x), Ok(y)) if x.im == 0.0 && y.im == 0.0 => {
+                                calc.pairs.push((x.re, y.re));
+                                println!("PUSHXY -> ({}, {}) (size={})", calc.format_real(x.re), calc.format_real(y.re), calc.pairs.len());
+                            }
+                            (Err(e), _) | (_, Err(e)) => { println!("Error en '{} {}': {}", xy[0], xy[1], e); break; }
+                            _ => { println!("Error: los pares deben ser reales."); break; }
+                        }
+                    }
+                }
+            }
+            s if s.starts_with("fit ") => cmd_fit(&mut calc, &s[4..]),
+            s if s.starts_with("npv ") => {
+                let res = calc.evaluate(&s[4..]).and_then(|r| calc.stack_npv(r.re));
+                print_stack_result(&mut calc, res);
+            }
+            s if s.starts_with("amort ") => cmd_amort(&mut calc, &s[6..]),
+            "irr" => {
+                let res = calc.stack_irr();
+                print_stack_result(&mut calc, res);
+            }
+            "xirr" => {
+                let res = calc.pairs_xirr();
+                print_stack_result(&mut calc, res);
+            }
+            "seed" => println!("Semilla: {}", calc.rng.seed()),
+            s if s.starts_with("seed ") => {
+                let arg = s[5..].trim();
+                let seed = arg.parse::<u64>().ok().or_else(|| match calc.evaluate(arg) {
+                    Ok(v) if v.im == 0.0 && v.re >= 0.0 && v.re.fract() == 0.0 && v.re < 2f64.powi(64) => Some(v.re as u64),
+                    _ => None,
+                });
+                match seed {
+                    Some(n) => {
+                        calc.set_seed(n);
+                        println!("Semilla: {}", n);
+                    }
+                    None => println!("Error: la semilla debe ser un entero >= 0."),
+                }
+            }
+            s if s.starts_with("sample ") => cmd_sample(&mut calc, &s[7..]),
+            s if s.starts_with("factor ") || s.starts_with("factor(") => cmd_factor(&mut calc, &s[6..]),
+            s if s.starts_with("divisors ") || s.starts_with("divisors(") => cmd_divisors(&mut calc, &s[8..]),
+            s if s.starts_with("primes ") || s.starts_with("primes(") => cmd_primes(&mut calc, &s[6..]),
+            s if s.starts_with("egcd ") || s.starts_with("egcd(") => cmd_egcd(&mut calc, &s[4..]),
+            s if s.starts_with("crt ") || s.starts_with("crt(") => cmd_crt(&mut calc, &s[3..]),
+            "unc" => cmd_unc(&mut calc, ""),
+            s if s.starts_with("unc ") => cmd_unc(&mut calc, &s[4..]),
+            "real" | "real on" | "real off" => {
+                calc.real = match raw { "real" => !calc.real, r => r == "real on" };
+                println!("Modo real estricto: {}", if calc.real { "ON" } else { "OFF" });
+            }
+            "identify" => cmd_identify(&mut calc, ""),
+            s if s.starts_with("identify ") => cmd_identify(&mut calc, &s[9..]),
+            "prog" => cmd_prog(&mut calc, ""),
+            s if s.starts_with("prog ") => cmd_prog(&mut calc, &s[5..]),
+            "modulo" => cmd_modulo(&mut calc, ""),
+            s if s.starts_with("modulo ") => cmd_modulo(&mut calc, &s[7..]),
             "pop" => match calc.memory_stack.pop() {
                 Some(v) => {
                     calc.last_result = v;
-                    println!("POP -> {} (size={})", v, calc.memory_stack.len());
+                    println!("POP -> {} (size={})", calc.format(v), calc.memory_stack.len());
                 }
                 None => println!("Pila vacía."),
             },
             "dup" => match calc.memory_stack.last().copied() {
                 Some(v) => {
                     calc.memory_stack.push(v);
-                    println!("DUP -> {} (size={})", v, calc.memory_stack.len());
+                    println!("DUP -> {} (size={})", calc.format(v), calc.memory_stack.len());
                 }
                 None => println!("Pila vacía."),
             },
@@ -275,7 +1140,7 @@ pub fn run() {
                     println!("Se necesitan al menos 2 valores en la pila.");
                 } else {
                     calc.memory_stack.swap(n - 1, n - 2);
-                    println!("SWAP -> top={} (size={})", calc.memory_stack[n - 1], n);
+                    println!("SWAP -> top={} (size={})", calc.format(calc.memory_stack[n - 1]), n);
                 }
             },
 
@@ -294,40 +1159,36 @@ pub fn run() {
                         // Configuración del algoritmo
                         let max_iter = 100;
                         let tolerance = 1e-9;
-                        let h = 1e-5; // Paso para la derivada
-                        
-                        let saved_x = calc.vars.get("x").copied();
+                        let h = 1e-5; // Paso para la derivada si no se puede usar diferenciación automática
+
                         let mut converged = false;
 
                         println!("Iterando Newton-Raphson...");
 
                         for i in 1..=max_iter {
-                            // 1. Calcular f(x)
-                            calc.vars.insert("x".to_string(), x_curr);
-                            let y_res = calc.evaluate(expr);
-
-                            // 2. Calcular f'(x) numéricamente
-                            calc.vars.insert("x".to_string(), x_curr + h);
-                            let y_plus = calc.evaluate(expr);
-                            
-                            calc.vars.insert("x".to_string(), x_curr - h);
-                            let y_minus = calc.evaluate(expr);
-
-                            match (y_res, y_plus, y_minus) {
-                                (Ok(y), Ok(yp), Ok(ym)) => {
+                            // 1. f(x) y f'(x) exacta con números duales; si la expresión no es
+                            //    derivable así, f'(x) por diferencia centrada.
+                            let step = match calc.evaluate_dual(expr, &[("x", x_curr)]) {
+                                Ok(d) => Ok((d.val, d.d(0))),
+                                Err(_) => calc.eval_with(expr, &[("x", x_curr)]).and_then(|y| {
+                                    let yp = calc.eval_with(expr, &[("x", x_curr + h)])?;
+                                    let ym = calc.eval_with(expr, &[("x", x_curr - h)])?;
+                                    Ok((y, (yp - ym) / (2.0 * h)))
+                                }),
+                            };
+
+                            match step {
+                                Ok((y, deriv)) => {
                                     // Comprobamos si ya hemos encontrado la raíz (f(x) ~ 0)
                                     if y.norm() < tolerance {
                                         converged = true;
                                         calc.last_result = x_curr;
-                                        let res_str = format_complex(x_curr, calc.output_format);
+                                        let res_str = calc.format(x_curr);
                                         println!("Raíz encontrada en iteración {}:", i);
                                         println!("x = {}", res_str.bold());
                                         break;
                                     }
 
-                                    // Derivada: (f(x+h) - f(x-h)) / 2h
-                                    let deriv = (yp - ym) / (2.0 * h);
-
                                     // Protección contra derivada cero (división por cero)
                                     if deriv.norm() < 1e-14 {
                                         println!("Error: La derivada se hizo cero en x={}. El método falló.", x_curr);
@@ -335,9 +1196,9 @@ pub fn run() {
                                     }
 
                                     // Paso de Newton: x_new = x - f(x)/f'(x)
-                                    x_curr = x_curr - (y / deriv);
+                                    x_curr -= y / deriv;
                                 }
-                                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                                Err(e) => {
                                     println!("Error evaluando la expresión: {}", e);
                                     break;
                                 }
@@ -346,14 +1207,7 @@ pub fn run() {
 
                         if !converged {
                             println!("Advertencia: No se alcanzó la convergencia tras {} iteraciones.", max_iter);
-                            println!("Última aproximación: {}", x_curr);
-                        }
-
-                        // Restaurar variable x original
-                        if let Some(old) = saved_x {
-                            calc.vars.insert("x".to_string(), old);
-                        } else {
-                            calc.vars.remove("x");
+                            println!("Última aproximación: {}", calc.format(x_curr));
                         }
                     } else {
                         println!("Error: La estimación inicial debe ser un número válido.");
@@ -361,64 +1215,9 @@ pub fn run() {
                 }
             }
 
-            s if s.starts_with("deriv ") => {
-                // Sintaxis: deriv <expr> <x_val> [h]
-                let args: Vec<&str> = s[6..].split_whitespace().collect();
-
-                if args.len() < 2 {
-                    println!("Uso: deriv <expr> <punto_x> [h]");
-                    println!("Ejemplo: deriv x^2 3");
-                } else {
-                    let expr = args[0];
-                    let point_res = calc.evaluate(args[1]);
-
-                    if let Ok(p_complex) = point_res {
-                        let x0 = p_complex.re; // Derivamos respecto a X real
-
-                        // h por defecto = 0.00001 si no se especifica
-                        let h = if args.len() >= 3 {
-                            args[2].parse::<f64>().unwrap_or(1e-5)
-                        } else {
-                            1e-5
-                        };
-
-                        // Guardamos variable x original
-                        let saved_x = calc.vars.get("x").copied();
-
-                        // Calculamos f(x + h)
-                        calc.vars.insert("x".to_string(), num_complex::Complex64::new(x0 + h, 0.0));
-                        let fx_plus = calc.evaluate(expr);
-
-                        // Calculamos f(x - h)
-                        calc.vars.insert("x".to_string(), num_complex::Complex64::new(x0 - h, 0.0));
-                        let fx_minus = calc.evaluate(expr);
-
-                        // Restauramos x
-                        if let Some(old) = saved_x {
-                            calc.vars.insert("x".to_string(), old);
-                        } else {
-                            calc.vars.remove("x");
-                        }
-
-                        match (fx_plus, fx_minus) {
-                            (Ok(y2), Ok(y1)) => {
-                                // Fórmula: (y2 - y1) / 2h
-                                let deriv = (y2 - y1) / (2.0 * h);
-                                calc.last_result = deriv;
-
-                                // Usamos el formateador que ya creaste
-                                let res_str = format_complex(deriv, calc.output_format);
-                                println!("Derivada de '{}' en x={} (h={})", expr, x0, h);
-                                println!("= {}", res_str.bold());
-                            }
-                            (Err(e), _) | (_, Err(e)) => println!("Error evaluando la función: {}", e),
-                        }
-
-                    } else {
-                        println!("Error: El punto de evaluación debe ser un número válido.");
-                    }
-                }
-            }
+            s if s.starts_with("deriv ") => cmd_deriv(&mut calc, &s[6..]),
+            s if s.starts_with("limit ") => cmd_limit(&mut calc, &s[6..]),
+            s if s.starts_with("taylor ") => cmd_taylor(&mut calc, &s[7..]),
 
             s if s.starts_with("integ") => {
                 let args: Vec<&str> = s[6..].split_whitespace().collect();
@@ -479,7 +1278,7 @@ pub fn run() {
                                 let integral = (h / 2.0) * sum;
                                 calc.last_result = num_complex::Complex64::new(integral, 0.0);
                                 println!("Integral definida de '{}' entre {} y {} ({}, n={})", expr, a, b, integral, n);
-                                println!("= {}", integral);
+                                println!("= {}", calc.format_real(integral));
                             }
                         }
                     } else {
@@ -504,7 +1303,7 @@ pub fn run() {
                 else {
                     let v: num_complex::Complex64 = calc.memory_stack.iter().sum();
                     calc.last_result = v;
-                    println!("= {}", v);
+                    println!("= {}", calc.format(v));
                 }
             },
             "avg" => {
@@ -513,19 +1312,39 @@ pub fn run() {
                     let sum: num_complex::Complex64 = calc.memory_stack.iter().sum();
                     let v = sum / (calc.memory_stack.len() as f64);
                     calc.last_result = v;
-                    println!("= {}", v);
+                    println!("= {}", calc.format(v));
                 }
             },
             "min" => {
-                if let Some(m) = calc.memory_stack.iter().min_by(|a, b| a.re.partial_cmp(&b.re).unwrap()) {
-                    println!("= {}", m);
+                if let Some(m) = calc.memory_stack.iter().min_by(|a, b| a.re.total_cmp(&b.re)) {
+                    println!("= {}", calc.format(*m));
                 } else { println!("Pila vacía."); }
             },
             "max" => {
-                if let Some(m) = calc.memory_stack.iter().max_by(|a, b| a.re.partial_cmp(&b.re).unwrap()) {
-                    println!("= {}", m);
+                if let Some(m) = calc.memory_stack.iter().max_by(|a, b| a.re.total_cmp(&b.re)) {
+                    println!("= {}", calc.format(*m));
                 } else { println!("Pila vacía."); }
             },
+            "stats" => match calc.stack_summary() {
+                Ok(st) => print_summary(&st, &calc),
+                Err(e) => println!("{}", e),
+            },
+            "moda" => match calc.stack_summary() {
+                Ok(st) if st.modes.is_empty() => println!("Sin moda: todos los valores son distintos."),
+                Ok(st) => {
+                    calc.last_result = num_complex::Complex64::new(st.modes[0], 0.0);
+                    let list: Vec<String> = st.modes.iter().map(|m| calc.format_real(*m)).collect();
+                    println!("= {}", list.join(", ").bold());
+                }
+                Err(e) => println!("{}", e),
+            },
+            s if STACK_STATS.contains(&s) => match calc.stack_stat(s) {
+                Ok(v) => {
+                    calc.last_result = num_complex::Complex64::new(v, 0.0);
+                    println!("= {}", calc.format_real(v).bold());
+                }
+                Err(e) => println!("Error: {}", e),
+            },
             "author" => {
                 println!("By Oscar Gimenez Blasco.\nhttps://sabbat.cloud\nhttps://github.com/Sabat-cloud");
             }
@@ -533,18 +1352,16 @@ pub fn run() {
                 let target = s[6..].trim();
                 crate::help::show_specific_help(target);
             }
+            s if s.starts_with("ode ") => cmd_ode(&mut calc, &s[4..]),
             s if s.starts_with("plot ") => {
                 calc.plot(&s[5..]);
             }
             s if s.starts_with("push ") => {
-                let rest = s[5..].trim();
-                let cleaned = rest.replace(',', " ");
-                let parts: Vec<&str> = cleaned.split_whitespace().collect();
-                for p in parts {
+                for p in split_values(&s[5..]) {
                     match calc.evaluate(p) {
                         Ok(v) => {
                             calc.memory_stack.push(v);
-                            println!("PUSH -> {} (size={})", v, calc.memory_stack.len());
+                            println!("PUSH -> {} (size={})", calc.format(v), calc.memory_stack.len());
                         }
                         Err(e) => { println!("Error en '{}': {}", p, e); break; }
                     }
@@ -555,19 +1372,16 @@ pub fn run() {
                 if parts.len() == 2 {
                     let var_name = parts[0].trim();
                     let expr = parts[1].trim();
-                    let is_valid_name = !var_name.is_empty()
-                        && var_name.chars().next().unwrap().is_ascii_alphabetic()
-                        && var_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
-                    let reserved = ["pi", "e", "tau", "phi", "c", "i", "ans", "last"];
+                    let is_valid_name = crate::calc::lexer::is_identifier(var_name);
                     if !is_valid_name {
                         println!("Error: '{}' no es un nombre de variable válido.", var_name);
-                    } else if reserved.contains(&var_name) {
+                    } else if crate::calc::lexer::RESERVED_NAMES.contains(&var_name) {
                         println!("Error: '{}' es una constante reservada.", var_name);
                     } else {
                         match calc.evaluate(expr) {
                             Ok(r) => {
-                                calc.vars.insert(var_name.to_string(), r);
-                                println!("{} = {}", var_name, r);
+                                calc.assign(var_name, r);
+                                println!("{} = {}", var_name, calc.format_result(r));
                             }
                             Err(e) => println!("Error al asignar: {}", e),
                         }
@@ -579,8 +1393,19 @@ pub fn run() {
 
             _ => match calc.evaluate(raw) {
                 Ok(res) => {
-                    let output_str = format_complex(res, calc.output_format);
+                    let output_str = calc.format_result(res);
                     println!("= {}", output_str.bold());
+                    if let (Some(w), Some(p)) = (calc.word, calc.word_result) {
+                        for (name, view) in w.views(p) {
+                            println!("  {} {}", name.dimmed(), view);
+                        }
+                    }
+                    // Los enteros no necesitan forma cerrada
+                    if calc.identify && calc.word.is_none() && res.im == 0.0 && res.re.fract() != 0.0
+                        && let Some(f) = identify::identify(res.re, identify::DEFAULT_TOL).first()
+                    {
+                        println!("  {}", closed_form_line(f, identify::DEFAULT_TOL * res.re.abs()));
+                    }
                     if let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(&calc.history_file) {
                         use std::io::Write;
                         let _ = writeln!(file, "{} = {}", raw, output_str);